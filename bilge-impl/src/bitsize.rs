@@ -1,12 +1,24 @@
 mod split;
 
+use itertools::Itertools;
 use proc_macro2::{Ident, TokenStream};
 use proc_macro_error::{abort, abort_call_site};
-use quote::quote;
+use quote::{quote, quote_spanned};
 use split::SplitAttributes;
-use syn::{punctuated::Iter, spanned::Spanned, Fields, Item, ItemEnum, ItemStruct, Type, Variant};
+use syn::{punctuated::Iter, spanned::Spanned, Attribute, Fields, Generics, Item, ItemEnum, ItemStruct, Type, Variant};
 
-use crate::shared::{self, enum_fills_bitsize, is_fallback_attribute, unreachable, BitSize, MAX_ENUM_BIT_SIZE};
+use crate::shared::{
+    self, alias,
+    default_value::is_default_attribute,
+    discriminant_assigner::{self, DiscriminantAssigner},
+    enum_fills_bitsize,
+    field_access::FieldAccess,
+    generics::is_generic_type,
+    is_fallback_attribute, required_value,
+    tag::TagLayout,
+    unreachable, BitOrder, BitsizeArgs, Storage, MAX_ENUM_BIT_SIZE,
+};
+use crate::{default_bits, from_bits};
 
 /// Intermediate Representation, just for bundling these together
 struct ItemIr {
@@ -15,34 +27,34 @@ struct ItemIr {
 }
 
 pub(super) fn bitsize(args: TokenStream, item: TokenStream) -> TokenStream {
-    let (item, declared_bitsize) = parse(item, args);
+    let (item, parsed_args) = parse(item, args.clone());
     let attrs = SplitAttributes::from_item(&item);
     let ir = match item {
         Item::Struct(mut item) => {
             modify_special_field_names(&mut item.fields);
-            analyze_struct(&item.fields);
-            let expanded = generate_struct(&item, declared_bitsize);
+            analyze_struct(&parsed_args, &item.generics, &item.fields);
+            let expanded = generate_struct(&item, &parsed_args);
             ItemIr { expanded }
         }
         Item::Enum(item) => {
-            analyze_enum(declared_bitsize, item.variants.iter());
-            let expanded = generate_enum(&item);
+            let tag_layout = analyze_enum(&parsed_args, &item.generics, &item.attrs, item.variants.iter());
+            let expanded = generate_enum(&item, &parsed_args, tag_layout);
             ItemIr { expanded }
         }
         _ => unreachable(()),
     };
-    generate_common(ir, attrs, declared_bitsize)
+    generate_common(ir, attrs, args)
 }
 
-fn parse(item: TokenStream, args: TokenStream) -> (Item, BitSize) {
+fn parse(item: TokenStream, args: TokenStream) -> (Item, BitsizeArgs) {
     let item = syn::parse2(item).unwrap_or_else(unreachable);
 
     if args.is_empty() {
         abort_call_site!("missing attribute value"; help = "you need to define the size like this: `#[bitsize(32)]`")
     }
 
-    let (declared_bitsize, _arb_int) = shared::bitsize_and_arbitrary_int_from(args);
-    (item, declared_bitsize)
+    let args = shared::parse_bitsize_args(args);
+    (item, args)
 }
 
 fn check_type_is_supported(ty: &Type) {
@@ -66,6 +78,17 @@ fn check_type_is_supported(ty: &Type) {
     }
 }
 
+/// Arrays are converted with `transmute`, which doesn't work if their size depends on a generic parameter.
+fn check_array_is_not_generic(ty: &Type, generics: &Generics) {
+    match ty {
+        Type::Array(array) if is_generic_type(ty, generics) => {
+            abort!(array, "arrays using generic parameters are not supported"; help = "use a tuple or a generic type holding the array instead")
+        }
+        Type::Tuple(tuple) => tuple.elems.iter().for_each(|elem| check_array_is_not_generic(elem, generics)),
+        _ => (),
+    }
+}
+
 /// Allows you to give multiple fields the name `reserved` or `padding`
 /// by numbering them for you.
 fn modify_special_field_names(fields: &mut Fields) {
@@ -90,93 +113,207 @@ fn modify_special_field_names(fields: &mut Fields) {
     }
 }
 
-fn analyze_struct(fields: &Fields) {
-    if fields.is_empty() {
-        abort_call_site!("structs without fields are not supported")
+fn analyze_struct(args: &BitsizeArgs, generics: &Generics, fields: &Fields) {
+    if let Some(lifetime) = generics.lifetimes().next() {
+        abort!(lifetime, "lifetime parameters are not supported"; help = "bitfields only hold their bits, so they can't borrow anything")
     }
 
     // don't move this. we validate all nested field types here as well
     // and later assume this was checked.
     for field in fields {
-        check_type_is_supported(&field.ty)
+        check_type_is_supported(&field.ty);
+        check_array_is_not_generic(&field.ty, generics);
+        // validates `#[read_only]`, `#[write_only]`, `#[w1c]` and `#[w1s]`
+        let access = FieldAccess::of(field);
+        // `new` writes zero to read-only fields, and deserializing writes zero to write-only fields
+        if matches!(access, FieldAccess::ReadOnly | FieldAccess::WriteOnly) && !shared::is_always_filled(&field.ty) {
+            abort!(field.ty, "`#[read_only]` and `#[write_only]` are only supported on `bool` and `uN` fields"; help = "zero may not be a valid value of this type")
+        }
+        if access.is_write_one() {
+            if !shared::is_always_filled(&field.ty) {
+                abort!(field.ty, "`#[w1c]` and `#[w1s]` are only supported on `bool` and `uN` fields")
+            }
+            if Storage::from_bitsize(args.bitsize) == Storage::ByteArray {
+                abort!(field, "`#[w1c]` and `#[w1s]` are only supported in bitfields up to 128 bits")
+            }
+        }
+    }
+
+    let aliases: Vec<_> = fields.iter().filter(|field| alias::is_alias(field)).collect();
+    if !fields.is_empty() && aliases.len() == fields.len() {
+        abort_call_site!("structs need at least one field which isn't an alias"; help = "alias fields only give another view of the other fields' bits")
+    }
+    if let Some(alias) = aliases.first() {
+        if !generics.params.is_empty() {
+            abort!(alias, "alias fields are not supported in generic structs")
+        }
+    }
+    for alias in aliases {
+        if FieldAccess::of(alias).is_write_one() {
+            abort!(alias, "`#[w1c]` and `#[w1s]` are not supported on alias fields"; help = "mark the field these bits belong to instead")
+        }
+        if let Some(attr) = alias.attrs.iter().find(|attr| is_default_attribute(attr)) {
+            abort!(attr, "alias fields can't have a default value"; help = "set the default on the field these bits belong to instead")
+        }
+    }
+
+    for field in fields {
+        if required_value::required_bits(field).is_none() {
+            continue;
+        }
+        if !shared::is_always_filled(&field.ty) {
+            abort!(field.ty, "required values are only supported on `bool` and `uN` fields")
+        }
+        if alias::is_alias(field) {
+            abort!(field, "alias fields can't have a required value"; help = "mark the field these bits belong to instead")
+        }
+        if FieldAccess::of(field) != FieldAccess::ReadWrite {
+            abort!(field, "fields with a required value can't have `#[read_only]`, `#[write_only]`, `#[w1c]` or `#[w1s]`"; help = "these fields already have no safe setter")
+        }
+        if let Some(attr) = field.attrs.iter().find(|attr| is_default_attribute(attr)) {
+            abort!(attr, "fields with a required value can't have a default value"; help = "`DefaultBits` uses the required value")
+        }
+    }
+
+    // these would clash with the generated methods of the same name
+    for ident in fields.iter().filter_map(|field| field.ident.as_ref()) {
+        if ident == "raw" || ident == "from_raw_unchecked" {
+            abort!(ident, "the field name `{}` is already used by bilge", ident; help = "rename this field, the struct's raw value is accessible through `raw()`")
+        }
     }
 }
 
-fn analyze_enum(bitsize: BitSize, variants: Iter<Variant>) {
+fn analyze_enum(args: &BitsizeArgs, generics: &Generics, attrs: &[Attribute], variants: Iter<Variant>) -> Option<TagLayout> {
+    if !generics.params.is_empty() {
+        abort!(generics, "generic enums are not supported")
+    }
+
+    let bitsize = args.bitsize;
     if bitsize > MAX_ENUM_BIT_SIZE {
         abort_call_site!("enum bitsize is limited to {}", MAX_ENUM_BIT_SIZE)
     }
 
-    let variant_count = variants.clone().count();
-    if variant_count == 0 {
+    if bitsize == 0 {
+        abort_call_site!("enums need at least one bit"; help = "zero-width markers can be structs, like `#[bitsize(0)] struct Marker;`")
+    }
+
+    if args.bit_order == BitOrder::Msb0 {
+        abort_call_site!("`msb0` is only applicable to structs"; help = "enums are always stored as a single number, remove `msb0`")
+    }
+
+    if args.sealed {
+        abort_call_site!("`sealed` is only applicable to structs"; help = "enums can't hold invalid values, remove `sealed`")
+    }
+
+    if variants.len() == 0 {
         abort_call_site!("empty enums are not supported");
     }
 
-    let has_fallback = variants.flat_map(|variant| &variant.attrs).any(is_fallback_attribute);
+    // distinct tags can't overflow the enum, since they fit into the tag bits
+    let tag_layout = TagLayout::of(attrs, variants.clone(), bitsize);
 
-    if !has_fallback {
+    let has_fallback = variants.clone().flat_map(|variant| &variant.attrs).any(is_fallback_attribute);
+
+    if !has_fallback && tag_layout.is_none() {
         // this has a side-effect of validating the enum count
-        let _ = enum_fills_bitsize(bitsize, variant_count);
+        let _ = enum_fills_bitsize(bitsize, discriminant_assigner::value_count(variants));
     }
+
+    tag_layout
 }
 
-fn generate_struct(item: &ItemStruct, declared_bitsize: u8) -> TokenStream {
-    let ItemStruct { vis, ident, fields, .. } = item;
-    let declared_bitsize = declared_bitsize as usize;
-
-    let computed_bitsize = fields.iter().fold(quote!(0), |acc, next| {
-        let field_size = shared::generate_type_bitsize(&next.ty);
-        quote!(#acc + #field_size)
-    });
+fn generate_struct(item: &ItemStruct, args: &BitsizeArgs) -> TokenStream {
+    let ItemStruct {
+        vis,
+        ident,
+        fields,
+        generics,
+        ..
+    } = item;
+    let where_clause = &generics.where_clause;
 
     // we could remove this if the whole struct gets passed
     let is_tuple_struct = fields.iter().any(|field| field.ident.is_none());
     let fields_def = if is_tuple_struct {
         let fields = fields.iter();
         quote! {
-            ( #(#fields,)* );
+            ( #(#fields,)* ) #where_clause;
         }
     } else {
         let fields = fields.iter();
         quote! {
-            { #(#fields,)* }
+            #where_clause { #(#fields,)* }
         }
     };
 
+    // with generics, `bitsize_internal` checks the size for each instantiation instead
+    let size_check = if item.generics.params.is_empty() {
+        let checks = shared::generate_size_checks(fields, args);
+        quote!(#( const _: () = #checks; )*)
+    } else {
+        quote!()
+    };
+
+    let required_checks = fields.iter().filter_map(|field| {
+        let bits = required_value::required_bits(field)?;
+        let fits = default_bits::generate_fits_check(&bits, &shared::generate_type_bitsize(&field.ty));
+        // spanned, so the error points to the required value
+        Some(quote_spanned! {bits.span()=>
+            const _: () = assert!(#fits, "required value doesn't fit into the field");
+        })
+    });
+
+    // alias fields reinterpret the bits of other fields, so every field needs to accept any bits
+    let filled_check = fields.iter().any(alias::is_alias).then(|| {
+        let mut assumes = vec![];
+        for field in fields {
+            from_bits::generate_filled_check_for(&field.ty, &mut assumes);
+        }
+        let assumes = assumes.into_iter().unique_by(TokenStream::to_string);
+        quote!(const _: () = { #( #assumes )* };)
+    });
+
     quote! {
-        #vis struct #ident #fields_def
+        #vis struct #ident #generics #fields_def
 
-        // constness: when we get const blocks evaluated at compile time, add a const computed_bitsize
-        const _: () = assert!(
-            (#computed_bitsize) == (#declared_bitsize),
-            concat!("struct size and declared bit size differ: ",
-            // stringify!(#computed_bitsize),
-            " != ",
-            stringify!(#declared_bitsize))
-        );
+        #size_check
+        #( #required_checks )*
+        #filled_check
     }
 }
 
 // attributes are handled in `generate_common`
-fn generate_enum(item: &ItemEnum) -> TokenStream {
+fn generate_enum(item: &ItemEnum, args: &BitsizeArgs, tag_layout: Option<TagLayout>) -> TokenStream {
     let ItemEnum { vis, ident, variants, .. } = item;
+    let size_checks = tag_layout.map(|tag_layout| tag_layout.generate_payload_size_checks(variants.iter()));
+    let size_checks = size_checks.into_iter().flatten();
+    // discriminants like `OP_ADD` or `1 << 4` can only be checked by the compiler
+    let discriminant_checks = match tag_layout {
+        Some(_) => None,
+        None => DiscriminantAssigner::generate_checks(args.bitsize, variants.iter()),
+    };
+    let discriminant_checks = discriminant_checks.map(|checks| quote!(const _: () = #checks;));
     quote! {
         #vis enum #ident {
             #variants
         }
+
+        #( const _: () = #size_checks; )*
+        #discriminant_checks
     }
 }
 
 /// we have _one_ generate_common function, which holds everything that struct and enum have _in common_.
 /// Everything else has its own generate_ functions.
-fn generate_common(ir: ItemIr, attrs: SplitAttributes, declared_bitsize: u8) -> TokenStream {
+fn generate_common(ir: ItemIr, attrs: SplitAttributes, args: TokenStream) -> TokenStream {
     let ItemIr { expanded } = ir;
     let SplitAttributes {
         before_compression,
         after_compression,
     } = attrs;
 
-    let bitsize_internal_attr = quote! {#[::bilge::bitsize_internal(#declared_bitsize)]};
+    // the size and all options are passed on as-is, every `-Bits` derive parses them again
+    let bitsize_internal_attr = quote! {#[::bilge::bitsize_internal(#args)]};
 
     quote! {
         #(#before_compression)*
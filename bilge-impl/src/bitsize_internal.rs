@@ -1,8 +1,24 @@
-use proc_macro2::{Ident, TokenStream};
-use quote::quote;
-use syn::{Attribute, Field, Item, ItemEnum, ItemStruct, Type};
+use proc_macro2::{Ident, Literal, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Attribute, Expr, ExprLit, Field, Fields, Generics, Item, ItemEnum, ItemStruct, Lit, Type, TypeArray};
 
-use crate::shared::{self, unreachable};
+use crate::{
+    shared::{
+        self,
+        alias::{self, is_alias_attribute},
+        bit_range::is_bits_attribute,
+        byte_len,
+        default_value::{is_default_attribute, is_reset_value_attribute},
+        discriminant_assigner,
+        field_access::{is_access_attribute, FieldAccess},
+        field_name,
+        generics::{self, generate_cast, generate_const, is_generic_type},
+        required_value::{self, is_required_value_attribute},
+        tag::{is_tag_attribute, is_tag_bits_attribute},
+        unreachable, valid_bits, BitOrder, BitSize, BitsizeArgs, Storage,
+    },
+    try_from_bits,
+};
 
 pub(crate) mod struct_gen;
 
@@ -10,85 +26,528 @@ pub(crate) mod struct_gen;
 struct ItemIr<'a> {
     attrs: &'a Vec<Attribute>,
     name: &'a Ident,
+    /// with the bounds of generic field types, which are needed by `FIELDS`
+    generics: Generics,
     /// generated item (and setters, getters, constructor, impl Bitsized)
     expanded: TokenStream,
+    /// `FIELDS` of structs, for `impl Bitsized`
+    fields_info: TokenStream,
+    /// the `BitPattern` of `VALID_BITS`, for `impl Bitsized` up to 128 bits
+    valid_bits: TokenStream,
 }
 
 pub(super) fn bitsize_internal(args: TokenStream, item: TokenStream) -> TokenStream {
-    let (item, arb_int) = parse(item, args);
+    let (item, args) = parse(item, args);
     let ir = match item {
         Item::Struct(ref item) => {
-            let expanded = generate_struct(item, &arb_int);
+            let expanded = generate_struct(item, &args);
+            let fields_info = generate_fields_info(&item.fields);
+            let valid_bits = valid_bits::generate_struct_pattern(&item.fields, args.bitsize, args.bit_order);
             let attrs = &item.attrs;
             let name = &item.ident;
-            ItemIr { attrs, name, expanded }
+            let generics = generics::bitfield_generics(&item.generics, &item.fields, quote!());
+            ItemIr {
+                attrs,
+                name,
+                generics,
+                expanded,
+                fields_info,
+                valid_bits,
+            }
         }
         Item::Enum(ref item) => {
-            let expanded = generate_enum(item);
+            let expanded = generate_enum(item, args.bitsize);
+            let valid_bits = valid_bits::generate_enum_pattern(&item.attrs, item.variants.iter(), args.bitsize);
             let attrs = &item.attrs;
             let name = &item.ident;
-            ItemIr { attrs, name, expanded }
+            let generics = item.generics.clone();
+            ItemIr {
+                attrs,
+                name,
+                generics,
+                expanded,
+                fields_info: quote!(),
+                valid_bits,
+            }
         }
         _ => unreachable(()),
     };
-    generate_common(ir, &arb_int)
+    generate_common(ir, args.bitsize, &args.arb_int)
 }
 
-fn parse(item: TokenStream, args: TokenStream) -> (Item, TokenStream) {
+fn parse(item: TokenStream, args: TokenStream) -> (Item, BitsizeArgs) {
     let item = syn::parse2(item).unwrap_or_else(unreachable);
-    let (_declared_bitsize, arb_int) = shared::bitsize_and_arbitrary_int_from(args);
-    (item, arb_int)
+    let args = shared::parse_bitsize_args(args);
+    (item, args)
 }
 
-fn generate_struct(struct_data: &ItemStruct, arb_int: &TokenStream) -> TokenStream {
-    let ItemStruct { vis, ident, fields, .. } = struct_data;
+fn generate_struct(struct_data: &ItemStruct, args: &BitsizeArgs) -> TokenStream {
+    let ItemStruct {
+        vis,
+        ident,
+        fields,
+        generics,
+        ..
+    } = struct_data;
+    let BitsizeArgs {
+        bitsize: declared_bitsize,
+        ref arb_int,
+        bit_order,
+        sealed,
+    } = *args;
+    let storage = Storage::from_bitsize(declared_bitsize);
 
     let mut fieldless_next_int = 0;
-    let mut previous_field_sizes = vec![];
+    // offset is needed for bit-shifting
+    let field_offsets = shared::generate_field_offsets(fields, declared_bitsize, bit_order);
+    // the bits of all `#[w1c]` and `#[w1s]` fields, which can only be `bool` or `uN`
+    let write_one_mask = fields
+        .iter()
+        .zip(&field_offsets)
+        .filter(|(field, _)| FieldAccess::of(field).is_write_one())
+        .map(|(field, field_offset)| {
+            let ty = &field.ty;
+            quote!(((<#ty as Bitsized>::MAX.value() as BaseIntOf<Self>) << (#field_offset)))
+        })
+        .reduce(|acc, next| quote!(#acc | #next));
     let (accessors, (constructor_args, constructor_parts)): (Vec<TokenStream>, (Vec<TokenStream>, Vec<TokenStream>)) = fields
         .iter()
-        .map(|field| {
-            // offset is needed for bit-shifting
-            // struct Example { field1: u8, field2: u4, field3: u4 }
-            // previous_field_sizes = []     -> unwrap_or_else -> field_offset = 0
-            // previous_field_sizes = [8]    -> reduce         -> field_offset = 0 + 8     =  8
-            // previous_field_sizes = [8, 4] -> reduce         -> field_offset = 0 + 8 + 4 = 12
-            let field_offset = previous_field_sizes
-                .iter()
-                .cloned()
-                .reduce(|acc, next| quote!(#acc + #next))
-                .unwrap_or_else(|| quote!(0));
-            let field_size = shared::generate_type_bitsize(&field.ty);
-            previous_field_sizes.push(field_size);
-            generate_field(field, &field_offset, &mut fieldless_next_int)
+        .zip(&field_offsets)
+        .map(|(field, field_offset)| {
+            generate_field(
+                field,
+                field_offset,
+                &mut fieldless_next_int,
+                storage,
+                bit_order,
+                write_one_mask.as_ref(),
+                generics,
+            )
         })
         .unzip();
 
-    let const_ = if cfg!(feature = "nightly") { quote!(const) } else { quote!() };
+    // the size check and accessors need the bounds of generic field types
+    let bitfield_generics = generics::bitfield_generics(generics, fields, quote!());
+
+    let layout_consts = generate_layout_consts(fields, &field_offsets, storage, declared_bitsize, arb_int);
+    let bitfield_impl = generate_bitfield_impl(
+        struct_data,
+        &field_offsets,
+        storage,
+        bit_order,
+        write_one_mask.as_ref(),
+        &bitfield_generics,
+    );
+
+    let const_ = generate_const(generics);
+
+    // like with struct literals, other crates can't call the constructors of non_exhaustive structs,
+    // since their arguments change when fields are added
+    let constructor_vis = if struct_data.attrs.iter().any(shared::is_non_exhaustive_attribute) {
+        quote!(pub(crate))
+    } else {
+        quote!(pub)
+    };
+
+    let constructor_body = match storage {
+        Storage::ArbitraryInt => {
+            // structs without fields have no bits to combine
+            let raw_value = if constructor_parts.is_empty() {
+                quote!(0)
+            } else {
+                quote!(#( #constructor_parts )|*)
+            };
+            quote! {
+                let raw_value = #raw_value;
+                let value = #arb_int::new(raw_value);
+            }
+        }
+        Storage::ByteArray => {
+            let len = byte_len(declared_bitsize);
+            quote! {
+                let mut bytes = [0u8; #len];
+                #( #constructor_parts )*
+                let value = bytes;
+            }
+        }
+    };
+
+    // everything else, including the derives, only accesses `value` through `raw` and `from_raw_unchecked`
+    let (value_ty, raw, from_raw) = if sealed {
+        (
+            quote!(::bilge::Sealed<#arb_int, Self>),
+            quote!(self.value.get()),
+            quote!(::bilge::Sealed::new_unchecked(value)),
+        )
+    } else {
+        (quote!(#arb_int), quote!(self.value), quote!(value))
+    };
+
+    // type parameters are only used by the fields, which are replaced by `value`
+    let type_params: Vec<&Ident> = generics.type_params().map(|param| &param.ident).collect();
+    let (phantom_field, phantom_init) = if type_params.is_empty() {
+        (quote!(), quote!())
+    } else {
+        (
+            quote!(_phantom: ::core::marker::PhantomData<(#( #type_params, )*)>,),
+            quote!(_phantom: ::core::marker::PhantomData,),
+        )
+    };
+
+    // with generics, the size can only be checked for each instantiation.
+    // since every value is created by `from_raw_unchecked`, that's where this is evaluated.
+    let (size_check, use_size_check) = if generics.params.is_empty() {
+        (quote!(), quote!())
+    } else {
+        let checks = shared::generate_size_checks(fields, args);
+        (
+            quote! {
+                const BITSIZE_CHECK: () = { #( #checks; )* };
+            },
+            quote! {
+                #[allow(clippy::let_unit_value)]
+                let () = Self::BITSIZE_CHECK;
+            },
+        )
+    };
+
+    let where_clause = &generics.where_clause;
+    let (impl_generics, ty_generics, bitfield_where_clause) = bitfield_generics.split_for_impl();
+
+    // `new_preserving` starts at the base value and sets every argument of `new` with `with_<field>`
+    let with_args = fields
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| is_constructor_arg(field))
+        .map(|(i, field)| {
+            let name = field.ident.clone().unwrap_or_else(|| format_ident!("val_{}", i));
+            let with_name = format_ident!("with_{}", name);
+            quote!(.#with_name(#name))
+        });
+    // setters write zero to `#[w1c]` and `#[w1s]` fields, but without any arguments, nothing would
+    let preserving_base = match write_one_mask {
+        Some(_) => quote!(self.write_value()),
+        None => quote!(self),
+    };
+
+    let write_value = write_one_mask.map(|write_one_mask| {
+        quote! {
+            /// Returns this value with all `#[w1c]` and `#[w1s]` fields set to zero,
+            /// so writing it to a register doesn't clear or set them.
+            #[allow(dead_code, clippy::type_complexity, unused_parens)]
+            #vis #const_ fn write_value(&self) -> Self {
+                type ArbIntOf<T> = <T as Bitsized>::ArbitraryInt;
+                type BaseIntOf<T> = <ArbIntOf<T> as ::bilge::BaseInt>::UnderlyingType;
+
+                let value: BaseIntOf<Self> = self.raw().value() & !(#write_one_mask);
+                // W1C and W1S fields are `bool` or `uN`, so zero is valid for them
+                unsafe { Self::from_raw_unchecked(<ArbIntOf<Self>>::new(value)) }
+            }
+        }
+    });
 
     quote! {
-        #vis struct #ident {
+        #vis struct #ident #generics #where_clause {
             /// WARNING: modifying this value directly can break invariants
-            value: #arb_int,
+            value: #value_ty,
+            #phantom_field
         }
-        impl #ident {
+        impl #impl_generics #ident #ty_generics #bitfield_where_clause {
+            #size_check
+
+            #( #layout_consts )*
+
+            /// Returns all bits of this bitfield.
+            #[allow(dead_code)]
+            #vis #const_ fn raw(&self) -> #arb_int {
+                #raw
+            }
+
+            /// Creates this bitfield from its bits, without checking them.
+            ///
+            /// # Safety
+            ///
+            /// `value` needs to be a valid bit pattern for this bitfield, e.g. one accepted by `TryFrom`.
+            /// Otherwise, the getters of this bitfield can panic.
+            #[allow(dead_code)]
+            #vis #const_ unsafe fn from_raw_unchecked(value: #arb_int) -> Self {
+                #use_size_check
+                Self {
+                    value: #from_raw,
+                    #phantom_init
+                }
+            }
+
             // #[inline]
             #[allow(clippy::too_many_arguments, clippy::type_complexity, missing_docs, unused_parens)]
-            pub #const_ fn new(#( #constructor_args )*) -> Self {
+            #constructor_vis #const_ fn new(#( #constructor_args )*) -> Self {
                 type ArbIntOf<T> = <T as Bitsized>::ArbitraryInt;
-                type BaseIntOf<T> = <ArbIntOf<T> as Number>::UnderlyingType;
+                type BaseIntOf<T> = <ArbIntOf<T> as ::bilge::BaseInt>::UnderlyingType;
 
-                let mut offset = 0;
-                let raw_value = #( #constructor_parts )|*;
-                let value = #arb_int::new(raw_value);
-                Self { value }
+                #constructor_body
+                // all fields were given as valid values
+                unsafe { Self::from_raw_unchecked(value) }
+            }
+
+            /// Like `new`, but the bits which aren't arguments of `new` are copied from `self`,
+            /// e.g. reserved fields of a value read from a register: `Reg::new_preserving(read, ..)`.
+            #[allow(dead_code, clippy::too_many_arguments, clippy::type_complexity, unused_parens)]
+            #constructor_vis #const_ fn new_preserving(self, #( #constructor_args )*) -> Self {
+                #preserving_base #( #with_args )*
             }
+
+            #write_value
+
             #( #accessors )*
         }
+        #bitfield_impl
+    }
+}
+
+/// Whether this field was called `reserved` or `padding`, which `bitsize` renamed to `reserved_i` or `padding_i`.
+fn is_reserved(name: &str) -> bool {
+    name.contains("reserved_") || name.contains("padding_")
+}
+
+/// Whether this field is an argument of `new`, see [`generate_field`].
+fn is_constructor_arg(field: &Field) -> bool {
+    let is_reserved = matches!(&field.ident, Some(ident) if is_reserved(&ident.to_string()));
+    !is_reserved && FieldAccess::of(field).is_writable() && !alias::is_alias(field) && required_value::required_bits(field).is_none()
+}
+
+/// `const FIELDS` of `impl Bitsized`, using the layout constants.
+fn generate_fields_info(fields: &syn::Fields) -> TokenStream {
+    let infos = fields.iter().enumerate().map(|(i, field)| {
+        let name = field_name(field, i);
+        let upper = name.to_uppercase();
+        let offset_name = format_ident!("{}_OFFSET", upper);
+        let width_name = format_ident!("{}_WIDTH", upper);
+        let ty = &field.ty;
+        let type_name = type_name(ty);
+        let reserved = is_reserved(&name);
+        let alias = alias::is_alias(field);
+        let access = match FieldAccess::of(field) {
+            FieldAccess::ReadWrite => quote!(ReadWrite),
+            FieldAccess::ReadOnly => quote!(ReadOnly),
+            FieldAccess::WriteOnly => quote!(WriteOnly),
+            FieldAccess::WriteOneToClear => quote!(WriteOneToClear),
+            FieldAccess::WriteOneToSet => quote!(WriteOneToSet),
+        };
+        // nested structs have their own fields, everything else (including tuples and arrays) has none
+        let nested = match ty {
+            Type::Path(_) => quote!(<#ty as ::bilge::Bitsized>::FIELDS),
+            _ => quote!(&[]),
+        };
+        quote! {
+            ::bilge::FieldInfo::new(
+                #name,
+                Self::#offset_name as usize,
+                Self::#width_name as usize,
+                #type_name,
+                #reserved,
+                #alias,
+                ::bilge::Access::#access,
+                #nested,
+            )
+        }
+    });
+    quote! {
+        const FIELDS: &'static [::bilge::FieldInfo] = &[ #( #infos ),* ];
+    }
+}
+
+/// The type as written in the struct, e.g. `[u4; 4]` instead of the tokens' `[u4 ; 4]`.
+fn type_name(ty: &Type) -> String {
+    let tokens: String = quote!(#ty).to_string().split_whitespace().collect();
+    tokens.replace(';', "; ").replace(',', ", ")
+}
+
+/// `impl Bitfield`, which gets and sets fields by name.
+///
+/// Setting a field writes its bits into a copy of the struct, which is then validated like in `TryFrom`.
+fn generate_bitfield_impl(
+    struct_data: &ItemStruct, field_offsets: &[TokenStream], storage: Storage, bit_order: BitOrder, write_one_mask: Option<&TokenStream>,
+    bitfield_generics: &Generics,
+) -> TokenStream {
+    let ItemStruct { ident, fields, .. } = struct_data;
+    let (get_arms, set_arms): (Vec<TokenStream>, Vec<TokenStream>) = fields
+        .iter()
+        .zip(field_offsets)
+        .enumerate()
+        .map(|(i, (field, offset))| {
+            let name = field_name(field, i);
+            let upper = name.to_uppercase();
+            let offset_name = format_ident!("{}_OFFSET", upper);
+            let width_name = format_ident!("{}_WIDTH", upper);
+            let mask_name = format_ident!("{}_MASK", upper);
+            let access = FieldAccess::of(field);
+
+            let (get_value, set_value) = match storage {
+                Storage::ArbitraryInt => {
+                    let mask_write_one = write_one_mask.map(|write_one_mask| quote!(& !(#write_one_mask)));
+                    (
+                        quote!(Ok((self.raw().value() & Self::#mask_name).wrapping_shr(Self::#offset_name as u32) as u128)),
+                        quote! {
+                            // write zero to all W1C and W1S fields, like the setters do
+                            let others_values: BaseIntOf<Self> = self.raw().value() & !Self::#mask_name #mask_write_one;
+                            let value = <ArbIntOf<Self>>::new(others_values | (value as BaseIntOf<Self>).wrapping_shl(Self::#offset_name as u32));
+                        },
+                    )
+                }
+                Storage::ByteArray => (
+                    quote! {
+                        if Self::#width_name > 128 {
+                            Err(::bilge::give_me_error())
+                        } else {
+                            Ok(::bilge::read_bits(&self.raw(), Self::#offset_name, Self::#width_name))
+                        }
+                    },
+                    quote! {
+                        let value = ::bilge::write_bits(self.raw(), Self::#offset_name, Self::#width_name, value);
+                    },
+                ),
+            };
+
+            let get_arm = if access.is_readable() {
+                quote! {
+                    #name => #get_value,
+                }
+            } else {
+                quote! {
+                    #name => Err(::bilge::give_me_error()),
+                }
+            };
+            let is_required = required_value::required_bits(field).is_some();
+            let set_arm = if is_reserved(&name) || is_required || !(access.is_writable() || access.is_write_one()) {
+                quote! {
+                    #name => Err(::bilge::give_me_error()),
+                }
+            } else {
+                let check = try_from_bits::generate_field_check(&field.ty, &name, offset, storage, bit_order, bitfield_generics);
+                quote! {
+                    #name => {
+                        // the value needs to fit into the field, without touching the others
+                        if Self::#width_name as usize > 128 || value.checked_shr(Self::#width_name as u32).unwrap_or(0) != 0 {
+                            return Err(::bilge::give_me_error());
+                        }
+                        #set_value
+                        // only this field changed, so it's the only one needing validation
+                        #check?;
+                        *self = unsafe { Self::from_raw_unchecked(value) };
+                        Ok(())
+                    }
+                }
+            };
+            (get_arm, set_arm)
+        })
+        .unzip();
+
+    let (impl_generics, ty_generics, where_clause) = bitfield_generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::bilge::Bitfield for #ident #ty_generics #where_clause {
+            #[allow(clippy::type_complexity, unused_parens)]
+            fn get_raw(&self, name: &str) -> ::core::result::Result<u128, ::bilge::BitsError> {
+                match name {
+                    #( #get_arms )*
+                    _ => Err(::bilge::give_me_error()),
+                }
+            }
+
+            #[allow(clippy::type_complexity, unused_parens, unused_variables)]
+            fn set_raw(&mut self, name: &str, value: u128) -> ::core::result::Result<(), ::bilge::BitsError> {
+                type ArbIntOf<T> = <T as Bitsized>::ArbitraryInt;
+                type BaseIntOf<T> = <ArbIntOf<T> as ::bilge::BaseInt>::UnderlyingType;
+
+                match name {
+                    #( #set_arms )*
+                    _ => Err(::bilge::give_me_error()),
+                }
+            }
+        }
     }
 }
 
-fn generate_field(field: &Field, field_offset: &TokenStream, fieldless_next_int: &mut usize) -> (TokenStream, (TokenStream, TokenStream)) {
+/// `<FIELD>_OFFSET`, `<FIELD>_WIDTH` and `<FIELD>_MASK` for every field, e.g. for building masks in DMA code or assembly.
+///
+/// With `Storage::ArbitraryInt`, these are typed in the underlying integer. Byte arrays use `usize` and a `[u8; N]` mask.
+fn generate_layout_consts(
+    fields: &syn::Fields, field_offsets: &[TokenStream], storage: Storage, declared_bitsize: BitSize, arb_int: &TokenStream,
+) -> Vec<TokenStream> {
+    fields
+        .iter()
+        .zip(field_offsets)
+        .enumerate()
+        .map(|(i, (field, offset))| {
+            let vis = &field.vis;
+            let name = field_name(field, i);
+            let upper = name.to_uppercase();
+            let offset_name = format_ident!("{}_OFFSET", upper);
+            let width_name = format_ident!("{}_WIDTH", upper);
+            let mask_name = format_ident!("{}_MASK", upper);
+            let width = shared::generate_type_bitsize(&field.ty);
+            let offset_doc = format!("Position of the least significant bit of `{name}`.");
+            let width_doc = format!("Number of bits of `{name}`.");
+            let mask_doc = format!("All bits of `{name}`, in place.");
+
+            let (int_ty, offset, width, mask) = match storage {
+                Storage::ArbitraryInt => {
+                    let base_int = quote!(<#arb_int as ::bilge::BaseInt>::UnderlyingType);
+                    // zero-width fields may sit right after the last bit, where shifting would overflow
+                    let mask = quote! {
+                        if Self::#width_name == 0 {
+                            0
+                        } else {
+                            (<#base_int as ::bilge::arbitrary_int::Number>::MAX
+                                >> (<#base_int as ::bilge::arbitrary_int::Number>::BITS - Self::#width_name as usize))
+                                << Self::#offset_name
+                        }
+                    };
+                    (
+                        base_int.clone(),
+                        quote!((#offset) as #base_int),
+                        quote!((#width) as #base_int),
+                        quote!(const #mask_name: #base_int = #mask;),
+                    )
+                }
+                Storage::ByteArray => {
+                    let len = byte_len(declared_bitsize);
+                    let mask = quote! {
+                        const #mask_name: [u8; #len] = {
+                            let mut mask = [0u8; #len];
+                            let mut written = 0;
+                            // `write_bits` writes at most 128 bits at once
+                            while written < Self::#width_name {
+                                let remaining = Self::#width_name - written;
+                                let width = if remaining < 128 { remaining } else { 128 };
+                                mask = ::bilge::write_bits(mask, Self::#offset_name + written, width, u128::MAX);
+                                written += width;
+                            }
+                            mask
+                        };
+                    };
+                    (quote!(usize), quote!(#offset), quote!(#width), mask)
+                }
+            };
+
+            quote! {
+                #[doc = #offset_doc]
+                #[allow(dead_code)]
+                #vis const #offset_name: #int_ty = #offset;
+                #[doc = #width_doc]
+                #[allow(dead_code)]
+                #vis const #width_name: #int_ty = #width;
+                #[doc = #mask_doc]
+                #[allow(dead_code)]
+                #vis #mask
+            }
+        })
+        .collect()
+}
+
+fn generate_field(
+    field: &Field, field_offset: &TokenStream, fieldless_next_int: &mut usize, storage: Storage, bit_order: BitOrder,
+    write_one_mask: Option<&TokenStream>, generics: &Generics,
+) -> (TokenStream, (TokenStream, TokenStream)) {
     let Field { ident, ty, .. } = field;
     let name = if let Some(ident) = ident {
         ident.clone()
@@ -98,25 +557,68 @@ fn generate_field(field: &Field, field_offset: &TokenStream, fieldless_next_int:
         syn::parse_str(&name).unwrap_or_else(unreachable)
     };
 
-    // skip reserved fields in constructors and setters
-    let name_str = name.to_string();
-    if name_str.contains("reserved_") || name_str.contains("padding_") {
-        // needed for `DebugBits`
-        let getter = generate_getter(field, field_offset, &name);
-        let size = shared::generate_type_bitsize(ty);
-        let accessors = quote!(#getter);
+    // skip reserved and read-only fields in constructors and setters
+    let is_reserved = is_reserved(&name.to_string());
+    let access = FieldAccess::of(field);
+
+    // reserved fields still get a getter, which is needed for `DebugBits`
+    let getter = if access.is_readable() {
+        generate_getter(field, field_offset, &name, storage, bit_order, generics)
+    } else {
+        quote!()
+    };
+
+    if let Some(bits) = required_value::required_bits(field) {
+        // like a reserved field, but `new` writes the required bits
+        let constructor_part = match storage {
+            // zero-width fields can be at the very end, where `<<` would overflow
+            Storage::ArbitraryInt => quote!((((#bits) as BaseIntOf<Self>).wrapping_shl((#field_offset) as u32))),
+            Storage::ByteArray => {
+                let width = shared::generate_type_bitsize(ty);
+                quote! {
+                    bytes = ::bilge::write_bits(bytes, #field_offset, #width, (#bits) as u128);
+                }
+            }
+        };
+        let reserved_setter = generate_reserved_setter(field, field_offset, &name, storage, bit_order, write_one_mask, generics);
+        let accessors = quote! {
+            #getter
+            #reserved_setter
+        };
+        return (accessors, (quote!(), constructor_part));
+    }
+
+    if is_reserved || !access.is_writable() {
+        let setter = if access.is_write_one() {
+            generate_write_one(field, field_offset, &name, access, bit_order, write_one_mask, generics)
+        } else if is_reserved && access.is_writable() {
+            generate_reserved_setter(field, field_offset, &name, storage, bit_order, write_one_mask, generics)
+        } else {
+            quote!()
+        };
+        let accessors = quote! {
+            #getter
+            #setter
+        };
         let constructor_arg = quote!();
-        let constructor_part = quote! { {
-            // we still need to shift by the element's size
-            offset += #size;
-            0
-        } };
+        // with `Storage::ByteArray`, the bytes are already zeroed
+        let constructor_part = match storage {
+            Storage::ArbitraryInt => quote!(0),
+            Storage::ByteArray => quote!(),
+        };
         return (accessors, (constructor_arg, constructor_part));
     }
 
-    let getter = generate_getter(field, field_offset, &name);
-    let setter = generate_setter(field, field_offset, &name);
-    let (constructor_arg, constructor_part) = generate_constructor_stuff(ty, &name);
+    let setter = generate_setter(field, field_offset, &name, storage, bit_order, write_one_mask, generics);
+    // alias fields only give another view of bits, which are already set by the other arguments
+    let (constructor_arg, constructor_part) = if alias::is_alias(field) {
+        match storage {
+            Storage::ArbitraryInt => (quote!(), quote!(0)),
+            Storage::ByteArray => (quote!(), quote!()),
+        }
+    } else {
+        generate_constructor_stuff(ty, field_offset, &name, storage, bit_order, generics)
+    };
 
     let accessors = quote! {
         #getter
@@ -126,26 +628,96 @@ fn generate_field(field: &Field, field_offset: &TokenStream, fieldless_next_int:
     (accessors, (constructor_arg, constructor_part))
 }
 
-fn generate_getter(field: &Field, offset: &TokenStream, name: &Ident) -> TokenStream {
-    let Field { attrs, vis, ty, .. } = field;
+fn generate_getter(field: &Field, offset: &TokenStream, name: &Ident, storage: Storage, bit_order: BitOrder, generics: &Generics) -> TokenStream {
+    let Field { vis, ty, .. } = field;
+    let attrs = accessor_attrs(field);
 
-    let getter_value = struct_gen::generate_getter_value(ty, offset, false);
+    let getter_value = struct_gen::generate_getter_value(ty, offset, false, storage, bit_order, generics);
 
-    let const_ = if cfg!(feature = "nightly") { quote!(const) } else { quote!() };
+    let const_ = generate_const(generics);
 
     let array_at = if let Type::Array(array) = ty {
-        let elem_ty = &array.elem;
-        let len_expr = &array.len;
-        let name: Ident = syn::parse_str(&format!("{name}_at")).unwrap_or_else(unreachable);
-        let getter_value = struct_gen::generate_getter_value(elem_ty, offset, true);
+        let at_name = format_ident!("{}_at", name);
+        let at_elem_ty = &array.elem;
+        let at_value = struct_gen::generate_getter_value(at_elem_ty, offset, true, storage, bit_order, generics);
+        let at_len_expr = &array.len;
+        let at_reverse_index = generate_reverse_index(&quote!(#at_len_expr), bit_order);
+
+        // [[T; N1]; N2] -> (N1*N2, T)
+        let (len_expr, elem_ty) = struct_gen::length_and_type_of_nested_array(array);
+        let iter_name = format_ident!("{}_iter", name);
+        let try_iter_name = format_ident!("try_{}_iter", name);
+        let getter_value = struct_gen::generate_getter_value(&elem_ty, offset, true, storage, bit_order, generics);
+        let reverse_index = generate_reverse_index(&len_expr, bit_order);
+
+        let element_at = generate_element_indices(array).map(|(indices, flat_index)| {
+            let element_at_name = format_ident!("{}_element_at", name);
+            quote! {
+                /// Reads a single element of the nested arrays, with one index per level.
+                #(#attrs)*
+                #[allow(clippy::type_complexity, clippy::unused_unit, unused_parens)]
+                #vis #const_ fn #element_at_name(&self, #( #indices: usize ),*) -> #elem_ty {
+                    #flat_index
+                    #reverse_index
+                    #getter_value
+                }
+            }
+        });
+
+        // fallible elements can't be read from unchecked bits, so the bits are validated first
+        let try_iter = (!shared::is_always_filled(&elem_ty) && !shared::is_signed_primitive(&elem_ty)).then(|| {
+            let size = shared::generate_type_bitsize(&elem_ty);
+            let name = name.to_string();
+            let lengths = struct_gen::nested_array_lengths(array);
+            let path = [quote!(::bilge::PathSegment::Field(#name))]
+                .into_iter()
+                .chain((0..lengths.len()).map(|dim| quote!(::bilge::PathSegment::nested_index(flat_index, &[#( #lengths ),*], #dim))))
+                .collect::<Vec<_>>();
+            let check = try_from_bits::generate_check(&elem_ty, &path, &quote!((#offset) + (#size) * index), storage, bit_order, generics);
+            quote! {
+                /// Like the iterator above, but validates each element's bits first,
+                /// e.g. for bitfields created with `from_raw_unchecked`.
+                #(#attrs)*
+                #[allow(clippy::type_complexity, unused_parens)]
+                #vis fn #try_iter_name(&self) -> impl ::core::iter::ExactSizeIterator<Item = ::core::result::Result<#elem_ty, ::bilge::BitsError>> + '_ {
+                    (0..#len_expr).map(move |index| {
+                        // the path uses the index before reversing
+                        let flat_index = index;
+                        #reverse_index
+                        type ArbIntOf<T> = <T as Bitsized>::ArbitraryInt;
+                        type BaseIntOf<T> = <ArbIntOf<T> as ::bilge::BaseInt>::UnderlyingType;
+                        let value = self.raw();
+                        #check?;
+                        Ok({ #getter_value })
+                    })
+                }
+            }
+        });
+
         quote! {
             // #[inline]
             #(#attrs)*
-            #[allow(clippy::type_complexity, unused_parens)]
-            #vis #const_ fn #name(&self, index: usize) -> #elem_ty {
-                ::core::assert!(index < #len_expr);
-                #getter_value
+            #[allow(clippy::type_complexity, clippy::unused_unit, unused_parens)]
+            #vis #const_ fn #at_name(&self, index: usize) -> #at_elem_ty {
+                ::core::assert!(index < #at_len_expr);
+                #at_reverse_index
+                #at_value
             }
+
+            #element_at
+
+            /// Iterates over the elements, reading each one when it's needed.
+            /// Nested arrays are flattened, so `[[T; N1]; N2]` yields `N1 * N2` elements of `T`.
+            #(#attrs)*
+            #[allow(clippy::type_complexity, clippy::unused_unit, unused_parens)]
+            #vis fn #iter_name(&self) -> impl ::core::iter::ExactSizeIterator<Item = #elem_ty> + '_ {
+                (0..#len_expr).map(move |index| {
+                    #reverse_index
+                    #getter_value
+                })
+            }
+
+            #try_iter
         }
     } else {
         quote!()
@@ -154,7 +726,7 @@ fn generate_getter(field: &Field, offset: &TokenStream, name: &Ident) -> TokenSt
     quote! {
         // #[inline]
         #(#attrs)*
-        #[allow(clippy::type_complexity, unused_parens)]
+        #[allow(clippy::type_complexity, clippy::unused_unit, unused_parens)]
         #vis #const_ fn #name(&self) -> #ty {
             #getter_value
         }
@@ -163,27 +735,100 @@ fn generate_getter(field: &Field, offset: &TokenStream, name: &Ident) -> TokenSt
     }
 }
 
-fn generate_setter(field: &Field, offset: &TokenStream, name: &Ident) -> TokenStream {
-    let Field { attrs, vis, ty, .. } = field;
-    let setter_value = struct_gen::generate_setter_value(ty, offset, false);
+/// Reserved fields and fields with a required value only get an `unsafe` setter, so their bits aren't changed by accident.
+fn generate_reserved_setter(
+    field: &Field, offset: &TokenStream, name: &Ident, storage: Storage, bit_order: BitOrder, write_one_mask: Option<&TokenStream>,
+    generics: &Generics,
+) -> TokenStream {
+    let Field { vis, ty, .. } = field;
+    let attrs = accessor_attrs(field);
+    let setter_value = struct_gen::generate_setter_value(ty, offset, false, storage, bit_order, write_one_mask, generics);
+    let name = format_ident!("set_{}", name);
+    let const_ = generate_const(generics);
+
+    quote! {
+        #(#attrs)*
+        ///
+        /// This field has no safe setter, since it's reserved or needs a certain value.
+        ///
+        /// # Safety
+        ///
+        /// Hardware usually expects reserved bits to keep their value. With a required value like `#[reserved(0)]`,
+        /// writing anything else leaves this bitfield with bits `TryFrom` would reject.
+        #[allow(dead_code, clippy::type_complexity, unused_parens)]
+        #vis #const_ unsafe fn #name(&mut self, value: #ty) {
+            #setter_value
+        }
+    }
+}
+
+fn generate_setter(
+    field: &Field, offset: &TokenStream, name: &Ident, storage: Storage, bit_order: BitOrder, write_one_mask: Option<&TokenStream>,
+    generics: &Generics,
+) -> TokenStream {
+    let Field { vis, ty, .. } = field;
+    let attrs = accessor_attrs(field);
+    let setter_value = struct_gen::generate_setter_value(ty, offset, false, storage, bit_order, write_one_mask, generics);
 
-    let name: Ident = syn::parse_str(&format!("set_{name}")).unwrap_or_else(unreachable);
+    let with_name = format_ident!("with_{}", name);
+    let name = format_ident!("set_{}", name);
 
-    let const_ = if cfg!(feature = "nightly") { quote!(const) } else { quote!() };
+    let const_ = generate_const(generics);
 
     let array_at = if let Type::Array(array) = ty {
         let elem_ty = &array.elem;
         let len_expr = &array.len;
-        let name: Ident = syn::parse_str(&format!("{name}_at")).unwrap_or_else(unreachable);
-        let setter_value = struct_gen::generate_setter_value(elem_ty, offset, true);
+        let at_with_name = format_ident!("{}_at", with_name);
+        let at_name = format_ident!("{}_at", name);
+        let setter_value = struct_gen::generate_setter_value(elem_ty, offset, true, storage, bit_order, write_one_mask, generics);
+        let reverse_index = generate_reverse_index(&quote!(#len_expr), bit_order);
+
+        let element_at = generate_element_indices(array).map(|(indices, flat_index)| {
+            let (len_expr, elem_ty) = struct_gen::length_and_type_of_nested_array(array);
+            let with_name = format_ident!("{}_element_at", with_name);
+            let name = format_ident!("{}_element_at", name);
+            let setter_value = struct_gen::generate_setter_value(&elem_ty, offset, true, storage, bit_order, write_one_mask, generics);
+            let reverse_index = generate_reverse_index(&len_expr, bit_order);
+            quote! {
+                /// Writes a single element of the nested arrays, with one index per level.
+                #(#attrs)*
+                #[allow(clippy::type_complexity, unused_parens)]
+                #vis #const_ fn #name(&mut self, #( #indices: usize, )* value: #elem_ty) {
+                    #flat_index
+                    #reverse_index
+                    #setter_value
+                }
+
+                #(#attrs)*
+                #[must_use]
+                #[allow(clippy::type_complexity, unused_parens)]
+                #vis #const_ fn #with_name(mut self, #( #indices: usize, )* value: #elem_ty) -> Self {
+                    self.#name(#( #indices, )* value);
+                    self
+                }
+            }
+        });
+
         quote! {
             // #[inline]
             #(#attrs)*
             #[allow(clippy::type_complexity, unused_parens)]
-            #vis #const_ fn #name(&mut self, index: usize, value: #elem_ty) {
+            #vis #const_ fn #at_name(&mut self, index: usize, value: #elem_ty) {
                 ::core::assert!(index < #len_expr);
+                #reverse_index
                 #setter_value
             }
+
+            // #[inline]
+            #(#attrs)*
+            #[must_use]
+            #[allow(clippy::type_complexity, unused_parens)]
+            #vis #const_ fn #at_with_name(mut self, index: usize, value: #elem_ty) -> Self {
+                self.#at_name(index, value);
+                self
+            }
+
+            #element_at
         }
     } else {
         quote!()
@@ -197,39 +842,228 @@ fn generate_setter(field: &Field, offset: &TokenStream, name: &Ident) -> TokenSt
             #setter_value
         }
 
+        // chainable version of the setter, e.g. `Struct::default().with_field1(value)`
+        #(#attrs)*
+        #[must_use]
+        #[allow(clippy::type_complexity, unused_parens)]
+        #vis #const_ fn #with_name(mut self, value: #ty) -> Self {
+            self.#name(value);
+            self
+        }
+
         #array_at
     }
 }
 
-fn generate_constructor_stuff(ty: &Type, name: &Ident) -> (TokenStream, TokenStream) {
+/// `clear_<field>()` for `#[w1c]` and `set_<field>()` for `#[w1s]` fields.
+///
+/// These write ones to the field and zero to all other W1C and W1S fields, so only this field is cleared or set
+/// when writing the struct to a register.
+fn generate_write_one(
+    field: &Field, offset: &TokenStream, name: &Ident, access: FieldAccess, bit_order: BitOrder, write_one_mask: Option<&TokenStream>,
+    generics: &Generics,
+) -> TokenStream {
+    let Field { vis, ty, .. } = field;
+    let attrs = accessor_attrs(field);
+    // `#[w1c]` and `#[w1s]` are rejected for `Storage::ByteArray`
+    let setter_value = struct_gen::generate_setter_value(ty, offset, false, Storage::ArbitraryInt, bit_order, write_one_mask, generics);
+
+    let name = match access {
+        FieldAccess::WriteOneToClear => format_ident!("clear_{}", name),
+        _ => format_ident!("set_{}", name),
+    };
+    let all_ones = if matches!(shared::last_ident_of_path(ty), Some(ident) if ident == "bool") {
+        quote!(true)
+    } else {
+        quote!(<#ty as Bitsized>::MAX)
+    };
+
+    let const_ = generate_const(generics);
+
+    quote! {
+        // #[inline]
+        #(#attrs)*
+        #[allow(clippy::type_complexity, unused_parens)]
+        #vis #const_ fn #name(&mut self) {
+            let value = #all_ones;
+            #setter_value
+        }
+    }
+}
+
+/// Field attributes like doc comments are copied onto the accessors, besides the ones only meant for bilge.
+fn accessor_attrs(field: &Field) -> Vec<&Attribute> {
+    field
+        .attrs
+        .iter()
+        .filter(|attr| {
+            !is_bits_attribute(attr)
+                && !is_access_attribute(attr)
+                && !is_default_attribute(attr)
+                && !is_alias_attribute(attr)
+                && !is_required_value_attribute(attr)
+        })
+        .collect()
+}
+
+/// For nested arrays, the arguments of `<field>_element_at`, one index per level,
+/// and the checks combining them into `index`, the position of the element in the flattened array.
+fn generate_element_indices(array: &TypeArray) -> Option<(Vec<Ident>, TokenStream)> {
+    let lengths = struct_gen::nested_array_lengths(array);
+    if lengths.len() == 1 {
+        return None;
+    }
+    let indices: Vec<Ident> = (0..lengths.len()).map(|dim| format_ident!("index_{}", dim)).collect();
+    // `[i][j]` of `[[T; N1]; N2]` is at `i * N1 + j` of `[T; N2 * N1]`
+    let first = &indices[0];
+    let index = indices
+        .iter()
+        .zip(&lengths)
+        .skip(1)
+        .fold(quote!(#first), |acc, (index, len_expr)| quote!((#acc) * (#len_expr) + #index));
+    let checks = quote!(#( ::core::assert!(#indices < #lengths); )*);
+    Some((
+        indices,
+        quote! {
+            #checks
+            let index = #index;
+        },
+    ))
+}
+
+/// With `BitOrder::Msb0`, the first array element is stored at the highest bits.
+fn generate_reverse_index(len_expr: &TokenStream, bit_order: BitOrder) -> TokenStream {
+    match bit_order {
+        BitOrder::Lsb0 => quote!(),
+        BitOrder::Msb0 => quote! {
+            let index = #len_expr - 1 - index;
+        },
+    }
+}
+
+fn generate_constructor_stuff(
+    ty: &Type, offset: &TokenStream, name: &Ident, storage: Storage, bit_order: BitOrder, generics: &Generics,
+) -> (TokenStream, TokenStream) {
     let constructor_arg = quote! {
         #name: #ty,
     };
-    let constructor_part = struct_gen::generate_constructor_part(ty, name);
+    let constructor_part = struct_gen::generate_constructor_part(ty, offset, name, storage, bit_order, generics);
     (constructor_arg, constructor_part)
 }
 
-fn generate_enum(enum_data: &ItemEnum) -> TokenStream {
-    let ItemEnum { vis, ident, variants, .. } = enum_data;
+fn generate_enum(enum_data: &ItemEnum, bitsize: BitSize) -> TokenStream {
+    let ItemEnum {
+        vis, ident, variants, attrs, ..
+    } = enum_data;
+    let discriminant_type = discriminant_type(attrs);
+    let is_unit_only = variants.iter().all(|variant| matches!(variant.fields, Fields::Unit));
+    // the value after the highest one of the previous `#[bits(..)]` variant
+    let mut next_after_bits = None;
+    let variants = variants.iter().map(|variant| {
+        let mut variant = variant.clone();
+        // rust gives the variant its first value and the next variant the value after the highest one,
+        // so `as` casts agree with the `-Bits` derives
+        if let Some(ranges) = discriminant_assigner::variant_bits(&variant) {
+            if is_unit_only {
+                let canonical = Literal::u128_unsuffixed(ranges[0].0);
+                variant.discriminant = Some((Default::default(), syn::parse_quote!(#canonical)));
+            }
+            next_after_bits = discriminant_assigner::highest_value(&ranges).checked_add(1);
+        } else if let (true, None, Some(next)) = (is_unit_only, &variant.discriminant, next_after_bits.take()) {
+            let next = Literal::u128_unsuffixed(next);
+            variant.discriminant = Some((Default::default(), syn::parse_quote!(#next)));
+        }
+        // `#[tag(..)]` and `#[bits(..)]` were already used by `FromBits` and `TryFromBits`
+        variant.attrs.retain(|attr| !is_tag_attribute(attr) && !is_bits_attribute(attr));
+        // conversions match on the variants, so they don't need rust's discriminants.
+        // these are `isize` without `#[repr]`, which can't hold the values of wider enums
+        if bitsize > 64 {
+            variant.discriminant = None;
+        }
+        // the `-Bits` derives evaluate discriminants as `u128`, but rust needs them to have the enum's repr type.
+        // untyped ones like `1 << 40` already get it from rust
+        if let Some((_, expr)) = &mut variant.discriminant {
+            if !matches!(expr, Expr::Lit(ExprLit { lit: Lit::Int(_), .. })) && !discriminant_assigner::is_untyped(expr) {
+                *expr = syn::parse_quote!((#expr) as #discriminant_type);
+            }
+        }
+        variant
+    });
     quote! {
         #vis enum #ident {
-            #variants
+            #(#variants,)*
         }
     }
 }
 
+/// The type of the discriminants, given by `#[repr(u8)]` and similar, or `isize` by default.
+fn discriminant_type(attrs: &[Attribute]) -> Ident {
+    let mut discriminant_type = format_ident!("isize");
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        // other options like `C` are ignored
+        let _ = attr.parse_nested_meta(|meta| {
+            let is_int = ["u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize"]
+                .iter()
+                .any(|name| meta.path.is_ident(name));
+            if let (true, Some(ident)) = (is_int, meta.path.get_ident()) {
+                discriminant_type = ident.clone();
+            }
+            Ok(())
+        });
+    }
+    discriminant_type
+}
+
 /// We have _one_ `generate_common` function, which holds everything struct and enum have _in common_.
 /// Everything else has its own `generate_` functions.
-fn generate_common(ir: ItemIr, arb_int: &TokenStream) -> TokenStream {
-    let ItemIr { attrs, name, expanded } = ir;
+fn generate_common(ir: ItemIr, declared_bitsize: BitSize, arb_int: &TokenStream) -> TokenStream {
+    let ItemIr {
+        attrs,
+        name,
+        generics,
+        expanded,
+        fields_info,
+        valid_bits,
+    } = ir;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let bitsized_impl = match Storage::from_bitsize(declared_bitsize) {
+        Storage::ArbitraryInt => quote! {
+            impl #impl_generics ::bilge::Bitsized for #name #ty_generics #where_clause {
+                type ArbitraryInt = #arb_int;
+                const BITS: usize = <Self::ArbitraryInt as Bitsized>::BITS;
+                const MAX: Self::ArbitraryInt = <Self::ArbitraryInt as Bitsized>::MAX;
+                const VALID_BITS: ::bilge::BitPattern = #valid_bits;
+                #fields_info
+            }
+        },
+        Storage::ByteArray => {
+            let bits = declared_bitsize as usize;
+            let len = byte_len(declared_bitsize);
+            quote! {
+                impl #impl_generics ::bilge::Bitsized for #name #ty_generics #where_clause {
+                    type ArbitraryInt = #arb_int;
+                    const BITS: usize = #bits;
+                    // all bits set, besides the ones in the last byte which are not part of the bitfield
+                    const MAX: Self::ArbitraryInt = {
+                        let mut max = [u8::MAX; #len];
+                        max[#len - 1] = u8::MAX >> (#len * 8 - #bits);
+                        max
+                    };
+                    #fields_info
+                }
+            }
+        }
+    };
+
+    // `#[reset_value(..)]` was already used by `DefaultBits`, `#[tag_bits(..)]` by `FromBits` and `TryFromBits`
+    let attrs = attrs
+        .iter()
+        .filter(|attr| !is_reset_value_attribute(attr) && !is_tag_bits_attribute(attr));
 
     quote! {
         #(#attrs)*
         #expanded
-        impl ::bilge::Bitsized for #name {
-            type ArbitraryInt = #arb_int;
-            const BITS: usize = <Self::ArbitraryInt as Bitsized>::BITS;
-            const MAX: Self::ArbitraryInt = <Self::ArbitraryInt as Bitsized>::MAX;
-        }
+        #bitsized_impl
     }
 }
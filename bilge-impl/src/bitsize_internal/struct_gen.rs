@@ -23,69 +23,127 @@
 //! ```
 //! which aids in reading this here macro code, but doesn't help reading the generated code since it introduces
 //! lots of new scopes (curly brackets). We need the scope since `#value_shifted` expands to multiple lines.
+//!
+//! ## Bit order
+//!
+//! With `BitOrder::Msb0`, the elements of tuples and arrays are laid out in reverse,
+//! so the first element is stored at the highest bits. Since the cursor and offset always move
+//! from the lowest to the highest bits, we just visit the elements in reverse.
 use super::*;
 
 /// Top-level function which initializes the cursor and offsets it to what we want to read
 ///
 /// `is_array_elem_getter` allows us to generate an array_at getter more easily
-pub(crate) fn generate_getter_value(ty: &Type, offset: &TokenStream, is_array_elem_getter: bool) -> TokenStream {
+pub(crate) fn generate_getter_value(
+    ty: &Type, offset: &TokenStream, is_array_elem_getter: bool, storage: Storage, bit_order: BitOrder, generics: &Generics,
+) -> TokenStream {
     // if we generate `fn array_at(index)`, we need to offset to the array element
     let elem_offset = if is_array_elem_getter {
         let size = shared::generate_type_bitsize(ty);
+        let advance_cursor = generate_cursor_advance(quote!(size * index), storage);
         quote! {
             let size = #size;
             // cursor now starts at this element
-            cursor >>= size * index;
+            #advance_cursor
         }
     } else {
         quote!()
     };
 
-    let inner = generate_getter_inner(ty, true);
+    let cursor_init = generate_cursor_init(quote!(self.raw()), storage);
+    let advance_cursor = generate_cursor_advance(quote!(field_offset), storage);
+    let inner = generate_getter_inner(ty, true, &[], storage, bit_order, generics);
     quote! {
         // for ease of reading
         type ArbIntOf<T> = <T as Bitsized>::ArbitraryInt;
-        type BaseIntOf<T> = <ArbIntOf<T> as Number>::UnderlyingType;
-        // cursor is the value we read from and starts at the struct's first field
-        let mut cursor = self.value.value();
+        type BaseIntOf<T> = <ArbIntOf<T> as ::bilge::BaseInt>::UnderlyingType;
+        #cursor_init
         // this field's offset
         let field_offset = #offset;
         // cursor now starts at this field
-        cursor >>= field_offset;
+        #advance_cursor
         #elem_offset
 
         #inner
     }
 }
 
+/// The cursor is what we read from and starts at the struct's first field.
+///
+/// With `Storage::ArbitraryInt`, it is the struct's value, which gets shifted while reading.
+/// With `Storage::ByteArray`, it is a bit position inside `bytes`, which gets increased while reading.
+pub(crate) fn generate_cursor_init(struct_value: TokenStream, storage: Storage) -> TokenStream {
+    match storage {
+        Storage::ArbitraryInt => quote! {
+            let mut cursor = #struct_value.value();
+        },
+        Storage::ByteArray => quote! {
+            let bytes = &#struct_value;
+            let mut cursor: usize = 0;
+        },
+    }
+}
+
+/// Moves the cursor by `bits`, see [`generate_cursor_init`].
+pub(crate) fn generate_cursor_advance(bits: TokenStream, storage: Storage) -> TokenStream {
+    match storage {
+        Storage::ArbitraryInt => quote! {
+            cursor = cursor.wrapping_shr((#bits) as u32);
+        },
+        Storage::ByteArray => quote! {
+            cursor += #bits;
+        },
+    }
+}
+
 /// We heavily rely on the fact that transmuting into a nested array [[T; N1]; N2] can
 /// be done in the same way as transmuting into an array [T; N1*N2].
 /// Otherwise, nested arrays would generate even more code.
 ///
-/// `is_getter` allows us to generate a try_from impl more easily
-pub(crate) fn generate_getter_inner(ty: &Type, is_getter: bool) -> TokenStream {
+/// `is_getter` allows us to generate a try_from impl more easily: instead of the value, this generates statements
+/// which `break 'check` with a `BitsError` for the first invalid element. `path` holds the `PathSegment`s leading
+/// to the current element, and `bit_offset` counts the element's offset, since the cursor can't tell us.
+pub(crate) fn generate_getter_inner(
+    ty: &Type, is_getter: bool, path: &[TokenStream], storage: Storage, bit_order: BitOrder, generics: &Generics,
+) -> TokenStream {
     use Type::*;
     match ty {
+        Tuple(tuple) if bit_order == BitOrder::Msb0 && is_getter => {
+            // the last element is stored at the lowest bits, so it needs to be read first
+            let elem_names: Vec<Ident> = (0..tuple.elems.len()).map(|i| format_ident!("elem_{}", i)).collect();
+            let elem_values = tuple.elems.iter().zip(&elem_names).rev().map(|(elem, elem_name)| {
+                let getter = generate_getter_inner(elem, is_getter, path, storage, bit_order, generics);
+                quote! {
+                    let #elem_name = {#getter};
+                }
+            });
+            quote! { {
+                #( #elem_values )*
+                (#( #elem_names ),*)
+            } }
+        }
+        Tuple(tuple) if !is_getter => {
+            let elems: Vec<_> = tuple.elems.iter().enumerate().collect();
+            let elem_checks = in_bit_order(&elems, bit_order).into_iter().map(|(i, elem)| {
+                // the element's index, e.g. `.0`, is added to the path
+                let index = i.to_string();
+                let path: Vec<TokenStream> = path.iter().cloned().chain([quote!(::bilge::PathSegment::Field(#index))]).collect();
+                let check = generate_getter_inner(elem, is_getter, &path, storage, bit_order, generics);
+                quote! { {#check} }
+            });
+            quote! { #( #elem_checks )* }
+        }
         Tuple(tuple) => {
-            let unbraced = tuple
-                .elems
-                .iter()
+            let unbraced = in_bit_order(&tuple.elems, bit_order)
+                .into_iter()
                 .map(|elem| {
                     // for every tuple element, generate its getter code
-                    let getter = generate_getter_inner(elem, is_getter);
+                    let getter = generate_getter_inner(elem, is_getter, path, storage, bit_order, generics);
                     // and add a scope around it
                     quote! { {#getter} }
                 })
-                .reduce(|acc, next| {
-                    // join all getter codes with:
-                    if is_getter {
-                        // comma, to later produce (val_1, val_2, ...)
-                        quote!(#acc, #next)
-                    } else {
-                        // bool-and, since for try_from we just generate bools
-                        quote!(#acc && #next)
-                    }
-                })
+                // join all getter codes with a comma, to later produce (val_1, val_2, ...)
+                .reduce(|acc, next| quote!(#acc, #next))
                 // `field: (),` will be handled like this:
                 .unwrap_or_else(|| quote!());
             // add tuple braces, to produce (val_1, val_2, ...)
@@ -94,8 +152,14 @@ pub(crate) fn generate_getter_inner(ty: &Type, is_getter: bool) -> TokenStream {
         Array(array) => {
             // [[T; N1]; N2] -> (N1*N2, T)
             let (len_expr, elem_ty) = length_and_type_of_nested_array(array);
+            let array_index = generate_array_index(&len_expr, bit_order);
+            // the indices of nested arrays, e.g. `[1][2]`, are added to the path, computed from the flattened index
+            let index_name = format_ident!("index_{}", path.len());
+            let lengths = nested_array_lengths(array);
+            let index_path = (0..lengths.len()).map(|dim| quote!(::bilge::PathSegment::nested_index(#index_name, &[#( #lengths ),*], #dim)));
+            let path: Vec<TokenStream> = path.iter().cloned().chain(index_path).collect();
             // generate the getter code for one array element
-            let array_elem = generate_getter_inner(&elem_ty, is_getter);
+            let array_elem = generate_getter_inner(&elem_ty, is_getter, &path, storage, bit_order, generics);
             // either generate an array or only check each value
             if is_getter {
                 quote! {
@@ -113,7 +177,7 @@ pub(crate) fn generate_getter_inner(ty: &Type, is_getter: bool) -> TokenStream {
                                 #array_elem
                             };
                             // and write it to the output array
-                            array[i].write(elem_value);
+                            array[#array_index].write(elem_value);
                             i += 1;
                         }
                         // [T; N1*N2] -> [[T; N1]; N2]
@@ -123,70 +187,97 @@ pub(crate) fn generate_getter_inner(ty: &Type, is_getter: bool) -> TokenStream {
                 }
             } else {
                 quote! { {
-                    let mut is_filled = true;
                     let mut i = 0;
                     // TODO: this could be simplified for always-filled values
                     while i < #len_expr {
-                        // for every element, get its filled check
-                        let elem_filled = {
+                        // nested loops have their own `i`, so the path needs this one by name
+                        let #index_name = #array_index;
+                        // for every element, check it
+                        {
                             #array_elem
-                        };
-                        // and join it with the others
-                        is_filled = is_filled && elem_filled;
+                        }
                         i += 1;
                     }
-                    is_filled
                 } }
             }
         }
         Path(_) => {
             // get the size, so we can shift to the next element's offset
             let size = shared::generate_type_bitsize(ty);
-            // get the mask, so we can get this element's value
-            let mask = generate_ty_mask(ty);
+            let advance_cursor = generate_cursor_advance(quote!(size), storage);
 
+            let raw_value = match storage {
+                Storage::ArbitraryInt => {
+                    // get the mask, so we can get this element's value
+                    let mask = generate_ty_mask(ty, bit_order, generics);
+                    quote! {
+                        // the element's mask
+                        let mask = #mask;
+                        // the cursor starts at this element's offset, now get its value
+                        let raw_value = cursor & mask;
+                    }
+                }
+                Storage::ByteArray => quote! {
+                    // the cursor points to this element's offset, now get its value (as u128)
+                    let raw_value = ::bilge::read_bits(bytes, cursor, #size);
+                },
+            };
+
+            let is_generic = is_generic_type(ty, generics);
+            let cast_raw_value = generate_cast(quote!(raw_value), quote!(BaseIntOf<#ty>), is_generic);
+            // the checks keep the bits, since the errors of generic types can't be nested
+            let elem_bits = (!is_getter).then(|| quote!(let elem_bits = raw_value as u128;));
             // do all steps until conversion
             let elem_value = quote! {
-                // the element's mask
-                let mask = #mask;
-                // the cursor starts at this element's offset, now get its value
-                let raw_value = cursor & mask;
+                #raw_value
+                #elem_bits
                 // after getting the value, we can shift by the element's size
                 // TODO: we could move this into tuple/array (and try_from, below)
                 let size = #size;
-                cursor = cursor.wrapping_shr(size as u32);
+                #advance_cursor
                 // cast the element value (e.g. u32 -> u8),
-                let raw_value: BaseIntOf<#ty> = raw_value as BaseIntOf<#ty>;
+                let raw_value: BaseIntOf<#ty> = #cast_raw_value;
                 // which allows it to be used here (e.g. u4::new(u8))
                 let elem_value = <#ty as Bitsized>::ArbitraryInt::new(raw_value);
             };
 
             if is_getter {
                 // generate the real value from the arbint `elem_value`
+                let field_value = shared::generate_arb_int_to_field(ty, quote!(elem_value));
                 quote! {
                     #elem_value
-                    match <#ty>::try_from(elem_value) {
-                        Ok(v) => v,
-                        Err(_) => panic!("unreachable"),
-                    }
+                    #field_value
                 }
             } else {
                 // generate only the filled check
-                if shared::is_always_filled(ty) {
+                if shared::is_always_filled(ty) || shared::is_signed_primitive(ty) {
                     // skip the obviously filled values
-                    quote! {
+                    quote! { {
                         // we still need to shift by the element's size
                         let size = #size;
-                        cursor = cursor.wrapping_shr(size as u32);
-                        true
-                    }
+                        #advance_cursor
+                        bit_offset += size;
+                    } }
                 } else {
+                    let error = if is_generic {
+                        // we don't know the error type, so the error is about the whole element
+                        quote!(::bilge::BitsError::new(size, elem_bits))
+                    } else {
+                        // the error's path and offset are relative to the element,
+                        // and it can also be `Infallible`, since nested `FromBits` types implement `From`
+                        quote!(::bilge::BitsError::from(error))
+                    };
                     // handle structs, enums - everything which can be unfilled
                     quote! { {
                         #elem_value
+                        let elem_offset = bit_offset;
+                        bit_offset += size;
                         // so, has try_from impl
                         // note this is available even if the type is `From`
-                        <#ty>::try_from(elem_value).is_ok()
+                        if let Err(error) = <#ty>::try_from(elem_value) {
+                            let error = #error;
+                            break 'check Err(error.nest(&[#( #path ),*], elem_offset));
+                        }
                     } }
                 }
             }
@@ -198,7 +289,13 @@ pub(crate) fn generate_getter_inner(ty: &Type, is_getter: bool) -> TokenStream {
 /// Top-level function which initializes the offset, masks other values and combines the final value
 ///
 /// `is_array_elem_setter` allows us to generate a set_array_at setter more easily
-pub(crate) fn generate_setter_value(ty: &Type, offset: &TokenStream, is_array_elem_setter: bool) -> TokenStream {
+///
+/// `write_one_mask` holds the bits of all `#[w1c]` and `#[w1s]` fields, which are set to zero when writing any other field.
+/// This way, writing the struct back to a register only changes them if they were written explicitly.
+pub(crate) fn generate_setter_value(
+    ty: &Type, offset: &TokenStream, is_array_elem_setter: bool, storage: Storage, bit_order: BitOrder, write_one_mask: Option<&TokenStream>,
+    generics: &Generics,
+) -> TokenStream {
     // if we generate `fn set_array_at(index, value)`, we need to offset to the array element
     let elem_offset = if is_array_elem_setter {
         let size = shared::generate_type_bitsize(ty);
@@ -211,53 +308,85 @@ pub(crate) fn generate_setter_value(ty: &Type, offset: &TokenStream, is_array_el
         quote!()
     };
 
-    let value_shifted = generate_setter_inner(ty);
+    if storage == Storage::ByteArray {
+        let value_written = generate_setter_inner(ty, storage, bit_order, generics);
+        return quote! {
+            type ArbIntOf<T> = <T as Bitsized>::ArbitraryInt;
+            type BaseIntOf<T> = <ArbIntOf<T> as ::bilge::BaseInt>::UnderlyingType;
+
+            // offset now starts at this field
+            let mut offset = #offset;
+            #elem_offset
+
+            // the current struct value
+            let mut bytes = self.raw();
+            // overwrite each element, one after another
+            #value_written
+            // only this field changed, to a valid value
+            *self = unsafe { Self::from_raw_unchecked(bytes) };
+        };
+    }
+
+    // `#[w1c]` and `#[w1s]` are rejected for `Storage::ByteArray`
+    let mask_write_one = write_one_mask.map(|write_one_mask| {
+        quote! {
+            // write zero to all W1C and W1S fields, so they don't change
+            let others_values: BaseIntOf<Self> = others_values & !(#write_one_mask);
+        }
+    });
+    let value_shifted = generate_setter_inner(ty, storage, bit_order, generics);
     // get the mask, so we can set this field's value
-    let mask = generate_ty_mask(ty);
+    let mask = generate_ty_mask(ty, bit_order, generics);
     quote! {
         type ArbIntOf<T> = <T as Bitsized>::ArbitraryInt;
-        type BaseIntOf<T> = <ArbIntOf<T> as Number>::UnderlyingType;
+        type BaseIntOf<T> = <ArbIntOf<T> as ::bilge::BaseInt>::UnderlyingType;
 
         // offset now starts at this field
         let mut offset = #offset;
         #elem_offset
 
         let field_mask = #mask;
-        // shift the mask into place
-        let field_mask: BaseIntOf<Self> = field_mask << offset;
+        // shift the mask into place, zero-width fields may sit right after the last bit
+        let field_mask: BaseIntOf<Self> = field_mask.wrapping_shl(offset as u32);
         // all other fields as a mask
         let others_mask: BaseIntOf<Self> = !field_mask;
         // the current struct value
-        let struct_value: BaseIntOf<Self> = self.value.value();
+        let struct_value: BaseIntOf<Self> = self.raw().value();
         // mask off the field getting set
         let others_values: BaseIntOf<Self> = struct_value & others_mask;
+        #mask_write_one
 
         // get the new field value, shifted into place
         #value_shifted
 
         // join the values using bit-or
         let new_struct_value = others_values | value_shifted;
-        self.value = <ArbIntOf<Self>>::new(new_struct_value);
+        // only this field changed, to a valid value
+        *self = unsafe { Self::from_raw_unchecked(<ArbIntOf<Self>>::new(new_struct_value)) };
     }
 }
 
 /// We heavily rely on the fact that transmuting into a nested array [[T; N1]; N2] can
 /// be done in the same way as transmuting into an array [T; N1*N2].
 /// Otherwise, nested arrays would generate even more code.
-fn generate_setter_inner(ty: &Type) -> TokenStream {
+///
+/// With `Storage::ByteArray`, we don't produce `value_shifted`, but directly write each element into `bytes`.
+fn generate_setter_inner(ty: &Type, storage: Storage, bit_order: BitOrder, generics: &Generics) -> TokenStream {
     use Type::*;
+    if storage == Storage::ByteArray {
+        return generate_byte_array_setter_inner(ty, bit_order, generics);
+    }
     match ty {
         Tuple(tuple) => {
-            // to index into the tuple value
-            let mut tuple_index = syn::Index::from(0);
-            let value_shifted = tuple
-                .elems
-                .iter()
-                .map(|elem| {
+            let elems: Vec<_> = tuple.elems.iter().enumerate().collect();
+            let value_shifted = in_bit_order(&elems, bit_order)
+                .into_iter()
+                .map(|(tuple_index, elem)| {
+                    // to index into the tuple value
+                    let tuple_index = syn::Index::from(*tuple_index);
                     let elem_name = quote!(value.#tuple_index);
-                    tuple_index.index += 1;
                     // for every tuple element, generate its setter code
-                    let value_shifted = generate_setter_inner(elem);
+                    let value_shifted = generate_setter_inner(elem, storage, bit_order, generics);
                     // set the value and add a scope around it
                     quote! { {
                         let value = #elem_name;
@@ -277,7 +406,8 @@ fn generate_setter_inner(ty: &Type) -> TokenStream {
             // [[T; N1]; N2] -> (N1*N2, T)
             let (len_expr, elem_ty) = length_and_type_of_nested_array(array);
             // generate the setter code for one array element
-            let value_shifted = generate_setter_inner(&elem_ty);
+            let value_shifted = generate_setter_inner(&elem_ty, storage, bit_order, generics);
+            let array_index = generate_array_index(&len_expr, bit_order);
             quote! {
                 // [[T; N1]; N2] -> [T; N1*N2], for example: [[(u2, u2); 3]; 4] -> [(u2, u2); 12]
                 #[allow(clippy::useless_transmute)]
@@ -287,7 +417,7 @@ fn generate_setter_inner(ty: &Type) -> TokenStream {
                 let mut acc = 0;
                 let mut i = 0;
                 while i < #len_expr {
-                    let value = value[i];
+                    let value = value[#array_index];
                     // for every element, shift its value into its place
                     #value_shifted
                     // and bit-or them together
@@ -300,13 +430,15 @@ fn generate_setter_inner(ty: &Type) -> TokenStream {
         Path(_) => {
             // get the size, so we can reach the next element afterwards
             let size = shared::generate_type_bitsize(ty);
+            let cast_value = generate_cast(quote!(value), quote!(BaseIntOf<Self>), is_generic_type(ty, generics));
+            let base_int = shared::generate_field_to_base_int(ty, quote!(value));
             quote! {
                 // the element's value as it's underlying type
-                let value: BaseIntOf<#ty> = <ArbIntOf<#ty>>::from(value).value();
+                let value: BaseIntOf<#ty> = #base_int;
                 // cast the element value (e.g. u8 -> u32),
                 // which allows it to be combined with the struct's value later
-                let value: BaseIntOf<Self> = value as BaseIntOf<Self>;
-                let value_shifted = value << offset;
+                let value: BaseIntOf<Self> = #cast_value;
+                let value_shifted = value.wrapping_shl(offset as u32);
                 // increase the offset to allow the next element to be read
                 offset += #size;
             }
@@ -315,15 +447,82 @@ fn generate_setter_inner(ty: &Type) -> TokenStream {
     }
 }
 
-/// The constructor code just needs every field setter.
-///
-/// [`super::generate_struct`] contains the initialization of `offset`.
-pub(crate) fn generate_constructor_part(ty: &Type, name: &Ident) -> TokenStream {
-    let value_shifted = generate_setter_inner(ty);
+/// Same as [`generate_setter_inner`], but for `Storage::ByteArray`.
+fn generate_byte_array_setter_inner(ty: &Type, bit_order: BitOrder, generics: &Generics) -> TokenStream {
+    use Type::*;
+    match ty {
+        Tuple(tuple) => {
+            let elems: Vec<_> = tuple.elems.iter().enumerate().collect();
+            let value_written = in_bit_order(&elems, bit_order).into_iter().map(|(tuple_index, elem)| {
+                let tuple_index = syn::Index::from(*tuple_index);
+                // for every tuple element, generate its setter code
+                let value_written = generate_byte_array_setter_inner(elem, bit_order, generics);
+                // set the value and add a scope around it
+                quote! { {
+                    let value = value.#tuple_index;
+                    #value_written
+                } }
+            });
+            quote! {
+                #( #value_written )*
+            }
+        }
+        Array(array) => {
+            // [[T; N1]; N2] -> (N1*N2, T)
+            let (len_expr, elem_ty) = length_and_type_of_nested_array(array);
+            // generate the setter code for one array element
+            let value_written = generate_byte_array_setter_inner(&elem_ty, bit_order, generics);
+            let array_index = generate_array_index(&len_expr, bit_order);
+            quote! {
+                // [[T; N1]; N2] -> [T; N1*N2], for example: [[(u2, u2); 3]; 4] -> [(u2, u2); 12]
+                #[allow(clippy::useless_transmute)]
+                let value: [#elem_ty; #len_expr] = unsafe { ::core::mem::transmute(value) };
+                // constness: iter, for-loop, range are not const, so we're using while loops
+                let mut i = 0;
+                while i < #len_expr {
+                    let value = value[#array_index];
+                    // for every element, write its value into its place
+                    #value_written
+                    i += 1;
+                }
+            }
+        }
+        Path(_) => {
+            // get the size, so we can reach the next element afterwards
+            let size = shared::generate_type_bitsize(ty);
+            let cast_value = generate_cast(quote!(value), quote!(u128), is_generic_type(ty, generics));
+            let base_int = shared::generate_field_to_base_int(ty, quote!(value));
+            quote! {
+                // the element's value as it's underlying type
+                let value: BaseIntOf<#ty> = #base_int;
+                // write it into place
+                bytes = ::bilge::write_bits(bytes, offset, #size, #cast_value);
+                // increase the offset to allow the next element to be written
+                offset += #size;
+            }
+        }
+        _ => unreachable(()),
+    }
+}
+
+/// The constructor code just needs every field setter, starting at the field's offset.
+pub(crate) fn generate_constructor_part(
+    ty: &Type, offset: &TokenStream, name: &Ident, storage: Storage, bit_order: BitOrder, generics: &Generics,
+) -> TokenStream {
+    if storage == Storage::ByteArray {
+        let value_written = generate_setter_inner(ty, storage, bit_order, generics);
+        return quote! { {
+            let mut offset = #offset;
+            let value = #name;
+            #value_written
+        } };
+    }
+    let value_shifted = generate_setter_inner(ty, storage, bit_order, generics);
     // setters look like this: `fn set_field1(&mut self, value: u3)`
     // constructors like this: `fn new(field1: u3, field2: u4) -> Self`
     // so we need to rename `field1` -> `value` and put this in a scope
     quote! { {
+        let mut offset = #offset;
         let value = #name;
         #value_shifted
         value_shifted
@@ -332,17 +531,16 @@ pub(crate) fn generate_constructor_part(ty: &Type, name: &Ident) -> TokenStream
 
 /// We mostly need this in [`generate_setter_value`], to mask the whole field.
 /// It basically combines a bunch of `Bitsized::MAX` values into a mask.
-fn generate_ty_mask(ty: &Type) -> TokenStream {
+fn generate_ty_mask(ty: &Type, bit_order: BitOrder, generics: &Generics) -> TokenStream {
     use Type::*;
     match ty {
         Tuple(tuple) => {
             let mut previous_elem_sizes = vec![];
-            tuple
-                .elems
-                .iter()
+            in_bit_order(&tuple.elems, bit_order)
+                .into_iter()
                 .map(|elem| {
                     // for every element, generate a mask
-                    let mask = generate_ty_mask(elem);
+                    let mask = generate_ty_mask(elem, bit_order, generics);
                     // get it's size
                     let elem_size = shared::generate_type_bitsize(elem);
                     // generate it's offset from all previous sizes
@@ -350,7 +548,7 @@ fn generate_ty_mask(ty: &Type) -> TokenStream {
                     previous_elem_sizes.push(elem_size);
                     // the first field doesn't need to be shifted
                     if let Some(elem_offset) = elem_offset {
-                        quote!(#mask << #elem_offset)
+                        quote!((#mask).wrapping_shl((#elem_offset) as u32))
                     } else {
                         quote!(#mask)
                     }
@@ -358,13 +556,14 @@ fn generate_ty_mask(ty: &Type) -> TokenStream {
                 // join all shifted masks with bit-or
                 .reduce(|acc, next| quote!(#acc | #next))
                 // `field: (),` will be handled like this:
-                .unwrap_or_else(|| quote!(0))
+                .unwrap_or_else(|| quote!((0 as BaseIntOf<Self>)))
         }
         Array(array) => {
             let elem_ty = &array.elem;
             let len_expr = &array.len;
             // generate the mask for one array element
-            let mask = generate_ty_mask(elem_ty);
+            // all elements have the same mask, so the bit order doesn't matter here
+            let mask = generate_ty_mask(elem_ty, bit_order, generics);
             // and the size
             let ty_size = shared::generate_type_bitsize(elem_ty);
             quote! { {
@@ -380,17 +579,24 @@ fn generate_ty_mask(ty: &Type) -> TokenStream {
                 field_mask
             } }
         }
-        Path(_) => quote! {
-            // Casting this is needed in some places, but it might not be needed in some others.
-            // (u2, u12) -> u8 << 0 | u16 << 2 -> u8 | u16 not possible
-            (<#ty as Bitsized>::MAX.value() as BaseIntOf<Self>)
-        },
+        Path(_) => {
+            let cast_max = generate_cast(
+                quote!(<#ty as Bitsized>::MAX.value()),
+                quote!(BaseIntOf<Self>),
+                is_generic_type(ty, generics),
+            );
+            quote! {
+                // Casting this is needed in some places, but it might not be needed in some others.
+                // (u2, u12) -> u8 << 0 | u16 << 2 -> u8 | u16 not possible
+                (#cast_max)
+            }
+        }
         _ => unreachable(()),
     }
 }
 
 /// We compute nested length here, to fold [[T; N]; M] to [T; N * M].
-fn length_and_type_of_nested_array(array: &syn::TypeArray) -> (TokenStream, Type) {
+pub(crate) fn length_and_type_of_nested_array(array: &syn::TypeArray) -> (TokenStream, Type) {
     let elem_ty = &array.elem;
     let len_expr = &array.len;
     if let Type::Array(array) = &**elem_ty {
@@ -400,3 +606,33 @@ fn length_and_type_of_nested_array(array: &syn::TypeArray) -> (TokenStream, Type
         (quote!(#len_expr), *elem_ty.clone())
     }
 }
+
+/// The lengths of nested arrays, starting with the outermost, e.g. `[M, N]` for `[[T; N]; M]`.
+pub(crate) fn nested_array_lengths(array: &syn::TypeArray) -> Vec<TokenStream> {
+    let len_expr = &array.len;
+    let mut lengths = vec![quote!(#len_expr)];
+    if let Type::Array(array) = &*array.elem {
+        lengths.extend(nested_array_lengths(array));
+    }
+    lengths
+}
+
+/// Returns the elements in the order they are stored, starting at the lowest bits.
+fn in_bit_order<'a, T>(elems: impl IntoIterator<Item = &'a T>, bit_order: BitOrder) -> Vec<&'a T>
+where
+    T: 'a,
+{
+    let mut elems: Vec<_> = elems.into_iter().collect();
+    if bit_order == BitOrder::Msb0 {
+        elems.reverse();
+    }
+    elems
+}
+
+/// The index into the flattened array, for the `i`th element starting at the lowest bits.
+fn generate_array_index(len_expr: &TokenStream, bit_order: BitOrder) -> TokenStream {
+    match bit_order {
+        BitOrder::Lsb0 => quote!(i),
+        BitOrder::Msb0 => quote!(#len_expr - 1 - i),
+    }
+}
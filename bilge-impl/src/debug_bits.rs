@@ -3,7 +3,7 @@ use proc_macro_error::abort_call_site;
 use quote::quote;
 use syn::{Data, Fields};
 
-use crate::shared::{self, unreachable};
+use crate::shared::{self, alias, field_access::FieldAccess, generics, unreachable};
 
 pub(super) fn debug_bits(item: TokenStream) -> TokenStream {
     let derive_input = shared::parse_derive(item);
@@ -16,14 +16,22 @@ pub(super) fn debug_bits(item: TokenStream) -> TokenStream {
         Data::Union(_) => unreachable(()),
     };
 
+    let generics = generics::bitfield_generics(&derive_input.generics, &struct_data.fields, quote!(+ ::core::fmt::Debug));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
     let fmt_impl = match struct_data.fields {
         Fields::Named(fields) => {
-            let calls = fields.named.iter().map(|f| {
-                // We can unwrap since this is a named field
-                let call = f.ident.as_ref().unwrap();
-                let name = call.to_string();
-                quote!(.field(#name, &self.#call()))
-            });
+            // write-only fields have no getter, alias fields only show bits again
+            let calls = fields
+                .named
+                .iter()
+                .filter(|f| FieldAccess::of(f).is_readable() && !alias::is_alias(f))
+                .map(|f| {
+                    // We can unwrap since this is a named field
+                    let call = f.ident.as_ref().unwrap();
+                    let name = call.to_string();
+                    quote!(.field(#name, &self.#call()))
+                });
             quote! {
                 f.debug_struct(#name_str)
                 // .field("field1", &self.field1()).field("field2", &self.field2()).field("field3", &self.field3()).finish()
@@ -31,10 +39,10 @@ pub(super) fn debug_bits(item: TokenStream) -> TokenStream {
             }
         }
         Fields::Unnamed(fields) => {
-            let calls = fields.unnamed.iter().map(|_| {
+            let calls = fields.unnamed.iter().filter_map(|f| {
                 let call: Ident = syn::parse_str(&format!("val_{}", fieldless_next_int)).unwrap_or_else(unreachable);
                 fieldless_next_int += 1;
-                quote!(.field(&self.#call()))
+                (FieldAccess::of(f).is_readable() && !alias::is_alias(f)).then(|| quote!(.field(&self.#call())))
             });
             quote! {
                 f.debug_tuple(#name_str)
@@ -42,11 +50,12 @@ pub(super) fn debug_bits(item: TokenStream) -> TokenStream {
                 #(#calls)*.finish()
             }
         }
-        Fields::Unit => todo!("this is a unit struct, which is not supported right now"),
+        // `#[bitsize]` turns these into `{}` structs, so this only happens without it
+        Fields::Unit => quote!(f.write_str(#name_str)),
     };
 
     quote! {
-        impl ::core::fmt::Debug for #name {
+        impl #impl_generics ::core::fmt::Debug for #name #ty_generics #where_clause {
             fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
                 #fmt_impl
             }
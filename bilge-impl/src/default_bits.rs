@@ -1,41 +1,273 @@
 use proc_macro2::{Ident, TokenStream};
-use proc_macro_error::abort_call_site;
-use quote::quote;
-use syn::{Data, DeriveInput, Fields, Type};
+use proc_macro_error::{abort, abort_call_site};
+use quote::{quote, quote_spanned};
+use syn::{spanned::Spanned, Data, DeriveInput, Expr, Field, Fields, Generics, Type, TypeTuple};
 
-use crate::shared::{self, fallback::Fallback, unreachable, BitSize};
+use crate::bitsize_internal::struct_gen;
+use crate::shared::{
+    self, alias,
+    default_value::{self, FieldDefault},
+    fallback::Fallback,
+    generics::{self, generate_cast, is_generic_type},
+    required_value, unreachable, BitOrder, BitSize, BitsizeArgs, DeriveKind, Storage,
+};
+use crate::try_from_bits;
 
 pub(crate) fn default_bits(item: TokenStream) -> TokenStream {
     let derive_input = parse(item);
     //TODO: does fallback need handling?
-    let (derive_data, _, name, ..) = analyze(&derive_input);
+    let (derive_data, BitsizeArgs { bitsize, bit_order, .. }, name, generics, _) = analyze(&derive_input);
 
     match derive_data {
-        Data::Struct(data) => generate_struct_default_impl(name, &data.fields),
+        Data::Struct(data) => match default_value::reset_value(&derive_input.attrs) {
+            Some(reset_value) => generate_struct_reset_value_impl(name, generics, &data.fields, bitsize, bit_order, &reset_value),
+            None => generate_struct_default_impl(name, generics, &data.fields, bitsize, bit_order),
+        },
         Data::Enum(_) => abort_call_site!("use derive(Default) for enums"),
         _ => unreachable(()),
     }
 }
 
-fn generate_struct_default_impl(struct_name: &Ident, fields: &Fields) -> TokenStream {
-    let default_value = fields
+fn generate_struct_default_impl(struct_name: &Ident, generics: &Generics, fields: &Fields, bitsize: BitSize, bit_order: BitOrder) -> TokenStream {
+    let field_offsets = shared::generate_field_offsets(fields, bitsize, bit_order);
+    let storage = Storage::from_bitsize(bitsize);
+    // fields with a required value always get it
+    let field_defaults: Vec<Option<FieldDefault>> = fields
         .iter()
-        .map(|field| generate_default_inner(&field.ty))
-        .reduce(|acc, next| quote!(#acc | #next));
+        .map(|field| FieldDefault::of(field).or_else(|| required_value::required_bits(field).map(FieldDefault::Bits)))
+        .collect();
+    let bits_checks = fields.iter().zip(&field_defaults).filter_map(|(field, default)| match default {
+        Some(FieldDefault::Bits(bits)) => Some(generate_default_bits_check(field, bits, generics)),
+        _ => None,
+    });
+    let bits_checks = quote!(#( #bits_checks )*);
+
+    // alias fields only give another view of bits, which get their default through the other fields
+    let not_alias = |((field, _), _): &((&Field, _), _)| !alias::is_alias(field);
+
+    let default_value = match storage {
+        Storage::ArbitraryInt => {
+            let default_value = fields
+                .iter()
+                .zip(&field_offsets)
+                .zip(&field_defaults)
+                .filter(not_alias)
+                .map(|((field, offset), default)| match default {
+                    Some(default) => generate_field_default(field, offset, default, storage, bit_order, generics),
+                    None => {
+                        let value_shifted = generate_default_inner(&field.ty, bit_order, generics);
+                        quote! {{
+                            let mut offset = #offset;
+                            (#value_shifted)
+                        }}
+                    }
+                })
+                .reduce(|acc, next| quote!(#acc | #next))
+                .unwrap_or_else(|| quote!(0));
+            quote! {
+                let value = #default_value;
+                let value = <Self as Bitsized>::ArbitraryInt::new(value);
+            }
+        }
+        Storage::ByteArray => {
+            let default_written = fields
+                .iter()
+                .zip(&field_offsets)
+                .zip(&field_defaults)
+                .filter(not_alias)
+                .map(|((field, offset), default)| match default {
+                    Some(default) => generate_field_default(field, offset, default, storage, bit_order, generics),
+                    None => {
+                        let value_written = generate_byte_array_default_inner(&field.ty, bit_order, generics);
+                        quote! {{
+                            let mut offset = #offset;
+                            #value_written
+                        }}
+                    }
+                });
+            let len = shared::byte_len(bitsize);
+            quote! {
+                let mut bytes = [0u8; #len];
+                #( #default_written )*
+                let value = bytes;
+            }
+        }
+    };
+
+    // the setter code used for fields with a default value needs these
+    let type_aliases = field_defaults.iter().any(Option::is_some).then(|| {
+        quote! {
+            type ArbIntOf<T> = <T as Bitsized>::ArbitraryInt;
+            type BaseIntOf<T> = <ArbIntOf<T> as ::bilge::BaseInt>::UnderlyingType;
+        }
+    });
+
+    let generics = generics::bitfield_generics(generics, fields, quote!(+ ::core::default::Default));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     quote! {
-        impl ::core::default::Default for #struct_name {
+        impl #impl_generics ::core::default::Default for #struct_name #ty_generics #where_clause {
+            #[allow(clippy::type_complexity, unused_parens)]
             fn default() -> Self {
-                let mut offset = 0;
-                let value = #default_value;
-                let value = <#struct_name as Bitsized>::ArbitraryInt::new(value);
-                Self { value }
+                #type_aliases
+                #default_value
+                // all fields were set to their default values
+                unsafe { Self::from_raw_unchecked(value) }
+            }
+        }
+
+        #bits_checks
+    }
+}
+
+/// `#[reset_value(..)]` gives the bits of the whole bitfield, so we only need to check whether they are valid.
+fn generate_struct_reset_value_impl(
+    struct_name: &Ident, generics: &Generics, fields: &Fields, bitsize: BitSize, bit_order: BitOrder, reset_value: &Expr,
+) -> TokenStream {
+    let storage = Storage::from_bitsize(bitsize);
+    if storage == Storage::ByteArray {
+        abort!(reset_value, "`#[reset_value]` is only supported in bitfields up to 128 bits"; help = "use `#[default = ..]` on the fields instead")
+    }
+    if let Some(field) = fields.iter().find(|field| FieldDefault::of(field).is_some()) {
+        abort!(field, "fields can't have a default value if the bitfield has a reset value"; help = "change the reset value instead")
+    }
+
+    // generic structs can't be checked in a `const _`, so they are checked like `TryFromBits` does, when calling `default`
+    let (valid_check, runtime_check) = if generics.params.is_empty() {
+        let fits = generate_fits_check(reset_value, &quote!(#bitsize as usize));
+        let valid_check = quote_spanned! {reset_value.span()=>
+            #[allow(clippy::unnecessary_cast)]
+            const _: () = {
+                assert!(#fits, "reset value doesn't fit into the bitfield");
+                match <#struct_name as Bitsized>::VALID_BITS.matches((#reset_value) as u128) {
+                    Some(true) => {}
+                    Some(false) => panic!("reset value is not a valid bit pattern for this bitfield"),
+                    None => panic!("reset value can't be checked, since a field's type implements `Bitsized` by hand"),
+                }
+            };
+        };
+        (valid_check, quote!())
+    } else {
+        let field_offsets = shared::generate_field_offsets(fields, bitsize, bit_order);
+        let is_ok = try_from_bits::generate_struct_checks(fields, &field_offsets, storage, bit_order, generics)
+            .into_iter()
+            .map(|check| quote!(#check.is_ok()))
+            .reduce(|acc, next| quote!((#acc && #next)))
+            .unwrap_or_else(|| quote!(true));
+        let runtime_check = quote! {
+            let is_ok: bool = {#is_ok};
+            assert!(is_ok, "reset value is not a valid bit pattern for this bitfield");
+        };
+        (quote!(), runtime_check)
+    };
+
+    let generics = generics::bitfield_generics(generics, fields, quote!());
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::core::default::Default for #struct_name #ty_generics #where_clause {
+            #[allow(clippy::type_complexity, unused_parens)]
+            fn default() -> Self {
+                type ArbIntOf<T> = <T as Bitsized>::ArbitraryInt;
+                type BaseIntOf<T> = <ArbIntOf<T> as ::bilge::BaseInt>::UnderlyingType;
+
+                let value = <ArbIntOf<Self>>::new(#reset_value);
+                #runtime_check
+                // the reset value was validated above
+                unsafe { Self::from_raw_unchecked(value) }
+            }
+        }
+
+        #valid_check
+    }
+}
+
+/// Sets a field to its `#[default = ..]` value or `#[default(..)]` bits, like `new` does.
+fn generate_field_default(
+    field: &Field, offset: &TokenStream, default: &FieldDefault, storage: Storage, bit_order: BitOrder, generics: &Generics,
+) -> TokenStream {
+    let ty = &field.ty;
+    let value = match default {
+        FieldDefault::Value(value) => quote!(#value),
+        FieldDefault::Bits(bits) => {
+            let field_value = if shared::is_signed_primitive(ty) {
+                quote!(arb_int as #ty)
+            } else {
+                quote! {
+                    match <#ty>::try_from(arb_int) {
+                        Ok(v) => v,
+                        Err(_) => panic!("default bits are not a valid bit pattern for this field"),
+                    }
+                }
+            };
+            quote! {{
+                let arb_int = <ArbIntOf<#ty>>::new(#bits);
+                #field_value
+            }}
+        }
+    };
+    let field_default = Ident::new("field_default", field.span());
+    let value_set = struct_gen::generate_constructor_part(ty, offset, &field_default, storage, bit_order, generics);
+    quote! {{
+        let #field_default: #ty = #value;
+        #value_set
+    }}
+}
+
+/// `#[default(..)]` bits are checked against the field width at compile time.
+fn generate_default_bits_check(field: &Field, bits: &Expr, generics: &Generics) -> TokenStream {
+    if is_generic_type(&field.ty, generics) {
+        abort!(bits, "default bits can't be checked for fields using generic parameters"; help = "use `#[default = ..]` instead")
+    }
+    let fits = generate_fits_check(bits, &shared::generate_type_bitsize(&field.ty));
+    quote_spanned! {bits.span()=>
+        const _: () = assert!(#fits, "default bits don't fit into the field");
+    }
+}
+
+/// Whether `value` has at most `width` bits. Shifting twice avoids overflowing for `width == 128`.
+pub(crate) fn generate_fits_check(value: &Expr, width: &TokenStream) -> TokenStream {
+    quote!(if (#width) == 0 { ((#value) as u128) == 0 } else { ((#value) as u128) >> 1 >> ((#width) - 1) == 0 })
+}
+
+/// Same as [`generate_default_inner`], but directly writes each element into `bytes`.
+fn generate_byte_array_default_inner(ty: &Type, bit_order: BitOrder, generics: &Generics) -> TokenStream {
+    use Type::*;
+    match ty {
+        Array(array) => {
+            let len_expr = &array.len;
+            let elem_ty = &*array.elem;
+            // generate the default value code for one array element
+            let value_written = generate_byte_array_default_inner(elem_ty, bit_order, generics);
+            quote! {{
+                let mut i = 0;
+                while i < #len_expr {
+                    #value_written
+                    i += 1;
+                }
+            }}
+        }
+        Path(path) => {
+            let field_size = shared::generate_type_bitsize(ty);
+            let as_int = shared::generate_field_to_base_int(ty, quote!(<#path as ::core::default::Default>::default()));
+            let as_u128 = generate_cast(quote!(as_int), quote!(u128), is_generic_type(ty, generics));
+            quote! {{
+                let as_int = #as_int;
+                bytes = ::bilge::write_bits(bytes, offset, #field_size, #as_u128);
+                offset += #field_size;
+            }}
+        }
+        Tuple(tuple) => {
+            let values_written = in_bit_order(tuple, bit_order).map(|elem| generate_byte_array_default_inner(elem, bit_order, generics));
+            quote! {
+                #( #values_written )*
             }
         }
+        _ => unreachable(()),
     }
 }
 
-fn generate_default_inner(ty: &Type) -> TokenStream {
+fn generate_default_inner(ty: &Type, bit_order: BitOrder, generics: &Generics) -> TokenStream {
     use Type::*;
     match ty {
         // TODO?: we could optimize nested arrays here like in `struct_gen.rs`
@@ -44,7 +276,7 @@ fn generate_default_inner(ty: &Type) -> TokenStream {
             let len_expr = &array.len;
             let elem_ty = &*array.elem;
             // generate the default value code for one array element
-            let value_shifted = generate_default_inner(elem_ty);
+            let value_shifted = generate_default_inner(elem_ty, bit_order, generics);
             quote! {{
                 // constness: iter, array::from_fn, for-loop, range are not const, so we're using while loops
                 let mut acc = 0;
@@ -62,19 +294,23 @@ fn generate_default_inner(ty: &Type) -> TokenStream {
         Path(path) => {
             let field_size = shared::generate_type_bitsize(ty);
             // u2::from(HaveFun::default()).value() as u32;
+            let as_int = shared::generate_field_to_base_int(ty, quote!(<#path as ::core::default::Default>::default()));
+            let as_base_int = generate_cast(
+                quote!(as_int),
+                quote!(<<Self as Bitsized>::ArbitraryInt as ::bilge::BaseInt>::UnderlyingType),
+                is_generic_type(ty, generics),
+            );
             quote! {{
-                let as_int = <#path as Bitsized>::ArbitraryInt::from(<#path as ::core::default::Default>::default()).value();
-                let as_base_int = as_int as <<Self as Bitsized>::ArbitraryInt as Number>::UnderlyingType;
-                let shifted = as_base_int << offset;
+                let as_int = #as_int;
+                let as_base_int = #as_base_int;
+                let shifted = as_base_int.wrapping_shl(offset as u32);
                 offset += #field_size;
                 shifted
             }}
         }
         Tuple(tuple) => {
-            tuple
-                .elems
-                .iter()
-                .map(generate_default_inner)
+            in_bit_order(tuple, bit_order)
+                .map(|elem| generate_default_inner(elem, bit_order, generics))
                 .reduce(|acc, next| quote!(#acc | #next))
                 // `field: (),` will be handled like this:
                 .unwrap_or_else(|| quote!(0))
@@ -83,10 +319,19 @@ fn generate_default_inner(ty: &Type) -> TokenStream {
     }
 }
 
+/// Tuple elements, starting with the one stored at the lowest bits.
+/// The elements of arrays all have the same default value, so they don't need this.
+fn in_bit_order(tuple: &TypeTuple, bit_order: BitOrder) -> Box<dyn Iterator<Item = &Type> + '_> {
+    match bit_order {
+        BitOrder::Lsb0 => Box::new(tuple.elems.iter()),
+        BitOrder::Msb0 => Box::new(tuple.elems.iter().rev()),
+    }
+}
+
 fn parse(item: TokenStream) -> DeriveInput {
     shared::parse_derive(item)
 }
 
-fn analyze(derive_input: &DeriveInput) -> (&Data, TokenStream, &Ident, BitSize, Option<Fallback>) {
-    shared::analyze_derive(derive_input, false)
+fn analyze(derive_input: &DeriveInput) -> (&syn::Data, BitsizeArgs, &Ident, &Generics, Option<Fallback>) {
+    shared::analyze_derive(derive_input, DeriveKind::DefaultBits)
 }
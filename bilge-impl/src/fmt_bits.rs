@@ -1,48 +1,81 @@
 use proc_macro2::{Ident, TokenStream};
+use proc_macro_error::abort_call_site;
 use quote::quote;
-use syn::{punctuated::Iter, Data, DeriveInput, Fields, Variant};
+use syn::{punctuated::Iter, Data, DeriveInput, Fields, Generics, Variant};
 
-use crate::shared::{self, discriminant_assigner::DiscriminantAssigner, fallback::Fallback, unreachable, BitSize};
+use crate::shared::{
+    self, alias, bit_range::BitRange, discriminant_assigner::DiscriminantAssigner, fallback::Fallback, generics, tag::TagLayout, unreachable,
+    BitOrder, BitSize, BitsizeArgs, DeriveKind, Storage,
+};
 
 pub(crate) fn binary(item: TokenStream) -> TokenStream {
     let derive_input = parse(item);
-    let (derive_data, arb_int, name, bitsize, fallback) = analyze(&derive_input);
+    let (
+        derive_data,
+        BitsizeArgs {
+            bitsize, arb_int, bit_order, ..
+        },
+        name,
+        generics,
+        fallback,
+    ) = analyze(&derive_input);
 
     match derive_data {
-        Data::Struct(data) => generate_struct_binary_impl(name, &data.fields),
+        Data::Struct(data) => generate_struct_binary_impl(name, generics, &data.fields, bitsize, bit_order),
+        Data::Enum(data) if TagLayout::of(&derive_input.attrs, data.variants.iter(), bitsize).is_some() => {
+            abort_call_site!("BinaryBits doesn't support tagged enums"; help = "format `u{}::from(value)` instead", bitsize)
+        }
         Data::Enum(data) => generate_enum_binary_impl(name, data.variants.iter(), arb_int, bitsize, fallback),
         _ => unreachable(()),
     }
 }
 
-fn generate_struct_binary_impl(struct_name: &Ident, fields: &Fields) -> TokenStream {
+fn generate_struct_binary_impl(struct_name: &Ident, generics: &Generics, fields: &Fields, bitsize: BitSize, bit_order: BitOrder) -> TokenStream {
+    let storage = Storage::from_bitsize(bitsize);
+    let generics = generics::bitfield_generics(generics, fields, quote!());
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     let write_underscore = quote! { write!(f, "_")?; };
 
-    // fields are printed from most significant to least significant, separated by an underscore
-    let writes = fields
-        .iter()
-        .rev()
-        .map(|field| {
-            let field_size = shared::generate_type_bitsize(&field.ty);
+    let write_extracted = match storage {
+        Storage::ArbitraryInt => quote! {
+            let field_mask = mask >> (struct_size - field_size);
+            let extracted = field_mask & (self.raw() >> first_bit_pos);
+            write!(f, "{:0width$b}", extracted, width = field_size)?;
+        },
+        // fields may be wider than 128 bits here, so we write them bit by bit
+        Storage::ByteArray => quote! {
+            let mut i = field_size;
+            while i > 0 {
+                i -= 1;
+                write!(f, "{}", ::bilge::read_bits(&self.raw(), first_bit_pos + i, 1))?;
+            }
+        },
+    };
 
+    // fields are printed from most significant to least significant, separated by an underscore
+    let segments = generate_segments(fields, bitsize, bit_order);
+    let writes = segments
+        .into_iter()
+        .map(|(first_bit_pos, field_size)| {
             // `extracted` is `field_size` bits of `value`, starting from index `first_bit_pos` (counting from LSB)
             quote! {
                 let field_size = #field_size;
-                let field_mask = mask >> (struct_size - field_size);
-                let first_bit_pos = last_bit_pos - field_size;
-                last_bit_pos -= field_size;
-                let extracted = field_mask & (self.value >> first_bit_pos);
-                write!(f, "{:0width$b}", extracted, width = field_size)?;
+                let first_bit_pos = #first_bit_pos;
+                #write_extracted
             }
         })
         .reduce(|acc, next| quote!(#acc #write_underscore #next));
 
+    let mask = match storage {
+        Storage::ArbitraryInt => quote! { let mask = <Self as Bitsized>::MAX; },
+        Storage::ByteArray => quote!(),
+    };
+
     quote! {
-        impl ::core::fmt::Binary for #struct_name {
+        impl #impl_generics ::core::fmt::Binary for #struct_name #ty_generics #where_clause {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                let struct_size = <#struct_name as Bitsized>::BITS;
-                let mut last_bit_pos = struct_size;
-                let mask = <#struct_name as Bitsized>::MAX;
+                let struct_size = <Self as Bitsized>::BITS;
+                #mask
                 #writes
                 Ok(())
             }
@@ -50,6 +83,50 @@ fn generate_struct_binary_impl(struct_name: &Ident, fields: &Fields) -> TokenStr
     }
 }
 
+/// The offset and size of every field, starting with the most significant one.
+///
+/// With explicit `#[bits(..)]` ranges, fields can be declared in any order and
+/// unlisted bits are printed as well, as if they were reserved fields.
+fn generate_segments(fields: &Fields, bitsize: BitSize, bit_order: BitOrder) -> Vec<(TokenStream, TokenStream)> {
+    let field_offsets = shared::generate_field_offsets(fields, bitsize, bit_order);
+    let field_sizes = fields.iter().map(|field| shared::generate_type_bitsize(&field.ty));
+    // alias fields would print their bits twice
+    let is_alias: Vec<bool> = fields.iter().map(alias::is_alias).collect();
+    fn not_alias<T>((segment, is_alias): (T, &bool)) -> Option<T> {
+        (!is_alias).then_some(segment)
+    }
+
+    let Some(ranges) = shared::bit_range::field_bit_ranges(fields, bitsize, bit_order) else {
+        let segments = field_offsets.into_iter().zip(field_sizes).zip(&is_alias).filter_map(not_alias);
+        return match bit_order {
+            BitOrder::Lsb0 => segments.rev().collect(),
+            BitOrder::Msb0 => segments.collect(),
+        };
+    };
+
+    let mut segments: Vec<_> = ranges
+        .iter()
+        .map(|range| range.offset)
+        .zip(field_offsets.into_iter().zip(field_sizes))
+        .zip(&is_alias)
+        .filter_map(not_alias)
+        .collect();
+    // fill the gaps between fields
+    let mut next_offset = 0;
+    let mut ranges: Vec<_> = ranges.into_iter().zip(&is_alias).filter_map(not_alias).collect();
+    ranges.sort_by_key(|range| range.offset);
+    for range in ranges.iter().chain([&BitRange { offset: bitsize, width: 0 }]) {
+        if range.offset > next_offset {
+            let gap_offset = next_offset as usize;
+            let gap_size = (range.offset - next_offset) as usize;
+            segments.push((next_offset, (quote!(#gap_offset), quote!(#gap_size))));
+        }
+        next_offset = range.offset + range.width;
+    }
+    segments.sort_by_key(|(offset, _)| std::cmp::Reverse(*offset));
+    segments.into_iter().map(|(_, segment)| segment).collect()
+}
+
 fn generate_enum_binary_impl(
     enum_name: &Ident, variants: Iter<Variant>, arb_int: TokenStream, bitsize: BitSize, fallback: Option<Fallback>,
 ) -> TokenStream {
@@ -92,12 +169,12 @@ fn generate_to_int_match_arms(
     variants
         .map(|variant| {
             let variant_name = &variant.ident;
-            let variant_value = assigner.assign_unsuffixed(variant);
-
             if is_value_fallback(variant_name) {
+                // the value is kept in the variant, and this is the last variant, so there's nothing to assign
                 quote! { #enum_name::#variant_name(number) => *number, }
             } else {
-                shared::to_int_match_arm(enum_name, variant_name, &arb_int, variant_value)
+                let variant_value = assigner.assign(variant);
+                shared::to_int_match_arm(enum_name, variant_name, &arb_int, variant_value.to_base_int(&arb_int))
             }
         })
         .collect()
@@ -107,6 +184,6 @@ fn parse(item: TokenStream) -> DeriveInput {
     shared::parse_derive(item)
 }
 
-fn analyze(derive_input: &DeriveInput) -> (&Data, TokenStream, &Ident, BitSize, Option<Fallback>) {
-    shared::analyze_derive(derive_input, false)
+fn analyze(derive_input: &DeriveInput) -> (&syn::Data, BitsizeArgs, &Ident, &Generics, Option<Fallback>) {
+    shared::analyze_derive(derive_input, DeriveKind::DebugBits)
 }
@@ -2,31 +2,59 @@ use itertools::Itertools;
 use proc_macro2::{Ident, TokenStream};
 use proc_macro_error::{abort, abort_call_site};
 use quote::quote;
-use syn::{punctuated::Iter, Data, DeriveInput, Fields, Type, Variant};
+use syn::{punctuated::Iter, Data, DeriveInput, Fields, Generics, Type, Variant};
 
-use crate::shared::{self, discriminant_assigner::DiscriminantAssigner, enum_fills_bitsize, fallback::Fallback, unreachable, BitSize};
+use crate::shared::{
+    self, byte_conversions,
+    discriminant_assigner::{self, DiscriminantAssigner},
+    enum_fills_bitsize,
+    fallback::Fallback,
+    generics, required_value,
+    tag::TagLayout,
+    unreachable, BitSize, BitsizeArgs, DeriveKind, Storage,
+};
 
 pub(super) fn from_bits(item: TokenStream) -> TokenStream {
     let derive_input = parse(item);
-    let (derive_data, arb_int, name, internal_bitsize, fallback) = analyze(&derive_input);
+    let (
+        derive_data,
+        BitsizeArgs {
+            bitsize: internal_bitsize,
+            arb_int,
+            ..
+        },
+        name,
+        generics,
+        fallback,
+    ) = analyze(&derive_input);
+    // field types using a generic parameter need to be filled as well
+    let generics = match derive_data {
+        Data::Struct(struct_data) => generics::bitfield_generics(generics, &struct_data.fields, quote!(+ ::bilge::Filled)),
+        _ => generics.clone(),
+    };
+    let byte_conversions = byte_conversions::generate_byte_conversions(name, &derive_input.vis, &generics, internal_bitsize, &arb_int, false);
     let expanded = match &derive_data {
-        Data::Struct(struct_data) => generate_struct(arb_int, name, &struct_data.fields),
+        Data::Struct(struct_data) => generate_struct(arb_int, name, &generics, &struct_data.fields, Storage::from_bitsize(internal_bitsize)),
         Data::Enum(enum_data) => {
             let variants = enum_data.variants.iter();
-            let match_arms = analyze_enum(variants, name, internal_bitsize, fallback.as_ref(), &arb_int);
-            generate_enum(arb_int, name, match_arms, fallback)
+            if let Some(tag_layout) = TagLayout::of(&derive_input.attrs, variants.clone(), internal_bitsize) {
+                generate_tagged_enum(arb_int, name, variants, tag_layout)
+            } else {
+                let match_arms = analyze_enum(variants, name, internal_bitsize, fallback.as_ref(), &arb_int);
+                generate_enum(arb_int, name, match_arms, fallback)
+            }
         }
         _ => unreachable(()),
     };
-    generate_common(expanded)
+    generate_common(expanded, byte_conversions)
 }
 
 fn parse(item: TokenStream) -> DeriveInput {
     shared::parse_derive(item)
 }
 
-fn analyze(derive_input: &DeriveInput) -> (&syn::Data, TokenStream, &Ident, BitSize, Option<Fallback>) {
-    shared::analyze_derive(derive_input, false)
+fn analyze(derive_input: &DeriveInput) -> (&syn::Data, BitsizeArgs, &Ident, &Generics, Option<Fallback>) {
+    shared::analyze_derive(derive_input, DeriveKind::FromBits)
 }
 
 fn analyze_enum(
@@ -34,7 +62,7 @@ fn analyze_enum(
 ) -> (Vec<TokenStream>, Vec<TokenStream>) {
     validate_enum_variants(variants.clone(), fallback);
 
-    let enum_is_filled = enum_fills_bitsize(internal_bitsize, variants.len());
+    let enum_is_filled = enum_fills_bitsize(internal_bitsize, discriminant_assigner::value_count(variants.clone()));
     if !enum_is_filled && fallback.is_none() {
         abort_call_site!("enum doesn't fill its bitsize"; help = "you need to use `#[derive(TryFromBits)]` instead, or specify one of the variants as #[fallback]")
     }
@@ -64,20 +92,21 @@ fn analyze_enum(
     variants
         .map(|variant| {
             let variant_name = &variant.ident;
-            let variant_value = assigner.assign_unsuffixed(variant);
+            if is_value_fallback(variant_name) {
+                // the value is kept in the variant, and this is the last variant, so there's nothing to assign
+                return (quote!(), quote! { #name::#variant_name(number) => number, });
+            }
+            let variant_value = assigner.assign(variant);
 
             let from_int_match_arm = if is_fallback(variant_name) {
                 // this value will be handled by the catch-all arm
                 quote!()
             } else {
-                quote! { #variant_value => Self::#variant_name, }
+                let pattern = variant_value.pattern();
+                quote! { #pattern => Self::#variant_name, }
             };
 
-            let to_int_match_arm = if is_value_fallback(variant_name) {
-                quote! { #name::#variant_name(number) => number, }
-            } else {
-                shared::to_int_match_arm(name, variant_name, arb_int, variant_value)
-            };
+            let to_int_match_arm = shared::to_int_match_arm(name, variant_name, arb_int, variant_value.to_base_int(arb_int));
 
             (from_int_match_arm, to_int_match_arm)
         })
@@ -119,14 +148,44 @@ fn generate_enum(
     }
 }
 
+fn generate_tagged_enum(arb_int: TokenStream, enum_type: &Ident, variants: Iter<Variant>, tag_layout: TagLayout) -> TokenStream {
+    tag_layout.validate_filled(variants.clone());
+
+    let const_ = if cfg!(feature = "nightly") { quote!(const) } else { quote!() };
+
+    let mut assumes = Vec::new();
+    for variant in variants.clone() {
+        for field in &variant.fields {
+            generate_filled_check_for(&field.ty, &mut assumes)
+        }
+    }
+    let assumes = assumes.into_iter().unique_by(TokenStream::to_string);
+
+    let from_int = tag_layout.generate_from_int(variants.clone(), false);
+    let to_int_match_arms = tag_layout.generate_to_int_match_arms(enum_type, variants, &arb_int);
+    let from_enum_impl = shared::generate_from_enum_impl(&arb_int, enum_type, to_int_match_arms, &const_);
+
+    quote! {
+        impl #const_ ::core::convert::From<#arb_int> for #enum_type {
+            fn from(number: #arb_int) -> Self {
+                #( #assumes )*
+                #from_int
+            }
+        }
+        #from_enum_impl
+    }
+}
+
 /// a type is considered "filled" if it implements `Bitsized` with `BITS == N`,
 /// and additionally is allowed to have any unsigned value from `0` to `2^N - 1`.
 /// such a type can then safely implement `From<uN>`.
 /// a filled type automatically implements the trait `Filled` thanks to a blanket impl.
 /// the check generated by this function will prevent compilation if `ty` is not `Filled`.
-fn generate_filled_check_for(ty: &Type, vec: &mut Vec<TokenStream>) {
+pub(crate) fn generate_filled_check_for(ty: &Type, vec: &mut Vec<TokenStream>) {
     use Type::*;
     match ty {
+        // `i8` up to `i128` can't implement `From<uN>`, but every bit pattern is valid for them
+        Path(_) if shared::is_signed_primitive(ty) => (),
         Path(_) => {
             let assume = quote! { ::bilge::assume_filled::<#ty>(); };
             vec.push(assume);
@@ -141,8 +200,13 @@ fn generate_filled_check_for(ty: &Type, vec: &mut Vec<TokenStream>) {
     }
 }
 
-fn generate_struct(arb_int: TokenStream, struct_type: &Ident, fields: &Fields) -> TokenStream {
-    let const_ = if cfg!(feature = "nightly") { quote!(const) } else { quote!() };
+fn generate_struct(arb_int: TokenStream, struct_type: &Ident, generics: &Generics, fields: &Fields, storage: Storage) -> TokenStream {
+    if let Some(field) = fields.iter().find(|field| required_value::required_bits(field).is_some()) {
+        abort!(field, "FromBits doesn't support fields with a required value"; help = "other bits would be accepted as well, use `#[derive(TryFromBits)]` instead")
+    }
+
+    let const_ = generics::generate_const(generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let mut assumes = Vec::new();
     for field in fields {
@@ -152,24 +216,29 @@ fn generate_struct(arb_int: TokenStream, struct_type: &Ident, fields: &Fields) -
     // a single check per type is enough, so the checks can be deduped
     let assumes = assumes.into_iter().unique_by(TokenStream::to_string);
 
+    let mask_unused_bits = shared::generate_unused_bits_mask(storage);
+
     quote! {
-        impl #const_ ::core::convert::From<#arb_int> for #struct_type {
+        impl #impl_generics #const_ ::core::convert::From<#arb_int> for #struct_type #ty_generics #where_clause {
             fn from(value: #arb_int) -> Self {
                 #( #assumes )*
-                Self { value }
+                #mask_unused_bits
+                // every bit pattern is valid, as checked by the `assume_filled` calls
+                unsafe { Self::from_raw_unchecked(value) }
             }
         }
-        impl #const_ ::core::convert::From<#struct_type> for #arb_int {
-            fn from(value: #struct_type) -> Self {
-                value.value
+        impl #impl_generics #const_ ::core::convert::From<#struct_type #ty_generics> for #arb_int #where_clause {
+            fn from(value: #struct_type #ty_generics) -> Self {
+                value.raw()
             }
         }
     }
 }
 
-fn generate_common(expanded: TokenStream) -> TokenStream {
+fn generate_common(expanded: TokenStream, byte_conversions: TokenStream) -> TokenStream {
     quote! {
         #expanded
+        #byte_conversions
     }
 }
 
@@ -11,15 +11,54 @@ mod from_bits;
 #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
 mod serde_bits;
 mod try_from_bits;
+mod visit_bits;
 
 mod shared;
 
 /// Defines the bitsize of a struct or an enum.
 ///
 /// e.g. `#[bitsize(4)]` represents the item as a u4, which is UInt<u8, 4> underneath.
-/// The size of structs is currently limited to 128 bits.
-/// The size of enums is limited to 64 bits.
-/// Please open an issue if you have a usecase for bigger bitfields.
+///
+/// # Storage
+///
+/// - Structs up to 128 bits are stored as `uN`, `#[bitsize(0)]` as `u0`.
+/// - Bigger structs, up to 4096 bits, are stored as a little-endian byte array, e.g. `#[bitsize(260)]` as `[u8; 33]`.
+///   They can't be used as fields of other bitfields (yet).
+/// - `raw()` returns the stored value, `unsafe fn from_raw_unchecked()` takes it without checking.
+/// - Structs can have type and const parameters. Their size is checked once for every instantiation.
+///
+/// # Options
+///
+/// - `msb0`, e.g. `#[bitsize(32, msb0)]`: the first field ends at the most significant bit, instead of starting at the least significant one.
+/// - `sealed`: the struct's `value` can't be written without `unsafe`.
+/// - `#[non_exhaustive]` structs need `TryFromBits`, so bits can become valid later.
+///   Other crates can't call `new` or `new_preserving`, since their arguments change when fields are added.
+///
+/// # Fields
+///
+/// Every field gets a getter, a setter `set_<field>()` and a chainable `with_<field>()`.
+/// Their positions are the constants `<FIELD>_OFFSET`, `<FIELD>_WIDTH` and `<FIELD>_MASK`,
+/// and `Bitfield::get_raw()` and `set_raw()` access them by name. `Bitsized::FIELDS` lists all of them.
+/// `new_preserving(self, ..)` takes the same arguments as `new`, but copies all other bits from `self`.
+/// Signed fields like `i12` are sign-extended by their getters. `()` and `PhantomData<T>` take up 0 bits.
+///
+/// - `#[bits(4..=7)]`: an explicit position, which all fields need then. Unlisted bits are reserved.
+///   With `msb0`, these bit numbers count from the most significant bit.
+/// - `#[alias]` or `#[view]`: another view of bits, starting at bit 0 again. Not an argument of `new`.
+/// - `#[read_only]`: no setter, not an argument of `new`.
+/// - `#[write_only]`: no getter, skipped by `DebugBits`.
+/// - `#[w1c]` or `#[w1s]`: `clear_<field>()` or `set_<field>()` instead of a setter. Every other setter and `write_value()` write 0 to them.
+/// - `#[reserved(0)]` or `#[must_be(0b1)]`: `new` writes these bits, the setter is `unsafe` and `TryFrom` rejects other bits,
+///   so the struct can't derive `FromBits`.
+/// - fields named `reserved` or `padding`: not an argument of `new`, which writes 0, and the setter is `unsafe`.
+///
+/// # Enums
+///
+/// - Enums are limited to 128 bits. Above 64 bits, `as` doesn't give a variant's bits, use `From` instead.
+/// - Discriminants can be constant expressions like `OP_ADD` or `1 << 4`, which are checked at compile time.
+/// - `#[bits(4..=7)]` or `#[bits(0 | 1)]`: a variant with several values, which is converted back to the first one.
+/// - `#[tag(0b01)]` on every variant: variants can carry a payload, which holds all other bits.
+///   The tag is stored in the least significant bits, or at `#[tag_bits(30..=31)]` on the enum.
 #[proc_macro_error]
 #[proc_macro_attribute]
 pub fn bitsize(args: TokenStream, item: TokenStream) -> TokenStream {
@@ -38,8 +77,10 @@ pub fn bitsize_internal(args: TokenStream, item: TokenStream) -> TokenStream {
 ///
 /// This should be used when your enum or enums nested in
 /// a struct don't fill their given `bitsize`.
+///
+/// Also generates `try_from_le_bytes`, `try_from_be_bytes`, `to_le_bytes` and `to_be_bytes`.
 #[proc_macro_error]
-#[proc_macro_derive(TryFromBits, attributes(bitsize_internal, fallback))]
+#[proc_macro_derive(TryFromBits, attributes(bitsize_internal, bits, fallback, tag, tag_bits))]
 pub fn derive_try_from_bits(item: TokenStream) -> TokenStream {
     try_from_bits::try_from_bits(item.into()).into()
 }
@@ -49,8 +90,11 @@ pub fn derive_try_from_bits(item: TokenStream) -> TokenStream {
 /// This should be used when your enum or enums nested in
 /// a struct fill their given `bitsize` or if you're not
 /// using enums.
+///
+/// Also generates `from_le_bytes`, `from_be_bytes`, `to_le_bytes` and `to_be_bytes`,
+/// which use `[u8; N]` with `N` being the bitsize divided by 8, rounded up.
 #[proc_macro_error]
-#[proc_macro_derive(FromBits, attributes(bitsize_internal, fallback))]
+#[proc_macro_derive(FromBits, attributes(bitsize_internal, bits, fallback, tag, tag_bits))]
 pub fn derive_from_bits(item: TokenStream) -> TokenStream {
     from_bits::from_bits(item.into()).into()
 }
@@ -72,12 +116,25 @@ pub fn derive_binary_bits(item: TokenStream) -> TokenStream {
 }
 
 /// Generate an `impl core::default::Default` for bitfield structs.
+///
+/// Fields use the `Default` of their type, unless they have a value like `#[default = u3::new(5)]`
+/// or bits like `#[default(0b101)]`. `#[reset_value(0x1F00)]` on the struct gives the bits of the whole bitfield.
 #[proc_macro_error]
 #[proc_macro_derive(DefaultBits)]
 pub fn derive_default_bits(item: TokenStream) -> TokenStream {
     default_bits::default_bits(item.into()).into()
 }
 
+/// Generate an `impl bilge::VisitBits` for bitfields.
+///
+/// Structs visit all their readable fields, recursing into nested bitfield structs, tuples and arrays.
+/// Nested structs and enums need this derive as well.
+#[proc_macro_error]
+#[proc_macro_derive(VisitBits, attributes(bitsize_internal))]
+pub fn derive_visit_bits(item: TokenStream) -> TokenStream {
+    visit_bits::visit_bits(item.into()).into()
+}
+
 /// Generate an `impl serde::Serialize` for bitfield structs.
 ///
 /// Please use normal #[derive(Serialize)] for enums.
@@ -2,15 +2,36 @@ use itertools::MultiUnzip;
 use proc_macro2::{Ident, TokenStream};
 use proc_macro_error::abort_call_site;
 use quote::quote;
-use syn::{Data, Field, Fields};
+use syn::{parse_quote, Data, Field, Fields};
 
-use crate::shared::{self, unreachable};
+use crate::shared::{self, alias, field_access::FieldAccess, generics, required_value, unreachable};
 
 fn filter_not_reserved_or_padding(field: &&Field) -> bool {
     let field_name_string = field.ident.as_ref().unwrap().to_string();
     !field_name_string.starts_with("reserved_") && !field_name_string.starts_with("padding_")
 }
 
+/// Only fields which are arguments of `new` can be deserialized. Alias fields only show bits again,
+/// and fields with a required value are skipped like reserved fields.
+fn filter_writable(field: &&Field) -> bool {
+    FieldAccess::of(field).is_writable() && !alias::is_alias(field) && required_value::required_bits(field).is_none()
+}
+
+/// Only fields with a getter can be serialized. To deserialize what was serialized,
+/// both only use the fields which are also arguments of `new`.
+fn filter_readable(field: &&Field) -> bool {
+    FieldAccess::of(field).is_readable() && filter_writable(field)
+}
+
+/// The arguments of `new`. Write-only fields aren't deserialized, so they are zeroed, which is their default.
+fn new_arg(field: &Field, field_ident: &Ident) -> TokenStream {
+    if FieldAccess::of(field).is_readable() {
+        quote!(#field_ident)
+    } else {
+        quote!(::core::default::Default::default())
+    }
+}
+
 pub(super) fn serialize_bits(item: TokenStream) -> TokenStream {
     let derive_input = shared::parse_derive(item);
     let name = &derive_input.ident;
@@ -21,15 +42,19 @@ pub(super) fn serialize_bits(item: TokenStream) -> TokenStream {
         Data::Union(_) => unreachable(()),
     };
 
+    let generics = generics::bitfield_generics(&derive_input.generics, &struct_data.fields, quote!(+ ::serde::Serialize));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
     let serialize_impl = match struct_data.fields {
         Fields::Named(fields) => {
-            let calls = fields.named.iter().filter(filter_not_reserved_or_padding).map(|f| {
+            let fields = || fields.named.iter().filter(filter_not_reserved_or_padding).filter(filter_readable);
+            let calls = fields().map(|f| {
                 // We can unwrap since this is a named field
                 let call = f.ident.as_ref().unwrap();
                 let name = call.to_string();
                 quote!(state.serialize_field(#name, &self.#call())?;)
             });
-            let len = fields.named.iter().filter(filter_not_reserved_or_padding).count();
+            let len = fields().count();
             quote! {
                 use ::serde::ser::SerializeStruct;
                 let mut state = serializer.serialize_struct(#name_str, #len)?;
@@ -39,11 +64,11 @@ pub(super) fn serialize_bits(item: TokenStream) -> TokenStream {
             }
         }
         Fields::Unnamed(fields) => {
-            let calls = fields.unnamed.iter().enumerate().map(|(i, _)| {
+            let calls = fields.unnamed.iter().enumerate().filter(|(_, f)| filter_readable(f)).map(|(i, _)| {
                 let call: Ident = syn::parse_str(&format!("val_{}", i)).unwrap_or_else(unreachable);
                 quote!(state.serialize_field(&self.#call())?;)
             });
-            let len = fields.unnamed.len();
+            let len = fields.unnamed.iter().filter(filter_readable).count();
             quote! {
                 use serde::ser::SerializeTupleStruct;
                 let mut state = serializer.serialize_tuple_struct(#name_str, #len)?;
@@ -52,11 +77,15 @@ pub(super) fn serialize_bits(item: TokenStream) -> TokenStream {
                 state.end()
             }
         }
-        Fields::Unit => todo!("this is a unit struct, which is not supported right now"),
+        // `#[bitsize]` turns these into `{}` structs, so they're serialized like one
+        Fields::Unit => quote! {
+            use ::serde::ser::SerializeStruct;
+            serializer.serialize_struct(#name_str, 0)?.end()
+        },
     };
 
     quote! {
-        impl ::serde::Serialize for #name {
+        impl #impl_generics ::serde::Serialize for #name #ty_generics #where_clause {
             fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
             where
                 S: ::serde::Serializer,
@@ -108,7 +137,30 @@ pub(super) fn deserialize_bits(item: TokenStream) -> TokenStream {
         Data::Union(_) => unreachable(()),
     };
 
-    let should_have_visit_map = matches!(struct_data.fields, Fields::Named(_));
+    let should_have_visit_map = matches!(struct_data.fields, Fields::Named(_) | Fields::Unit);
+
+    let (_, ty_generics, _) = derive_input.generics.split_for_impl();
+    let mut de_generics = generics::bitfield_generics(&derive_input.generics, &struct_data.fields, quote!(+ ::serde::Deserialize<'de>));
+    de_generics.params.insert(0, parse_quote!('de));
+    let (de_impl_generics, _, de_where_clause) = de_generics.split_for_impl();
+
+    let new_args: Vec<TokenStream> = match &struct_data.fields {
+        Fields::Named(fields) => fields
+                .named
+                .iter()
+                .filter(filter_not_reserved_or_padding)
+                .filter(filter_writable)
+                .map(|f| new_arg(f, f.ident.as_ref().unwrap()))
+                .collect(),
+        Fields::Unnamed(fields) => fields
+                .unnamed
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| filter_writable(f))
+                .map(|(n, f)| new_arg(f, &syn::parse_str(&format!("val_{}", n)).unwrap_or_else(unreachable)))
+                .collect(),
+        Fields::Unit => vec![],
+    };
 
     let (
         field_names,
@@ -124,6 +176,7 @@ pub(super) fn deserialize_bits(item: TokenStream) -> TokenStream {
                 .named
                 .iter()
                 .filter(filter_not_reserved_or_padding)
+                .filter(filter_readable)
                 .enumerate()
                 .map(|(i, f)| deserialize_field_parts(i, f.ident.as_ref().unwrap()))
                 .multiunzip(),
@@ -131,9 +184,11 @@ pub(super) fn deserialize_bits(item: TokenStream) -> TokenStream {
                 .unnamed
                 .iter()
                 .enumerate()
-                .map(|(i, _)| deserialize_field_parts(i, &syn::parse_str(&format!("val_{}", i)).unwrap_or_else(unreachable)))
+                .filter(|(_, f)| filter_readable(f))
+                .enumerate()
+                .map(|(i, (n, _))| deserialize_field_parts(i, &syn::parse_str(&format!("val_{}", n)).unwrap_or_else(unreachable)))
                 .multiunzip(),
-        Fields::Unit => todo!("this is a unit struct, which is not supported right now"),
+        Fields::Unit => Default::default(),
     };
 
     if field_expecting.len() > 1 {
@@ -147,20 +202,20 @@ pub(super) fn deserialize_bits(item: TokenStream) -> TokenStream {
                 V: ::serde::de::MapAccess<'de>,
             {
                 #(#field_visit_map_init)*
-                while let Some(key) = map.next_key()? {
+                while let Some(key) = map.next_key::<Field>()? {
                     match key {
                         #(#field_visit_map_match)*
                     }
                 }
                 #(#field_visit_map_check)*
-                Ok(#name::new(#(#field_names)*))
+                Ok(Self::Value::new(#( #new_args ),*))
             })
     } else {
         quote!()
     };
 
     quote! {
-        impl<'de> ::serde::Deserialize<'de> for #name {
+        impl #de_impl_generics ::serde::Deserialize<'de> for #name #ty_generics #de_where_clause {
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
             where
                 D: ::serde::Deserializer<'de>,
@@ -196,10 +251,11 @@ pub(super) fn deserialize_bits(item: TokenStream) -> TokenStream {
                     }
                 }
 
-                struct Visitor;
+                // items in this function can't use its generic parameters, so the visitor gets the bitfield type as its own parameter
+                struct Visitor<Bits>(::core::marker::PhantomData<fn() -> Bits>);
 
-                impl<'de> ::serde::de::Visitor<'de> for Visitor {
-                    type Value = #name;
+                impl #de_impl_generics ::serde::de::Visitor<'de> for Visitor<#name #ty_generics> #de_where_clause {
+                    type Value = #name #ty_generics;
 
                     fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                         formatter.write_str(#struct_name_str)
@@ -210,14 +266,14 @@ pub(super) fn deserialize_bits(item: TokenStream) -> TokenStream {
                         V: ::serde::de::SeqAccess<'de>,
                     {
                         #(#field_visit_seq)*
-                        Ok(Self::Value::new(#(#field_names)*))
+                        Ok(Self::Value::new(#( #new_args ),*))
                     }
 
                     #visit_map
                 }
 
                 const FIELDS: &'static [&'static str] = &[#(#field_name_strings)*];
-                deserializer.deserialize_struct(#name_str, FIELDS, Visitor)
+                deserializer.deserialize_struct(#name_str, FIELDS, Visitor::<Self>(::core::marker::PhantomData))
             }
         }
     }
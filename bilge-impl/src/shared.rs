@@ -1,63 +1,152 @@
+pub mod alias;
+pub mod bit_range;
+pub mod byte_conversions;
+pub mod default_value;
 pub mod discriminant_assigner;
 pub mod fallback;
+pub mod field_access;
+pub mod generics;
+pub mod required_value;
+pub mod tag;
 pub mod util;
+pub mod valid_bits;
 
 use fallback::{fallback_variant, Fallback};
-use proc_macro2::{Ident, Literal, TokenStream};
+use proc_macro2::{Ident, TokenStream, TokenTree};
 use proc_macro_error::{abort, abort_call_site};
-use quote::quote;
-use syn::{Attribute, DeriveInput, LitInt, Meta, Type};
+use quote::{quote, quote_spanned};
+use syn::{parse::Parser, punctuated::Punctuated, spanned::Spanned, Attribute, DeriveInput, Field, Fields, Generics, LitInt, Meta, Token, Type};
 use util::PathExt;
 
 /// As arbitrary_int is limited to basic rust primitives, the maximum is u128.
-/// Is there a true usecase for bitfields above this size?
 /// This would also be change-worthy when rust starts supporting LLVM's arbitrary integers.
-pub const MAX_STRUCT_BIT_SIZE: BitSize = 128;
-/// As `#[repr(u128)]` is unstable and currently no real usecase for higher sizes exists, the maximum is u64.
-pub const MAX_ENUM_BIT_SIZE: BitSize = 64;
-pub type BitSize = u8;
+pub const MAX_ARBITRARY_INT_BIT_SIZE: BitSize = 128;
+/// Bitfields above `MAX_ARBITRARY_INT_BIT_SIZE` are stored in a byte array.
+/// The limit is somewhat arbitrary, but covers things like NVMe and PCIe descriptors.
+pub const MAX_STRUCT_BIT_SIZE: BitSize = 4096;
+/// Enums are converted by matching on their variants, so they are limited by arbitrary_int, not by `#[repr]`.
+pub const MAX_ENUM_BIT_SIZE: BitSize = MAX_ARBITRARY_INT_BIT_SIZE;
+pub type BitSize = u16;
+
+/// How the bits of a bitfield are stored inside the generated struct.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Storage {
+    /// `value: uN`, used for bitfields up to `MAX_ARBITRARY_INT_BIT_SIZE` bits.
+    ArbitraryInt,
+    /// `value: [u8; N]`, used for everything wider.
+    /// Bit `i` of the bitfield is bit `i % 8` of `value[i / 8]`, so the bytes are little-endian.
+    ByteArray,
+}
+
+impl Storage {
+    pub fn from_bitsize(bitsize: BitSize) -> Storage {
+        if bitsize > MAX_ARBITRARY_INT_BIT_SIZE {
+            Storage::ByteArray
+        } else {
+            Storage::ArbitraryInt
+        }
+    }
+}
+
+/// Where the first field of a struct is placed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    /// The first field starts at the least significant bit. This is the default.
+    Lsb0,
+    /// The first field ends at the most significant bit, like many datasheets and RFCs list them.
+    /// Elements of tuples and arrays are reversed in the same way.
+    Msb0,
+}
+
+/// Everything given to `#[bitsize(N, options...)]`, which is passed on to `#[bitsize_internal]`.
+pub struct BitsizeArgs {
+    pub bitsize: BitSize,
+    pub arb_int: TokenStream,
+    pub bit_order: BitOrder,
+    /// With `sealed`, the struct's value is stored as `::bilge::Sealed`, so it can't be changed by accident.
+    pub sealed: bool,
+}
+
+/// The amount of bytes needed to hold `bitsize` bits.
+pub fn byte_len(bitsize: BitSize) -> usize {
+    (bitsize as usize + 7) / 8
+}
 
 pub(crate) fn parse_derive(item: TokenStream) -> DeriveInput {
     syn::parse2(item).unwrap_or_else(unreachable)
 }
 
-// allow since we want `if try_from` blocks to stand out
-#[allow(clippy::collapsible_if)]
-pub(crate) fn analyze_derive(derive_input: &DeriveInput, try_from: bool) -> (&syn::Data, TokenStream, &Ident, BitSize, Option<Fallback>) {
+/// The derive macro which analyzes the item, since some of them only support some items.
+// allow since the variants are named after the derives
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DeriveKind {
+    FromBits,
+    TryFromBits,
+    DefaultBits,
+    DebugBits,
+    VisitBits,
+}
+
+pub(crate) fn analyze_derive(derive_input: &DeriveInput, kind: DeriveKind) -> (&syn::Data, BitsizeArgs, &Ident, &Generics, Option<Fallback>) {
     let DeriveInput {
         attrs,
         ident,
-        // generics,
+        generics,
         data,
         ..
     } = derive_input;
 
-    if !try_from {
-        if attrs.iter().any(is_non_exhaustive_attribute) {
-            abort_call_site!("Item can't be FromBits and non_exhaustive"; help = "remove #[non_exhaustive] or derive(FromBits) here")
-        }
-    } else {
-        // currently not allowed, would need some thinking:
-        if let syn::Data::Struct(_) = data {
-            if attrs.iter().any(is_non_exhaustive_attribute) {
-                abort_call_site!("Using #[non_exhaustive] on structs is currently not supported"; help = "open an issue on our repository if needed")
-            }
-        }
+    // with TryFromBits, new bits can become valid later. non_exhaustive structs are handled in `bitsize_internal`
+    if kind == DeriveKind::FromBits && attrs.iter().any(is_non_exhaustive_attribute) {
+        abort_call_site!("Item can't be FromBits and non_exhaustive"; help = "remove #[non_exhaustive] or derive(FromBits) here")
     }
 
-    // parsing the #[bitsize_internal(num)] attribute macro
+    // parsing the #[bitsize_internal(num, options...)] attribute macro
     let args = attrs
         .iter()
         .find_map(bitsize_internal_arg)
         .unwrap_or_else(|| abort_call_site!("add #[bitsize] attribute above your derive attribute"));
-    let (bitsize, arb_int) = bitsize_and_arbitrary_int_from(args);
+    let args = parse_bitsize_args(args);
 
-    let fallback = fallback_variant(data, bitsize);
-    if fallback.is_some() && try_from {
+    let fallback = fallback_variant(data, args.bitsize);
+    if fallback.is_some() && kind == DeriveKind::TryFromBits {
         abort_call_site!("fallback is not allowed with `TryFromBits`"; help = "use `#[derive(FromBits)]` or remove this `#[fallback]`")
     }
 
-    (data, arb_int, ident, bitsize, fallback)
+    (data, args, ident, generics, fallback)
+}
+
+/// Parses `N, options...`, e.g. `32, msb0`.
+pub fn parse_bitsize_args(args: TokenStream) -> BitsizeArgs {
+    let mut args = args.into_iter();
+    let is_comma = |token: &TokenTree| matches!(token, TokenTree::Punct(punct) if punct.as_char() == ',');
+    let bitsize_arg: TokenStream = args.by_ref().take_while(|token| !is_comma(token)).collect();
+    let options: TokenStream = args.collect();
+
+    let (bitsize, arb_int) = bitsize_and_arbitrary_int_from(bitsize_arg);
+
+    let options = Punctuated::<Ident, Token![,]>::parse_terminated
+        .parse2(options.clone())
+        .unwrap_or_else(|_| abort!(options, "options are not valid"; help = "options are given like this: `#[bitsize(32, msb0)]`"));
+
+    let mut bit_order = BitOrder::Lsb0;
+    let mut sealed = false;
+    for option in options {
+        match option.to_string().as_str() {
+            "lsb0" => bit_order = BitOrder::Lsb0,
+            "msb0" => bit_order = BitOrder::Msb0,
+            "sealed" => sealed = true,
+            _ => abort!(option, "unknown option `{}`", option; help = "currently, `lsb0`, `msb0` and `sealed` are allowed"),
+        }
+    }
+
+    BitsizeArgs {
+        bitsize,
+        arb_int,
+        bit_order,
+        sealed,
+    }
 }
 
 // If we want to support bitsize(u4) besides bitsize(4), do that here.
@@ -65,12 +154,18 @@ pub fn bitsize_and_arbitrary_int_from(bitsize_arg: TokenStream) -> (BitSize, Tok
     let bitsize: LitInt = syn::parse2(bitsize_arg.clone())
         .unwrap_or_else(|_| abort!(bitsize_arg, "attribute value is not a number"; help = "you need to define the size like this: `#[bitsize(32)]`"));
     // without postfix
-    let bitsize = bitsize
-        .base10_parse()
-        .ok()
-        .filter(|&n| n != 0 && n <= MAX_STRUCT_BIT_SIZE)
-        .unwrap_or_else(|| abort!(bitsize_arg, "attribute value is not a valid number"; help = "currently, numbers from 1 to {} are allowed", MAX_STRUCT_BIT_SIZE));
-    let arb_int = syn::parse_str(&format!("u{bitsize}")).unwrap_or_else(unreachable);
+    let bitsize = bitsize.base10_parse().ok().filter(|&n| n <= MAX_STRUCT_BIT_SIZE).unwrap_or_else(
+        || abort!(bitsize_arg, "attribute value is not a valid number"; help = "currently, numbers from 0 to {} are allowed", MAX_STRUCT_BIT_SIZE),
+    );
+    let arb_int = match Storage::from_bitsize(bitsize) {
+        // `arbitrary_int` has no `u0`, so zero-width bitfields use the one from bilge
+        Storage::ArbitraryInt if bitsize == 0 => quote!(::bilge::u0),
+        Storage::ArbitraryInt => syn::parse_str(&format!("u{bitsize}")).unwrap_or_else(unreachable),
+        Storage::ByteArray => {
+            let len = byte_len(bitsize);
+            quote!([u8; #len])
+        }
+    };
     (bitsize, arb_int)
 }
 
@@ -98,6 +193,137 @@ pub fn generate_type_bitsize(ty: &Type) -> TokenStream {
     }
 }
 
+/// Asserts that the fields fill the declared bitsize, or with explicit `#[bits(..)]` ranges, that each field fills its range.
+///
+/// These are expressions, to be evaluated in a const context.
+pub fn generate_size_checks(fields: &Fields, args: &BitsizeArgs) -> Vec<TokenStream> {
+    let mut checks = generate_nested_width_checks(fields);
+    checks.extend(generate_layout_checks(fields, args));
+    checks
+}
+
+/// Bitfields over 128 bits are stored as byte arrays, which can't be read or written like the other fields.
+fn generate_nested_width_checks(fields: &Fields) -> Vec<TokenStream> {
+    fn path_types<'a>(ty: &'a Type, paths: &mut Vec<&'a Type>) {
+        match ty {
+            Type::Tuple(tuple) => tuple.elems.iter().for_each(|elem| path_types(elem, paths)),
+            Type::Array(array) => path_types(&array.elem, paths),
+            _ => paths.push(ty),
+        }
+    }
+    let mut paths = vec![];
+    for field in fields {
+        path_types(&field.ty, &mut paths);
+    }
+    paths
+        .into_iter()
+        .filter(|ty| !is_always_filled(ty) && !is_signed_primitive(ty))
+        .map(|ty| {
+            let max = MAX_ARBITRARY_INT_BIT_SIZE as usize;
+            // spanned, so the error points to the field
+            quote_spanned! {ty.span()=>
+                assert!(<#ty as Bitsized>::BITS <= #max, "bitfields wider than 128 bits can't be used as fields")
+            }
+        })
+        .collect()
+}
+
+fn generate_layout_checks(fields: &Fields, args: &BitsizeArgs) -> Vec<TokenStream> {
+    if let Some(ranges) = bit_range::field_bit_ranges(fields, args.bitsize, args.bit_order) {
+        // unlisted bits are reserved, so we only need to check each field against its range
+        fields
+            .iter()
+            .zip(ranges)
+            .map(|(field, range)| {
+                let field_size = generate_type_bitsize(&field.ty);
+                let width = range.width as usize;
+                // spanned, so the error points to the field
+                quote_spanned! {field.ty.span()=>
+                    assert!((#field_size) == (#width), "field size and its bit range differ")
+                }
+            })
+            .collect()
+    } else {
+        let declared_bitsize = args.bitsize as usize;
+        let (aliases, others): (Vec<&Field>, Vec<&Field>) = fields.iter().partition(|field| alias::is_alias(field));
+        let computed_bitsize = others.iter().fold(quote!(0), |acc, next| {
+            let field_size = generate_type_bitsize(&next.ty);
+            quote!(#acc + #field_size)
+        });
+        // constness: when we get const blocks evaluated at compile time, add a const computed_bitsize
+        let check = quote! {
+            assert!(
+                (#computed_bitsize) == (#declared_bitsize),
+                concat!("struct size and declared bit size differ: ",
+                // stringify!(#computed_bitsize),
+                " != ",
+                stringify!(#declared_bitsize))
+            )
+        };
+        // alias fields only need to stay inside the struct
+        let alias_checks = aliases.into_iter().map(|field| {
+            let alias_bitsize = generate_type_bitsize(&field.ty);
+            quote_spanned! {field.ty.span()=>
+                assert!((#alias_bitsize) <= (#declared_bitsize), "alias field exceeds the declared bit size")
+            }
+        });
+        [check].into_iter().chain(alias_checks).collect()
+    }
+}
+
+/// Generates the offset of every field, meaning the position of its least significant bit.
+///
+/// With `BitOrder::Lsb0`, this is the sum of all previous field sizes:
+/// ```ignore
+/// struct Example { field1: u8, field2: u4, field3: u4 }
+/// field1 -> 0
+/// field2 -> 0 + 8     =  8
+/// field3 -> 0 + 8 + 4 = 12
+/// ```
+/// With `BitOrder::Msb0`, this is the sum of all following field sizes:
+/// ```ignore
+/// field1 -> 0 + 4 + 4 = 8
+/// field2 -> 0 + 4     = 4
+/// field3 -> 0
+/// ```
+/// `#[alias]` fields are skipped in these sums, and every one of them starts at bit 0 again
+/// (or with `BitOrder::Msb0`, at the most significant bit), overlaying the other fields.
+///
+/// If the fields have explicit `#[bits(..)]` ranges, their offsets are used instead.
+pub fn generate_field_offsets(fields: &Fields, bitsize: BitSize, bit_order: BitOrder) -> Vec<TokenStream> {
+    if let Some(ranges) = bit_range::field_bit_ranges(fields, bitsize, bit_order) {
+        return ranges
+            .into_iter()
+            .map(|range| {
+                let offset = range.offset as usize;
+                quote!(#offset)
+            })
+            .collect();
+    }
+
+    let field_sizes: Vec<_> = fields.iter().map(|field| generate_type_bitsize(&field.ty)).collect();
+    let is_alias: Vec<bool> = fields.iter().map(alias::is_alias).collect();
+    let sum = |indices: &mut dyn Iterator<Item = usize>| {
+        indices.filter(|j| !is_alias[*j]).fold(quote!(0), |acc, j| {
+            let next = &field_sizes[j];
+            quote!(#acc + #next)
+        })
+    };
+    (0..field_sizes.len())
+        .map(|i| match bit_order {
+            BitOrder::Lsb0 if is_alias[i] => quote!(0),
+            BitOrder::Lsb0 => sum(&mut (0..i)),
+            // alias fields don't need to fill the struct, so they start at its most significant bit
+            BitOrder::Msb0 if is_alias[i] => {
+                let bitsize = bitsize as usize;
+                let field_size = &field_sizes[i];
+                quote!(#bitsize - (#field_size))
+            }
+            BitOrder::Msb0 => sum(&mut (i + 1..field_sizes.len())),
+        })
+        .collect()
+}
+
 pub(crate) fn generate_from_enum_impl(
     arb_int: &TokenStream, enum_type: &Ident, to_int_match_arms: Vec<TokenStream>, const_: &TokenStream,
 ) -> TokenStream {
@@ -112,6 +338,19 @@ pub(crate) fn generate_from_enum_impl(
     }
 }
 
+/// With `Storage::ByteArray`, the last byte of `value` may contain bits which are not part of the bitfield.
+/// `From` and `TryFrom` ignore them by clearing them.
+pub fn generate_unused_bits_mask(storage: Storage) -> TokenStream {
+    match storage {
+        Storage::ArbitraryInt => quote!(),
+        Storage::ByteArray => quote! {
+            let mut value = value;
+            let last = value.len() - 1;
+            value[last] &= <Self as Bitsized>::MAX[last];
+        },
+    }
+}
+
 /// Filters fields which are always `FILLED`, meaning all bit-patterns are possible,
 /// meaning they are (should be) From<uN>, not TryFrom<uN>
 ///
@@ -120,6 +359,42 @@ pub fn is_always_filled(ty: &Type) -> bool {
     last_ident_of_path(ty).and_then(bitsize_from_type_ident).is_some()
 }
 
+/// `i8` up to `i128` are stored as `u8` up to `u128`, but `From` can't be implemented between these.
+/// So for them, we reinterpret the bits with `as` instead of converting through `From` and `TryFrom`.
+/// Other signed types like `i12` convert from and to their `ArbitraryInt` like any other field type.
+pub fn is_signed_primitive(ty: &Type) -> bool {
+    matches!(last_ident_of_path(ty), Some(ident) if ["i8", "i16", "i32", "i64", "i128"].iter().any(|name| ident == name))
+}
+
+/// Converts the field value `value` of type `ty` to the underlying integer of its `ArbitraryInt`.
+pub fn generate_field_to_base_int(ty: &Type, value: TokenStream) -> TokenStream {
+    if is_signed_primitive(ty) {
+        quote!(#value as <#ty as Bitsized>::ArbitraryInt)
+    } else {
+        quote!(<<#ty as Bitsized>::ArbitraryInt>::from(#value).value())
+    }
+}
+
+/// Converts `arb_int`, the `ArbitraryInt` of `ty`, back to the field value, which was checked to be valid before.
+pub fn generate_arb_int_to_field(ty: &Type, arb_int: TokenStream) -> TokenStream {
+    if is_signed_primitive(ty) {
+        // sign-extension is not needed, since these fill their whole `ArbitraryInt`
+        quote!(#arb_int as #ty)
+    } else {
+        quote! {
+            match <#ty>::try_from(#arb_int) {
+                Ok(v) => v,
+                Err(_) => panic!("unreachable"),
+            }
+        }
+    }
+}
+
+/// Fields are named like their getters, e.g. `val_0` in tuple structs.
+pub(crate) fn field_name(field: &Field, index: usize) -> String {
+    field.ident.as_ref().map(ToString::to_string).unwrap_or_else(|| format!("val_{index}"))
+}
+
 pub fn last_ident_of_path(ty: &Type) -> Option<&Ident> {
     if let Type::Path(type_path) = ty {
         // the type may have a qualified path, so I don't think we can use `get_ident()` here
@@ -130,14 +405,23 @@ pub fn last_ident_of_path(ty: &Type) -> Option<&Ident> {
     }
 }
 
-/// in enums, internal_bitsize <= 64; u64::MAX + 1 = u128
-/// therefore the bitshift would not overflow.
-pub fn enum_fills_bitsize(bitsize: u8, variants_count: usize) -> bool {
-    let max_variants_count = 1u128 << bitsize;
-    if variants_count as u128 > max_variants_count {
-        abort_call_site!("enum overflows its bitsize"; help = "there should only be at most {} variants defined", max_variants_count);
+/// `values_count` is the number of values the variants cover, see `discriminant_assigner::value_count`.
+///
+/// in enums, internal_bitsize <= 128, so `2^bitsize` may not fit into a u128.
+/// we don't count that high and treat such an enum as not filled.
+pub fn enum_fills_bitsize(bitsize: BitSize, values_count: u128) -> bool {
+    let Some(max_values_count) = 1u128.checked_shl(bitsize.into()) else {
+        return false;
+    };
+    if values_count > max_values_count {
+        abort_call_site!("enum overflows its bitsize"; help = "there should only be at most {} variants defined", max_values_count);
     }
-    variants_count as u128 == max_variants_count
+    values_count == max_values_count
+}
+
+/// A mask of the `bits` least significant bits, up to all 128.
+pub fn low_bits_mask(bits: BitSize) -> u128 {
+    u128::MAX.checked_shr((MAX_ARBITRARY_INT_BIT_SIZE - bits).into()).unwrap_or(0)
 }
 
 #[inline]
@@ -153,7 +437,7 @@ pub fn is_attribute(attr: &Attribute, name: &str) -> bool {
     }
 }
 
-fn is_non_exhaustive_attribute(attr: &Attribute) -> bool {
+pub(crate) fn is_non_exhaustive_attribute(attr: &Attribute) -> bool {
     is_attribute(attr, "non_exhaustive")
 }
 
@@ -173,14 +457,14 @@ pub fn bitsize_from_type_ident(type_name: &Ident) -> Option<BitSize> {
         // parse() will reject letters and underscores, so this should be correct.
         let bitsize = suffix.parse().ok();
 
-        // the namespace contains u2 up to u{MAX_STRUCT_BIT_SIZE}. can't make assumptions about larger values
-        bitsize.filter(|&n| n <= MAX_STRUCT_BIT_SIZE)
+        // the namespace contains u2 up to u{MAX_ARBITRARY_INT_BIT_SIZE}. can't make assumptions about larger values
+        bitsize.filter(|&n| n <= MAX_ARBITRARY_INT_BIT_SIZE)
     } else {
         None
     }
 }
 
-pub fn to_int_match_arm(enum_name: &Ident, variant_name: &Ident, arb_int: &TokenStream, variant_value: Literal) -> TokenStream {
+pub fn to_int_match_arm(enum_name: &Ident, variant_name: &Ident, arb_int: &TokenStream, variant_value: TokenStream) -> TokenStream {
     quote! { #enum_name::#variant_name => #arb_int::new(#variant_value), }
 }
 
@@ -0,0 +1,25 @@
+use proc_macro_error::abort;
+use syn::{Attribute, Field, Meta};
+
+/// Whether this field is another view of bits which already belong to other fields, given by `#[alias]` or `#[view]`.
+///
+/// Every alias field starts at bit 0 again, so consecutive ones overlay the same bits instead of being placed next to each other.
+/// Alias fields get a getter and a setter, but aren't arguments of `new` and don't count towards the struct's size.
+/// Derives like `DebugBits` skip them, since they only show bits again.
+pub fn is_alias(field: &Field) -> bool {
+    let mut attrs = field.attrs.iter().filter(|attr| is_alias_attribute(attr));
+    let Some(attr) = attrs.next() else {
+        return false;
+    };
+    if !matches!(attr.meta, Meta::Path(_)) {
+        abort!(attr, "this attribute doesn't take any arguments"; help = "use `#[alias]` or `#[view]`")
+    }
+    if let Some(duplicate) = attrs.next() {
+        abort!(duplicate, "a field can only be marked as alias once"; help = "remove this attribute")
+    }
+    true
+}
+
+pub(crate) fn is_alias_attribute(attr: &Attribute) -> bool {
+    attr.path().is_ident("alias") || attr.path().is_ident("view")
+}
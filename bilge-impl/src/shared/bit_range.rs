@@ -0,0 +1,125 @@
+use proc_macro_error::abort;
+use syn::{Attribute, Expr, ExprLit, ExprRange, Field, Fields, Lit, Meta, RangeLimits};
+
+use super::{alias, bitsize_from_type_ident, field_name, last_ident_of_path, BitOrder, BitSize};
+
+/// The bits of a field, given by `#[bits(4..=7)]`, `#[bits(4..8)]` or `#[bits(4)]`.
+#[derive(Clone, Copy)]
+pub struct BitRange {
+    /// position of the field's least significant bit, like the offsets in `generate_field_offsets`
+    pub offset: BitSize,
+    pub width: BitSize,
+}
+
+pub(crate) fn is_bits_attribute(attr: &Attribute) -> bool {
+    matches!(&attr.meta, Meta::List(list) if list.path.is_ident("bits"))
+}
+
+/// Finds the `#[bits(..)]` range of every field.
+///
+/// Returns `None` if no field has one. Either all fields need a range or none, so a struct can be
+/// checked line by line against a datasheet. Unlisted bits are reserved: `new` sets them to zero and
+/// they get no accessors.
+///
+/// With `BitOrder::Msb0`, bit numbers are counted from the most significant bit, like datasheets using msb0 do.
+pub fn field_bit_ranges(fields: &Fields, bitsize: BitSize, bit_order: BitOrder) -> Option<Vec<BitRange>> {
+    if !fields.iter().flat_map(|field| &field.attrs).any(is_bits_attribute) {
+        return None;
+    }
+
+    let mut ranges: Vec<(String, BitRange, bool)> = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let range = field_bit_range(field, bitsize, bit_order);
+        validate_width(field, range);
+        let is_alias = alias::is_alias(field);
+        // alias fields are meant to overlap
+        let overlapping = ranges
+            .iter()
+            .find(|(_, other, other_is_alias)| !is_alias && !other_is_alias && overlaps(range, *other));
+        if let Some((other_name, ..)) = overlapping {
+            abort!(field, "bits of this field overlap with `{}`", other_name; help = "every bit can only belong to one field, mark this field as `#[alias]` if that's intended")
+        }
+        let name = field_name(field, i);
+        ranges.push((name, range, is_alias));
+    }
+
+    Some(ranges.into_iter().map(|(_, range, _)| range).collect())
+}
+
+fn field_bit_range(field: &Field, bitsize: BitSize, bit_order: BitOrder) -> BitRange {
+    let attr = field
+        .attrs
+        .iter()
+        .find(|attr| is_bits_attribute(attr))
+        .unwrap_or_else(|| abort!(field, "this field is missing its bits"; help = "if one field has `#[bits(..)]`, all fields need it"));
+    parse_bit_range(attr, bitsize, bit_order)
+}
+
+/// Parses the range given by an attribute like `#[bits(4..=7)]`, which needs to be inside of `bitsize`.
+pub(crate) fn parse_bit_range(attr: &Attribute, bitsize: BitSize, bit_order: BitOrder) -> BitRange {
+    let range: Expr = attr
+        .parse_args()
+        .unwrap_or_else(|_| abort!(attr, "bit range is not valid"; help = "bit ranges are given like this: `#[bits(4..=7)]`"));
+
+    let (first, last) = match &range {
+        Expr::Range(ExprRange {
+            start: Some(start),
+            limits,
+            end: Some(end),
+            ..
+        }) => {
+            let start = bit_number(start);
+            let end = bit_number(end);
+            let last = match limits {
+                RangeLimits::Closed(_) => Some(end),
+                RangeLimits::HalfOpen(_) => end.checked_sub(1),
+            };
+            match last {
+                Some(last) if start <= last => (start, last),
+                _ => abort!(range, "bit range is empty"; help = "bit ranges include at least one bit, like `#[bits(4..=4)]`"),
+            }
+        }
+        Expr::Lit(_) => {
+            let bit = bit_number(&range);
+            (bit, bit)
+        }
+        _ => abort!(range, "bit range is not valid"; help = "bit ranges are given like this: `#[bits(4..=7)]`"),
+    };
+
+    if last >= bitsize {
+        abort!(range, "bit range exceeds the declared bitsize"; help = "bits are numbered from 0 to {}", bitsize - 1)
+    }
+
+    let offset = match bit_order {
+        BitOrder::Lsb0 => first,
+        BitOrder::Msb0 => bitsize - 1 - last,
+    };
+    BitRange {
+        offset,
+        width: last - first + 1,
+    }
+}
+
+fn bit_number(expr: &Expr) -> BitSize {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Int(int), .. }) => int
+            .base10_parse()
+            .unwrap_or_else(|_| abort!(int, "bit number is not valid"; help = "use a number like `7`")),
+        _ => abort!(expr, "bit number is not valid"; help = "use a number like `7`"),
+    }
+}
+
+/// For `uN` and `bool`, the width can already be checked here.
+/// Everything else is checked at compile time, see `bitsize::generate_struct`.
+fn validate_width(field: &Field, range: BitRange) {
+    let type_bitsize = last_ident_of_path(&field.ty).and_then(bitsize_from_type_ident);
+    if let Some(type_bitsize) = type_bitsize {
+        if type_bitsize != range.width {
+            abort!(field, "bit range has {} bits, but the field has {}", range.width, type_bitsize; help = "the range needs to match the field's `Bitsized::BITS`")
+        }
+    }
+}
+
+fn overlaps(a: BitRange, b: BitRange) -> bool {
+    a.offset < b.offset + b.width && b.offset < a.offset + a.width
+}
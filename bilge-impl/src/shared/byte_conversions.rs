@@ -0,0 +1,138 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+use syn::{Generics, Visibility};
+
+use super::{byte_len, generics::generate_const, BitSize, Storage};
+
+/// Generates `from_le_bytes`, `from_be_bytes`, `to_le_bytes` and `to_be_bytes`,
+/// or `try_from_le_bytes` and `try_from_be_bytes` instead of the `from_` ones with `is_try_from`.
+///
+/// These work on `[u8; ceil(BITS / 8)]`, so e.g. a 24-bit bitfield uses `[u8; 3]`.
+/// If `BITS` is not a multiple of 8, the bits of the last byte (le) or the first byte (be) which are
+/// not part of the bitfield are ignored.
+///
+/// Like `raw()`, these have the visibility `vis` of the bitfield.
+pub(crate) fn generate_byte_conversions(
+    item_type: &Ident, vis: &Visibility, generics: &Generics, bitsize: BitSize, arb_int: &TokenStream, is_try_from: bool,
+) -> TokenStream {
+    let const_ = generate_const(generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let len = byte_len(bitsize);
+
+    let (from_le_value, from_be_value, to_le_bytes, to_be_bytes) = match Storage::from_bitsize(bitsize) {
+        Storage::ArbitraryInt => {
+            // the primitive holding the bitfield, e.g. u32 for u24
+            let base_bits = bitsize.next_power_of_two().max(8);
+            let base_int = format_ident!("u{}", base_bits);
+            let base_len = base_bits as usize / 8;
+            // the bytes of the bitfield are the lowest bytes of `base_int`, which are at its start (le) or end (be)
+            let from_bytes = |from_fn: TokenStream, padded_index: TokenStream| {
+                quote! {
+                    let mut padded = [0u8; #base_len];
+                    // constness: iter, for-loop, range are not const, so we're using while loops
+                    let mut i = 0;
+                    while i < #len {
+                        padded[#padded_index] = bytes[i];
+                        i += 1;
+                    }
+                    // ignore the bits which are not part of the bitfield
+                    let value = #base_int::#from_fn(padded) & <Self as Bitsized>::MAX.value();
+                    let value = <#arb_int>::new(value);
+                }
+            };
+            let to_bytes = |to_fn: TokenStream, padded_index: TokenStream| {
+                quote! {
+                    let padded = <#arb_int>::from(self).value().#to_fn();
+                    let mut bytes = [0u8; #len];
+                    let mut i = 0;
+                    while i < #len {
+                        bytes[i] = padded[#padded_index];
+                        i += 1;
+                    }
+                    bytes
+                }
+            };
+            (
+                from_bytes(quote!(from_le_bytes), quote!(i)),
+                from_bytes(quote!(from_be_bytes), quote!(#base_len - #len + i)),
+                to_bytes(quote!(to_le_bytes), quote!(i)),
+                to_bytes(quote!(to_be_bytes), quote!(#base_len - #len + i)),
+            )
+        }
+        // the storage already is a little-endian byte array, `From` and `TryFrom` ignore the unused bits
+        Storage::ByteArray => {
+            let reversed = |bytes: TokenStream| {
+                quote! {
+                    let mut reversed = [0u8; #len];
+                    let mut i = 0;
+                    while i < #len {
+                        reversed[i] = #bytes[#len - 1 - i];
+                        i += 1;
+                    }
+                }
+            };
+            let reverse_bytes = reversed(quote!(bytes));
+            let reverse_value = reversed(quote!(value));
+            (
+                quote! { let value = bytes; },
+                quote! {
+                    #reverse_bytes
+                    let value = reversed;
+                },
+                quote! { <#arb_int>::from(self) },
+                quote! {
+                    let value = <#arb_int>::from(self);
+                    #reverse_value
+                    reversed
+                },
+            )
+        }
+    };
+
+    let from_fns = if is_try_from {
+        quote! {
+            /// Creates this from its little-endian bytes, checking if they are a valid bit pattern.
+            #vis #const_ fn try_from_le_bytes(bytes: [u8; #len]) -> ::core::result::Result<Self, ::bilge::BitsError> {
+                #from_le_value
+                Self::try_from(value)
+            }
+
+            /// Creates this from its big-endian bytes, checking if they are a valid bit pattern.
+            #vis #const_ fn try_from_be_bytes(bytes: [u8; #len]) -> ::core::result::Result<Self, ::bilge::BitsError> {
+                #from_be_value
+                Self::try_from(value)
+            }
+        }
+    } else {
+        quote! {
+            /// Creates this from its little-endian bytes.
+            #vis #const_ fn from_le_bytes(bytes: [u8; #len]) -> Self {
+                #from_le_value
+                Self::from(value)
+            }
+
+            /// Creates this from its big-endian bytes.
+            #vis #const_ fn from_be_bytes(bytes: [u8; #len]) -> Self {
+                #from_be_value
+                Self::from(value)
+            }
+        }
+    };
+
+    quote! {
+        impl #impl_generics #item_type #ty_generics #where_clause {
+            #from_fns
+
+            /// Returns the bits as little-endian bytes.
+            #vis #const_ fn to_le_bytes(self) -> [u8; #len] {
+                #to_le_bytes
+            }
+
+            /// Returns the bits as big-endian bytes.
+            #vis #const_ fn to_be_bytes(self) -> [u8; #len] {
+                #to_be_bytes
+            }
+        }
+    }
+}
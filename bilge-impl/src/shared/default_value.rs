@@ -0,0 +1,53 @@
+use proc_macro_error::abort;
+use syn::{Attribute, Expr, Field, Meta, Type};
+
+/// The default value of a field, given by `#[default = u3::new(5)]` or `#[default(0b101)]`.
+pub enum FieldDefault<'a> {
+    /// a value of the field's type
+    Value(&'a Expr),
+    /// the field's bits, checked against its width
+    Bits(Expr),
+}
+
+impl<'a> FieldDefault<'a> {
+    pub fn of(field: &'a Field) -> Option<FieldDefault<'a>> {
+        let mut attrs = field.attrs.iter().filter(|attr| is_default_attribute(attr));
+        let attr = attrs.next()?;
+        if let Some(duplicate) = attrs.next() {
+            abort!(duplicate, "a field can only have one default value"; help = "remove one of them")
+        }
+        let default = match &attr.meta {
+            Meta::NameValue(name_value) => FieldDefault::Value(&name_value.value),
+            Meta::List(list) => {
+                let bits = list
+                    .parse_args()
+                    .unwrap_or_else(|_| abort!(list.tokens, "default bits are not valid"; help = "use a number like this: `#[default(0b101)]`"));
+                FieldDefault::Bits(bits)
+            }
+            Meta::Path(_) => {
+                abort!(attr, "missing default value"; help = "use `#[default = u3::new(5)]` or `#[default(0b101)]`")
+            }
+        };
+        if matches!(default, FieldDefault::Bits(_)) && !matches!(field.ty, Type::Path(_)) {
+            abort!(attr, "default bits are only supported on single values"; help = "use `#[default = ..]` for tuples and arrays")
+        }
+        Some(default)
+    }
+}
+
+pub(crate) fn is_default_attribute(attr: &Attribute) -> bool {
+    attr.path().is_ident("default")
+}
+
+/// The reset value of the whole bitfield, given by `#[reset_value(0x0000_1F00)]`.
+pub fn reset_value(attrs: &[Attribute]) -> Option<Expr> {
+    let attr = attrs.iter().find(|attr| is_reset_value_attribute(attr))?;
+    let value = attr
+        .parse_args()
+        .unwrap_or_else(|_| abort!(attr, "reset value is not valid"; help = "use a number like this: `#[reset_value(0x0000_1F00)]`"));
+    Some(value)
+}
+
+pub(crate) fn is_reset_value_attribute(attr: &Attribute) -> bool {
+    attr.path().is_ident("reset_value")
+}
@@ -1,37 +1,86 @@
-use proc_macro2::Literal;
+use proc_macro2::{Literal, TokenStream};
 use proc_macro_error::abort;
-use syn::{Expr, ExprLit, Lit, Variant};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{punctuated::Iter, spanned::Spanned, Expr, ExprLit, ExprRange, Fields, Lit, Pat, RangeLimits, Variant};
 
-use super::{unreachable, BitSize};
+use super::{bit_range::is_bits_attribute, is_fallback_attribute, low_bits_mask, unreachable, BitSize};
+
+/// The value of an enum variant.
+pub(crate) enum Discriminant {
+    /// A value which is already known in the proc macro, meaning an integer literal or counted up from one.
+    Literal(u128),
+    /// A `u128` expression like `(OP_ADD) as u128 + 1`, which is evaluated at compile time in the generated code.
+    Expr(TokenStream),
+    /// The values given by `#[bits(4..=7)]` or `#[bits(0 | 1)]`, as inclusive ranges.
+    /// The first value is the canonical one, which the variant is converted to.
+    Bits(Vec<(u128, u128)>),
+}
+
+impl Discriminant {
+    /// The match arm pattern for this value, when matching on the enum's underlying integer.
+    pub fn pattern(&self) -> TokenStream {
+        match self {
+            Discriminant::Literal(value) => unsuffixed(*value),
+            // constants can only be used as patterns if they're paths
+            Discriminant::Expr(expr) => quote!(value if value as u128 == #expr),
+            Discriminant::Bits(ranges) => {
+                let ranges = ranges.iter().map(|&(first, last)| {
+                    if first == last {
+                        unsuffixed(first)
+                    } else {
+                        let (first, last) = (unsuffixed(first), unsuffixed(last));
+                        quote!(#first..=#last)
+                    }
+                });
+                quote!(#( #ranges )|*)
+            }
+        }
+    }
+
+    /// This value, as the underlying integer of `arb_int`.
+    pub fn to_base_int(&self, arb_int: &TokenStream) -> TokenStream {
+        match self {
+            Discriminant::Literal(value) => unsuffixed(*value),
+            Discriminant::Expr(expr) => quote!((#expr) as <#arb_int as ::bilge::BaseInt>::UnderlyingType),
+            Discriminant::Bits(ranges) => unsuffixed(ranges[0].0),
+        }
+    }
+
+    /// The values as inclusive ranges of `u128` expressions.
+    fn ranges(&self) -> Vec<(TokenStream, TokenStream)> {
+        match self {
+            Discriminant::Literal(value) => vec![(unsuffixed(*value), unsuffixed(*value))],
+            Discriminant::Expr(expr) => vec![(expr.clone(), expr.clone())],
+            Discriminant::Bits(ranges) => ranges.iter().map(|&(first, last)| (unsuffixed(first), unsuffixed(last))).collect(),
+        }
+    }
+}
 
 pub(crate) struct DiscriminantAssigner {
     bitsize: BitSize,
-    next_expected_assignment: u128,
+    /// the last discriminant which is an expression, following variants count up from it
+    last_expr: Option<TokenStream>,
+    /// `None` if the previous value was `u128::MAX`
+    next_expected_assignment: Option<u128>,
 }
 
 impl DiscriminantAssigner {
-    pub fn new(bitsize: u8) -> DiscriminantAssigner {
+    pub fn new(bitsize: BitSize) -> DiscriminantAssigner {
         DiscriminantAssigner {
             bitsize,
-            next_expected_assignment: 0,
+            last_expr: None,
+            next_expected_assignment: Some(0),
         }
     }
 
     fn max_value(&self) -> u128 {
-        (1u128 << self.bitsize) - 1
+        low_bits_mask(self.bitsize)
     }
 
     fn value_from_discriminant(&self, variant: &Variant) -> Option<u128> {
         let discriminant = variant.discriminant.as_ref()?;
-        let discriminant_expr = &discriminant.1;
-        let variant_name = &variant.ident;
-
-        let Expr::Lit(ExprLit { lit: Lit::Int(int), .. }) = discriminant_expr else {
-            abort!(
-                discriminant_expr,
-                "variant `{}` is not a number", variant_name;
-                help = "only literal integers currently supported"
-            )
+        let Expr::Lit(ExprLit { lit: Lit::Int(int), .. }) = &discriminant.1 else {
+            unreachable(())
         };
 
         let discriminant_value: u128 = int.base10_parse().unwrap_or_else(unreachable);
@@ -42,15 +91,242 @@ impl DiscriminantAssigner {
         Some(discriminant_value)
     }
 
-    fn assign(&mut self, variant: &Variant) -> u128 {
-        let value = self.value_from_discriminant(variant).unwrap_or(self.next_expected_assignment);
-        self.next_expected_assignment = value + 1;
-        value
+    /// Assigns the next value, like rust does: the discriminant if there is one, or the previous value plus one.
+    ///
+    /// A variant with `#[bits(..)]` gets all of its values, and the next variant counts up from the highest one.
+    pub fn assign(&mut self, variant: &Variant) -> Discriminant {
+        if let Some(ranges) = variant_bits(variant) {
+            if ranges.iter().any(|&(_, last)| last > self.max_value()) {
+                abort!(variant, "Value of variant exceeds the given number of bits")
+            }
+            self.last_expr = None;
+            self.next_expected_assignment = highest_value(&ranges).checked_add(1);
+            return Discriminant::Bits(ranges);
+        }
+
+        let explicit_value = match &variant.discriminant {
+            Some((_, expr)) if !is_literal(expr) => {
+                self.last_expr = Some(to_u128(expr));
+                Some(0)
+            }
+            // a literal starts counting from scratch
+            Some(_) => {
+                self.last_expr = None;
+                self.value_from_discriminant(variant)
+            }
+            None => None,
+        };
+
+        let value = explicit_value
+            .or(self.next_expected_assignment)
+            .unwrap_or_else(|| abort!(variant, "Value of variant exceeds the given number of bits"));
+        self.next_expected_assignment = value.checked_add(1);
+
+        match &self.last_expr {
+            // the value counts up from the expression here
+            Some(expr) if value == 0 => Discriminant::Expr(expr.clone()),
+            Some(expr) => {
+                let offset = unsuffixed(value);
+                Discriminant::Expr(quote!((#expr + #offset)))
+            }
+            None => Discriminant::Literal(value),
+        }
+    }
+
+    /// Checks that the values of all variants fit into the bitsize and are distinct.
+    ///
+    /// If all values are known to the proc macro, this aborts right away. Otherwise, this is an expression
+    /// asserting it, to be evaluated in a const context.
+    pub fn generate_checks<'a>(bitsize: BitSize, variants: impl Iterator<Item = &'a Variant>) -> Option<TokenStream> {
+        // the value of a fallback variant with a field isn't used, and it's always the last variant
+        let variants: Vec<_> = variants.filter(|variant| matches!(variant.fields, Fields::Unit)).collect();
+
+        let mut assigner = DiscriminantAssigner::new(bitsize);
+        let values: Vec<_> = variants.iter().map(|variant| assigner.assign(variant)).collect();
+
+        if !values.iter().any(|value| matches!(value, Discriminant::Expr(_))) {
+            check_overlaps(&variants, &values, assigner.max_value());
+            return None;
+        }
+
+        // suffixed, so the comparisons aren't done with `i32`
+        let max_value = Literal::u128_suffixed(assigner.max_value());
+        let mut overflow_checks = vec![];
+        let (mut firsts, mut lasts, mut owners) = (vec![], vec![], vec![]);
+        for (i, (variant, value)) in variants.iter().zip(&values).enumerate() {
+            for (first, last) in value.ranges() {
+                // spanned, so the error points to the variant
+                overflow_checks.push(quote_spanned! {variant.span()=>
+                    assert!(#last <= #max_value, "value of variant exceeds the given number of bits");
+                });
+                firsts.push(first);
+                lasts.push(last);
+                owners.push(i);
+            }
+        }
+        let len = owners.len();
+
+        Some(quote! { {
+            #( #overflow_checks )*
+            let firsts: [u128; #len] = [#( #firsts ),*];
+            let lasts: [u128; #len] = [#( #lasts ),*];
+            let owners: [usize; #len] = [#( #owners ),*];
+            let mut i = 0;
+            while i < #len {
+                let mut j = i + 1;
+                while j < #len {
+                    let overlap = firsts[i] <= lasts[j] && firsts[j] <= lasts[i];
+                    assert!(owners[i] == owners[j] || !overlap, "two variants have the same value");
+                    j += 1;
+                }
+                i += 1;
+            }
+        } })
     }
 
-    /// syn adds a suffix when printing Rust integers. we use an unsuffixed `Literal` for better-looking codegen
-    pub fn assign_unsuffixed(&mut self, variant: &Variant) -> Literal {
-        let next = self.assign(variant);
-        Literal::u128_unsuffixed(next)
+    /// The `BitPattern` of an enum without tags or a fallback: the values of all variants.
+    pub fn generate_valid_bits<'a>(bitsize: BitSize, variants: impl Iterator<Item = &'a Variant>) -> TokenStream {
+        let mut assigner = DiscriminantAssigner::new(bitsize);
+        let ranges = variants
+            .flat_map(|variant| assigner.assign(variant).ranges())
+            .map(|(first, last)| quote!((#first, #last)));
+        quote!(::bilge::BitPattern::Ranges(&[#( #ranges ),*]))
     }
 }
+
+/// Aborts if a value is used by two variants. The values can't be expressions.
+fn check_overlaps(variants: &[&Variant], values: &[Discriminant], max_value: u128) {
+    let mut used: Vec<(u128, u128, &Variant)> = vec![];
+    for (variant, value) in variants.iter().zip(values) {
+        let ranges = match value {
+            Discriminant::Literal(value) => vec![(*value, *value)],
+            Discriminant::Bits(ranges) => ranges.clone(),
+            Discriminant::Expr(_) => unreachable(()),
+        };
+        for (first, last) in ranges {
+            // implicit values haven't been checked yet
+            if last > max_value {
+                abort!(variant, "Value of variant exceeds the given number of bits")
+            }
+            let overlapping = used
+                .iter()
+                .find(|(other_first, other_last, _)| first <= *other_last && *other_first <= last);
+            if let Some((other_first, _, other)) = overlapping {
+                abort!(variant, "value {} of this variant is already used by `{}`", first.max(*other_first), other.ident; help = "every value can only belong to one variant")
+            }
+            used.push((first, last, variant));
+        }
+    }
+}
+
+/// How many values the variants have together: one per variant, or all values given by `#[bits(..)]`.
+pub fn value_count(variants: Iter<Variant>) -> u128 {
+    variants
+        .map(|variant| match variant_bits(variant) {
+            Some(ranges) => ranges
+                .iter()
+                .map(|(first, last)| (last - first).saturating_add(1))
+                .fold(0, u128::saturating_add),
+            None => 1,
+        })
+        .fold(0, u128::saturating_add)
+}
+
+/// The values given by `#[bits(4..=7)]` or `#[bits(0 | 1)]`, as inclusive ranges, if the variant has them.
+pub(crate) fn variant_bits(variant: &Variant) -> Option<Vec<(u128, u128)>> {
+    let attr = variant.attrs.iter().find(|attr| is_bits_attribute(attr))?;
+    if let Some((_, discriminant)) = &variant.discriminant {
+        abort!(discriminant, "variants with `#[bits(..)]` can't have a discriminant"; help = "the first of its values is used instead, remove the discriminant")
+    }
+    if variant.attrs.iter().any(is_fallback_attribute) {
+        abort!(attr, "`#[bits(..)]` is not supported on the fallback variant"; help = "the fallback already gets all unused values, remove `#[bits(..)]`")
+    }
+
+    let pattern = attr
+        .parse_args_with(Pat::parse_multi)
+        .unwrap_or_else(|_| abort!(attr, "values are not valid"; help = "values are given like this: `#[bits(4..=7)]` or `#[bits(0 | 1)]`"));
+    let cases: Vec<&Pat> = match &pattern {
+        Pat::Or(or) => or.cases.iter().collect(),
+        pattern => vec![pattern],
+    };
+
+    let mut ranges: Vec<(u128, u128)> = vec![];
+    for case in cases {
+        let range = match case {
+            Pat::Lit(ExprLit { lit: Lit::Int(int), .. }) => {
+                let value = int.base10_parse().unwrap_or_else(|_| abort!(int, "value is not a valid number"));
+                (value, value)
+            }
+            Pat::Range(ExprRange {
+                start: Some(start),
+                limits,
+                end: Some(end),
+                ..
+            }) => {
+                let first = range_bound(start);
+                let last = match limits {
+                    RangeLimits::Closed(_) => Some(range_bound(end)),
+                    RangeLimits::HalfOpen(_) => range_bound(end).checked_sub(1),
+                };
+                match last {
+                    Some(last) if first <= last => (first, last),
+                    _ => abort!(case, "range of values is empty"),
+                }
+            }
+            _ => abort!(case, "values are not valid"; help = "values are given like this: `#[bits(4..=7)]` or `#[bits(0 | 1)]`"),
+        };
+        if ranges.iter().any(|&(first, last)| range.0 <= last && first <= range.1) {
+            abort!(case, "values overlap with other values of this variant")
+        }
+        ranges.push(range);
+    }
+    Some(ranges)
+}
+
+/// The highest of the values given by `#[bits(..)]`, which don't need to be in order.
+pub(crate) fn highest_value(ranges: &[(u128, u128)]) -> u128 {
+    ranges.iter().map(|&(_, last)| last).max().unwrap_or_else(|| unreachable(()))
+}
+
+fn range_bound(bound: &Expr) -> u128 {
+    match bound {
+        Expr::Lit(ExprLit { lit: Lit::Int(int), .. }) => int.base10_parse().unwrap_or_else(|_| abort!(int, "value is not a valid number")),
+        _ => abort!(bound, "values are not valid"; help = "values are given like this: `#[bits(4..=7)]` or `#[bits(0 | 1)]`"),
+    }
+}
+
+fn is_literal(expr: &Expr) -> bool {
+    matches!(expr, Expr::Lit(ExprLit { lit: Lit::Int(_), .. }))
+}
+
+/// Whether this expression only consists of unsuffixed integer literals, like `1 << 40`.
+///
+/// Rust would evaluate these as `i32`, so they need to be given a type instead of being cast.
+pub(crate) fn is_untyped(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Int(int), .. }) => int.suffix().is_empty(),
+        Expr::Binary(binary) => is_untyped(&binary.left) && is_untyped(&binary.right),
+        Expr::Unary(unary) => is_untyped(&unary.expr),
+        Expr::Paren(paren) => is_untyped(&paren.expr),
+        Expr::Group(group) => is_untyped(&group.expr),
+        _ => false,
+    }
+}
+
+/// The discriminant expression as `u128`: untyped ones are evaluated as `u128`, everything else is cast,
+/// so constants like `OP_ADD: u8` can be used as well.
+fn to_u128(expr: &Expr) -> TokenStream {
+    if is_untyped(expr) {
+        quote!({
+            const VALUE: u128 = #expr;
+            VALUE
+        })
+    } else {
+        quote!(((#expr) as u128))
+    }
+}
+
+/// syn adds a suffix when printing Rust integers. we use an unsuffixed `Literal` for better-looking codegen
+fn unsuffixed(value: u128) -> TokenStream {
+    Literal::u128_unsuffixed(value).into_token_stream()
+}
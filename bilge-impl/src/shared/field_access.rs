@@ -0,0 +1,62 @@
+use proc_macro_error::abort;
+use syn::{Attribute, Field, Meta};
+
+/// How a field can be accessed, given by `#[read_only]`, `#[write_only]`, `#[w1c]` or `#[w1s]`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FieldAccess {
+    ReadWrite,
+    /// No setter, and not an argument of `new`, which sets it to zero like a reserved field.
+    ReadOnly,
+    /// No getter, so derives like `DebugBits` skip it.
+    WriteOnly,
+    /// Writing 1 clears the bits in hardware, writing 0 leaves them unchanged.
+    /// Instead of a setter, this gets `clear_<field>()`, and like `ReadOnly` it's not an argument of `new`.
+    WriteOneToClear,
+    /// Writing 1 sets the bits in hardware, writing 0 leaves them unchanged.
+    /// Instead of a setter taking a value, this gets `set_<field>()`, and it's not an argument of `new`.
+    WriteOneToSet,
+}
+
+impl FieldAccess {
+    pub fn of(field: &Field) -> FieldAccess {
+        let mut access = FieldAccess::ReadWrite;
+        for attr in field.attrs.iter().filter(|attr| is_access_attribute(attr)) {
+            if !matches!(attr.meta, Meta::Path(_)) {
+                abort!(attr, "this attribute doesn't take any arguments"; help = "use `#[read_only]`, `#[write_only]`, `#[w1c]` or `#[w1s]`")
+            }
+            let path = attr.path();
+            let next = if path.is_ident("read_only") {
+                FieldAccess::ReadOnly
+            } else if path.is_ident("write_only") {
+                FieldAccess::WriteOnly
+            } else if path.is_ident("w1c") {
+                FieldAccess::WriteOneToClear
+            } else {
+                FieldAccess::WriteOneToSet
+            };
+            if access != FieldAccess::ReadWrite {
+                abort!(attr, "a field can only have one of `#[read_only]`, `#[write_only]`, `#[w1c]` and `#[w1s]`"; help = "remove one of them")
+            }
+            access = next;
+        }
+        access
+    }
+
+    pub fn is_readable(self) -> bool {
+        self != FieldAccess::WriteOnly
+    }
+
+    /// Whether this field gets a setter and is an argument of `new`.
+    pub fn is_writable(self) -> bool {
+        matches!(self, FieldAccess::ReadWrite | FieldAccess::WriteOnly)
+    }
+
+    /// Whether only writing 1 has an effect, so all other writes need to write 0 to this field.
+    pub fn is_write_one(self) -> bool {
+        matches!(self, FieldAccess::WriteOneToClear | FieldAccess::WriteOneToSet)
+    }
+}
+
+pub(crate) fn is_access_attribute(attr: &Attribute) -> bool {
+    ["read_only", "write_only", "w1c", "w1s"].iter().any(|name| attr.path().is_ident(name))
+}
@@ -0,0 +1,107 @@
+use itertools::Itertools;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{parse_quote, Fields, GenericParam, Generics, Ident, Type, WherePredicate};
+
+use super::last_ident_of_path;
+
+/// Whether `ty` uses one of the type or const parameters of `generics`, e.g. `T` or `Wrapper<T>`.
+///
+/// Code for these types can't use `as` casts between their underlying integers, since those are only known per instantiation.
+/// `PhantomData<T>` is always stored as `u0`, so it doesn't count.
+pub fn is_generic_type(ty: &Type, generics: &Generics) -> bool {
+    if matches!(last_ident_of_path(ty), Some(ident) if ident == "PhantomData") {
+        return false;
+    }
+
+    let params: Vec<&Ident> = generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(&param.ident),
+            GenericParam::Const(param) => Some(&param.ident),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    !params.is_empty() && mentions_any(ty.to_token_stream(), &params)
+}
+
+fn mentions_any(tokens: TokenStream, params: &[&Ident]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => params.iter().any(|param| **param == ident),
+        TokenTree::Group(group) => mentions_any(group.stream(), params),
+        _ => false,
+    })
+}
+
+/// The types of fields and tuple elements which use a generic parameter.
+fn generic_field_types<'a>(fields: &'a Fields, generics: &Generics) -> Vec<&'a Type> {
+    fn collect<'a>(ty: &'a Type, generics: &Generics, types: &mut Vec<&'a Type>) {
+        match ty {
+            Type::Tuple(tuple) => tuple.elems.iter().for_each(|elem| collect(elem, generics, types)),
+            // arrays of generic types are rejected by `bitsize`
+            _ if is_generic_type(ty, generics) => types.push(ty),
+            _ => (),
+        }
+    }
+
+    let mut types = vec![];
+    for field in fields {
+        collect(&field.ty, generics, &mut types);
+    }
+    // a single bound per type is enough
+    types.into_iter().unique_by(|ty| ty.to_token_stream().to_string()).collect()
+}
+
+/// The generics of a bitfield, with the bounds the generated code needs for every field type using a generic parameter.
+///
+/// `extra_bounds` are added to these types as well, e.g. `+ ::core::fmt::Debug` for `DebugBits`.
+pub fn bitfield_generics(generics: &Generics, fields: &Fields, extra_bounds: TokenStream) -> Generics {
+    let mut generics = generics.clone();
+    let types = generic_field_types(fields, &generics);
+    let where_clause = generics.make_where_clause();
+    for ty in types {
+        let predicates: [WherePredicate; 3] = [
+            parse_quote! {
+                #ty: ::bilge::Bitsized + ::core::convert::TryFrom<<#ty as ::bilge::Bitsized>::ArbitraryInt> #extra_bounds
+            },
+            parse_quote! {
+                <#ty as ::bilge::Bitsized>::ArbitraryInt: ::bilge::arbitrary_int::Number + ::core::convert::From<#ty>
+            },
+            // instead of `as` casts, the underlying integers are converted through `u128`
+            parse_quote! {
+                <<#ty as ::bilge::Bitsized>::ArbitraryInt as ::bilge::BaseInt>::UnderlyingType: ::core::convert::Into<u128>
+            },
+        ];
+        where_clause.predicates.extend(predicates);
+    }
+    generics
+}
+
+/// Casts `value`, an unsigned integer, to `target`, which is the underlying integer of a bitfield or field type.
+///
+/// If either of them depends on a generic parameter, `as` doesn't work, so we convert through `u128` instead.
+/// The value always fits, so the conversion can't fail.
+pub fn generate_cast(value: TokenStream, target: TokenStream, is_generic: bool) -> TokenStream {
+    if is_generic {
+        quote! {
+            match <#target as ::core::convert::TryFrom<u128>>::try_from(::core::convert::Into::<u128>::into(#value)) {
+                Ok(value) => value,
+                Err(_) => panic!("unreachable"),
+            }
+        }
+    } else {
+        quote!(#value as #target)
+    }
+}
+
+/// `const`, if the generated functions can be const.
+///
+/// Trait methods of generic field types can't be called in const fns, so bitfields with generics don't get const fns.
+pub fn generate_const(generics: &Generics) -> TokenStream {
+    if cfg!(feature = "nightly") && generics.params.is_empty() {
+        quote!(const)
+    } else {
+        quote!()
+    }
+}
@@ -0,0 +1,25 @@
+use proc_macro_error::abort;
+use syn::{Attribute, Expr, Field, Meta};
+
+/// The bits a field needs to have, given by `#[reserved(0)]` or `#[must_be(0b1)]`.
+///
+/// These fields aren't arguments of `new`, which writes the required bits instead, and only get an `unsafe` setter.
+/// `TryFrom` rejects any value where the field's bits differ, so `FromBits` can't be used.
+pub fn required_bits(field: &Field) -> Option<Expr> {
+    let mut attrs = field.attrs.iter().filter(|attr| is_required_value_attribute(attr));
+    let attr = attrs.next()?;
+    if let Some(duplicate) = attrs.next() {
+        abort!(duplicate, "a field can only have one required value"; help = "remove one of them")
+    }
+    let Meta::List(list) = &attr.meta else {
+        abort!(attr, "missing required value"; help = "use a number like this: `#[reserved(0)]` or `#[must_be(0b1)]`")
+    };
+    let bits = list.parse_args().unwrap_or_else(
+        |_| abort!(list.tokens, "required value is not valid"; help = "use a number like this: `#[reserved(0)]` or `#[must_be(0b1)]`"),
+    );
+    Some(bits)
+}
+
+pub(crate) fn is_required_value_attribute(attr: &Attribute) -> bool {
+    attr.path().is_ident("reserved") || attr.path().is_ident("must_be")
+}
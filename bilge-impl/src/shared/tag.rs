@@ -0,0 +1,286 @@
+use itertools::Itertools;
+use proc_macro2::{Ident, Literal, TokenStream};
+use proc_macro_error::{abort, abort_call_site};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{punctuated::Iter, spanned::Spanned, Attribute, Expr, ExprLit, Fields, Lit, Meta, Type, Variant};
+
+use super::{
+    bit_range::{self, BitRange},
+    generate_arb_int_to_field, generate_field_to_base_int, is_fallback_attribute, is_signed_primitive, low_bits_mask, to_int_match_arm, unreachable,
+    BitOrder, BitSize,
+};
+
+/// The layout of an enum whose variant is picked by some of its bits, the tag, given by `#[tag(0b01)]` on every variant.
+///
+/// By default, the tag is stored in the least significant bits, using as many bits as the biggest tag needs.
+/// `#[tag_bits(30..=31)]` on the enum places it somewhere else.
+/// All other bits are the payload of the variant, which is a single bitfield like `Load(LoadFields)`, or nothing.
+/// Payload bits above the tag are shifted down, so the payload doesn't see the tag at all.
+#[derive(Clone, Copy)]
+pub struct TagLayout {
+    pub tag: BitRange,
+    pub bitsize: BitSize,
+}
+
+pub(crate) fn is_tag_attribute(attr: &Attribute) -> bool {
+    attr.path().is_ident("tag")
+}
+
+pub(crate) fn is_tag_bits_attribute(attr: &Attribute) -> bool {
+    attr.path().is_ident("tag_bits")
+}
+
+impl TagLayout {
+    /// Finds and validates the tags of an enum's variants.
+    ///
+    /// Returns `None` if no variant has one. Either all variants need a tag or none.
+    pub fn of(attrs: &[Attribute], variants: Iter<Variant>, bitsize: BitSize) -> Option<TagLayout> {
+        let tag_bits = attrs.iter().find(|attr| is_tag_bits_attribute(attr));
+        if !variants.clone().flat_map(|variant| &variant.attrs).any(is_tag_attribute) {
+            if let Some(attr) = tag_bits {
+                abort!(attr, "`#[tag_bits(..)]` is only applicable to enums with tagged variants"; help = "add `#[tag(..)]` to every variant")
+            }
+            return None;
+        }
+
+        let mut tags: Vec<(&Variant, u128)> = vec![];
+        for variant in variants {
+            let tag = variant_tag(variant).unwrap_or_else(
+                || abort!(variant, "this variant is missing its tag"; help = "if one variant has `#[tag(..)]`, all variants need it"),
+            );
+            if let Some((_, discriminant)) = &variant.discriminant {
+                abort!(discriminant, "tagged variants can't have a discriminant"; help = "the tag is used instead, remove the discriminant")
+            }
+            if let Some(attr) = variant.attrs.iter().find(|attr| is_fallback_attribute(attr)) {
+                abort!(attr, "`#[fallback]` is not supported in tagged enums"; help = "use `#[derive(TryFromBits)]` to reject unknown tags")
+            }
+            if let Some(attr) = variant.attrs.iter().find(|attr| bit_range::is_bits_attribute(attr)) {
+                abort!(attr, "`#[bits(..)]` is not supported in tagged enums"; help = "every variant has exactly one tag, remove `#[bits(..)]`")
+            }
+            // validates the variant's fields
+            let _ = payload_type(variant);
+            if let Some((other, _)) = tags.iter().find(|(_, other_tag)| *other_tag == tag) {
+                abort!(variant, "tag {} is already used by `{}`", tag, other.ident; help = "every variant needs its own tag")
+            }
+            tags.push((variant, tag));
+        }
+
+        let tag = match tag_bits {
+            Some(attr) => bit_range::parse_bit_range(attr, bitsize, BitOrder::Lsb0),
+            None => {
+                let max_tag = tags.iter().map(|(_, tag)| *tag).max().unwrap_or(0);
+                // at least one bit, even if the only tag is 0
+                let width = (u128::BITS - max_tag.leading_zeros()).max(1) as BitSize;
+                if width > bitsize {
+                    abort_call_site!("tags need {} bits, which exceeds the enum's bitsize", width; help = "use a bigger bitsize or smaller tags")
+                }
+                BitRange { offset: 0, width }
+            }
+        };
+
+        for (variant, value) in &tags {
+            if value & !low_bits_mask(tag.width) != 0 {
+                abort!(variant, "tag doesn't fit into the {} tag bits", tag.width; help = "use `#[tag_bits(..)]` to give the tag more bits")
+            }
+        }
+
+        Some(TagLayout { tag, bitsize })
+    }
+
+    /// The amount of bits the payload of every variant has.
+    pub fn payload_bits(&self) -> BitSize {
+        self.bitsize - self.tag.width
+    }
+
+    /// Whether some payload bits are above the tag. Without them, the payload can't be shifted past the tag,
+    /// since shifting a `u128` by 128 bits overflows.
+    fn has_high_bits(&self) -> bool {
+        self.tag.offset + self.tag.width < self.bitsize
+    }
+
+    /// Whether every bit pattern is a valid variant: all tags are used, and every payload accepts all of its bits.
+    ///
+    /// Payloads are checked to be filled at compile time, see `from_bits::generate_filled_check_for`.
+    pub fn validate_filled(&self, variants: Iter<Variant>) {
+        // with 128 tag bits, there are more tags than a `u128` can count
+        let max_variants_count = 1u128.checked_shl(self.tag.width.into());
+        if Some(variants.len() as u128) != max_variants_count {
+            abort_call_site!("tagged enum doesn't use all of its tags"; help = "you need to use `#[derive(TryFromBits)]` instead")
+        }
+        for variant in variants {
+            if payload_type(variant).is_none() && self.payload_bits() > 0 {
+                abort!(variant, "FromBits doesn't support unit variants in tagged enums"; help = "the other bits would be lost, use `#[derive(TryFromBits)]` instead")
+            }
+        }
+    }
+
+    /// Asserts that every payload has exactly the bits which aren't used by the tag.
+    ///
+    /// These are expressions, to be evaluated in a const context.
+    pub fn generate_payload_size_checks(&self, variants: Iter<Variant>) -> Vec<TokenStream> {
+        let payload_bits = self.payload_bits() as usize;
+        variants
+            .filter_map(payload_type)
+            .map(|ty| {
+                // spanned, so the error points to the payload
+                quote_spanned! {ty.span()=>
+                    assert!(<#ty as Bitsized>::BITS == #payload_bits, "payload size and the enum's bits without the tag differ")
+                }
+            })
+            .collect()
+    }
+
+    /// Generates the body of `from`, or with `try_from`, the body of `try_from`, which reads the enum from `number`.
+    pub fn generate_from_int(&self, variants: Iter<Variant>, try_from: bool) -> TokenStream {
+        let offset = self.tag.offset;
+        let bitsize = self.bitsize as usize;
+        let tag_mask = Literal::u128_unsuffixed(low_bits_mask(self.tag.width));
+        let low_mask = Literal::u128_unsuffixed(low_bits_mask(offset));
+        let high_bits = self.has_high_bits().then(|| {
+            let high_shift = offset + self.tag.width;
+            quote!(| ((bits >> #high_shift) << #offset))
+        });
+
+        let arms = variants.map(|variant| {
+            let variant_name = &variant.ident;
+            let tag = Literal::u128_unsuffixed(variant_tag(variant).unwrap_or_else(|| unreachable(())));
+            let Some(ty) = payload_type(variant) else {
+                return if try_from {
+                    // the payload bits of unit variants need to be zero, so converting back gives the same bits
+                    quote!(#tag if payload == 0 => Ok(Self::#variant_name),)
+                } else {
+                    quote!(#tag => Self::#variant_name,)
+                };
+            };
+            let payload = quote! {
+                let payload = <#ty as Bitsized>::ArbitraryInt::new(payload as <<#ty as Bitsized>::ArbitraryInt as ::bilge::BaseInt>::UnderlyingType);
+            };
+            let variant_value = if !try_from {
+                let field = generate_arb_int_to_field(ty, quote!(payload));
+                quote!(Self::#variant_name(#field))
+            } else if is_signed_primitive(ty) {
+                quote!(Ok(Self::#variant_name(payload as #ty)))
+            } else {
+                quote! {
+                    match <#ty>::try_from(payload) {
+                        Ok(payload) => Ok(Self::#variant_name(payload)),
+                        // the payload's bits aren't contiguous if the tag is in between, so this reports the whole enum
+                        Err(_) => Err(::bilge::BitsError::new(#bitsize, bits)),
+                    }
+                }
+            };
+            quote! {
+                #tag => {
+                    #payload
+                    #variant_value
+                }
+            }
+        });
+
+        let catch_all_arm = if try_from {
+            quote!(_ => Err(::bilge::BitsError::new(#bitsize, bits)),)
+        } else {
+            quote! {
+                // constness: unreachable!() is not const yet
+                _ => ::core::panic!("unreachable: all tags are used and every payload is filled"),
+            }
+        };
+
+        quote! {
+            let bits = number.value() as u128;
+            // removes the tag from the bits
+            let payload = (bits & #low_mask) #high_bits;
+            match (bits >> #offset) & #tag_mask {
+                #( #arms )*
+                #catch_all_arm
+            }
+        }
+    }
+
+    /// The `BitPattern` of the enum: the pattern of each tag's payload.
+    /// Unit variants only accept zero as their payload.
+    pub fn generate_valid_bits(&self, variants: Iter<Variant>) -> TokenStream {
+        let offset = self.tag.offset as usize;
+        let width = self.tag.width as usize;
+        let bitsize = self.bitsize as usize;
+        let variants = variants.map(|variant| {
+            let tag = Literal::u128_unsuffixed(variant_tag(variant).unwrap_or_else(|| unreachable(())));
+            let payload = match payload_type(variant) {
+                Some(ty) => quote!(<#ty as ::bilge::Bitsized>::VALID_BITS),
+                None => quote!(::bilge::BitPattern::Ranges(&[(0, 0)])),
+            };
+            quote!((#tag, #payload))
+        });
+        quote!(::bilge::BitPattern::Tagged { offset: #offset, width: #width, bitsize: #bitsize, variants: &[#( #variants ),*] })
+    }
+
+    /// Generates the arms of `From<Enum> for uN`, which put the tag between the payload bits.
+    pub fn generate_to_int_match_arms(&self, enum_name: &Ident, variants: Iter<Variant>, arb_int: &TokenStream) -> Vec<TokenStream> {
+        let offset = self.tag.offset;
+        let low_mask = Literal::u128_unsuffixed(low_bits_mask(offset));
+        let high_bits = self.has_high_bits().then(|| {
+            let high_shift = offset + self.tag.width;
+            quote!(| ((payload >> #offset) << #high_shift))
+        });
+
+        variants
+            .map(|variant| {
+                let variant_name = &variant.ident;
+                let tag = variant_tag(variant).unwrap_or_else(|| unreachable(()));
+                let Some(ty) = payload_type(variant) else {
+                    return to_int_match_arm(
+                        enum_name,
+                        variant_name,
+                        arb_int,
+                        Literal::u128_unsuffixed(tag << offset).to_token_stream(),
+                    );
+                };
+                let tag = Literal::u128_unsuffixed(tag);
+                let payload = generate_field_to_base_int(ty, quote!(payload));
+                quote! {
+                    #enum_name::#variant_name(payload) => {
+                        let payload = (#payload) as u128;
+                        let bits = (payload & #low_mask) #high_bits | (#tag << #offset);
+                        #arb_int::new(bits as <#arb_int as ::bilge::BaseInt>::UnderlyingType)
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// The value of `#[tag(..)]`, if the variant has one.
+fn variant_tag(variant: &Variant) -> Option<u128> {
+    let mut attrs = variant.attrs.iter().filter(|attr| is_tag_attribute(attr));
+    let attr = attrs.next()?;
+    if let Some(duplicate) = attrs.next() {
+        abort!(duplicate, "a variant can only have one tag"; help = "remove this attribute")
+    }
+    let Meta::List(list) = &attr.meta else {
+        abort!(attr, "tag is not valid"; help = "tags are given like this: `#[tag(0b01)]`")
+    };
+    let tag = match syn::parse2(list.tokens.clone()) {
+        Ok(Expr::Lit(ExprLit { lit: Lit::Int(int), .. })) => int
+            .base10_parse()
+            .unwrap_or_else(|_| abort!(int, "tag is not a valid number"; help = "tags are given like this: `#[tag(0b01)]`")),
+        _ => abort!(attr, "tag is not valid"; help = "tags are given like this: `#[tag(0b01)]`"),
+    };
+    Some(tag)
+}
+
+/// The type of the variant's payload, or `None` for unit variants.
+fn payload_type(variant: &Variant) -> Option<&Type> {
+    let field = match &variant.fields {
+        Fields::Unit => return None,
+        Fields::Unnamed(fields) => fields.unnamed.iter().exactly_one().unwrap_or_else(
+            |_| abort!(variant.fields, "tagged variants can only have one field"; help = "put the payload into a `#[bitsize]` struct"),
+        ),
+        Fields::Named(_) => {
+            abort!(variant.fields, "tagged variants can't have named fields"; help = "put the payload into a `#[bitsize]` struct, like `Load(LoadFields)`")
+        }
+    };
+    if !matches!(field.ty, Type::Path(_)) {
+        abort!(field.ty, "this payload type is not supported"; help = "put the payload into a `#[bitsize]` struct")
+    }
+    Some(&field.ty)
+}
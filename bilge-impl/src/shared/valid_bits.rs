@@ -0,0 +1,79 @@
+//! `Bitsized::VALID_BITS`, which describes the valid bits of a type as a constant, so they can be checked at compile time.
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{punctuated::Iter, Attribute, Fields, Type, Variant};
+
+use super::{
+    alias, discriminant_assigner::DiscriminantAssigner, generate_field_offsets, generate_type_bitsize, is_always_filled, is_fallback_attribute,
+    is_signed_primitive, required_value, tag::TagLayout, BitOrder, BitSize,
+};
+
+/// The `BitPattern` of an enum. With a fallback, every bit pattern is valid.
+pub(crate) fn generate_enum_pattern(attrs: &[Attribute], variants: Iter<Variant>, bitsize: BitSize) -> TokenStream {
+    if let Some(tag_layout) = TagLayout::of(attrs, variants.clone(), bitsize) {
+        tag_layout.generate_valid_bits(variants)
+    } else if variants.clone().flat_map(|variant| &variant.attrs).any(is_fallback_attribute) {
+        quote!(::bilge::BitPattern::Any)
+    } else {
+        DiscriminantAssigner::generate_valid_bits(bitsize, variants)
+    }
+}
+
+/// The `BitPattern` of a struct: every field which can be invalid, and every field with a required value.
+///
+/// Alias fields are always valid, since they only reinterpret the bits of other fields.
+pub(crate) fn generate_struct_pattern(fields: &Fields, bitsize: BitSize, bit_order: BitOrder) -> TokenStream {
+    let field_offsets = generate_field_offsets(fields, bitsize, bit_order);
+    let parts = fields
+        .iter()
+        .zip(field_offsets)
+        .filter(|(field, _)| !alias::is_alias(field))
+        .filter_map(|(field, offset)| {
+            let pattern = match required_value::required_bits(field) {
+                Some(bits) => quote!(::bilge::BitPattern::Ranges(&[((#bits) as u128, (#bits) as u128)])),
+                None => generate_type_pattern(&field.ty, bit_order)?,
+            };
+            let width = generate_type_bitsize(&field.ty);
+            Some(quote!(::bilge::BitPart { offset: #offset, width: #width, pattern: #pattern }))
+        })
+        .collect();
+    generate_parts(parts).unwrap_or_else(|| quote!(::bilge::BitPattern::Any))
+}
+
+/// The `BitPattern` of a field's type, or `None` if all of its bit patterns are valid.
+fn generate_type_pattern(ty: &Type, bit_order: BitOrder) -> Option<TokenStream> {
+    match ty {
+        Type::Tuple(tuple) => {
+            let elem_sizes: Vec<_> = tuple.elems.iter().map(generate_type_bitsize).collect();
+            let parts = tuple
+                .elems
+                .iter()
+                .enumerate()
+                .filter_map(|(i, elem)| {
+                    let pattern = generate_type_pattern(elem, bit_order)?;
+                    // laid out like struct fields, see `generate_field_offsets`
+                    let elems_below = match bit_order {
+                        BitOrder::Lsb0 => &elem_sizes[..i],
+                        BitOrder::Msb0 => &elem_sizes[i + 1..],
+                    };
+                    let width = &elem_sizes[i];
+                    Some(quote!(::bilge::BitPart { offset: 0 #( + #elems_below )*, width: #width, pattern: #pattern }))
+                })
+                .collect();
+            generate_parts(parts)
+        }
+        // the elements all have the same pattern, so their order doesn't matter here
+        Type::Array(array) => {
+            let pattern = generate_type_pattern(&array.elem, bit_order)?;
+            let count = &array.len;
+            let width = generate_type_bitsize(&array.elem);
+            Some(quote!(::bilge::BitPattern::Repeat { count: #count, width: #width, pattern: &#pattern }))
+        }
+        _ if is_always_filled(ty) || is_signed_primitive(ty) => None,
+        _ => Some(quote!(<#ty as ::bilge::Bitsized>::VALID_BITS)),
+    }
+}
+
+fn generate_parts(parts: Vec<TokenStream>) -> Option<TokenStream> {
+    (!parts.is_empty()).then(|| quote!(::bilge::BitPattern::Parts(&[#( #parts ),*])))
+}
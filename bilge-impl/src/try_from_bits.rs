@@ -1,22 +1,54 @@
 use proc_macro2::{Ident, TokenStream};
 use proc_macro_error::{abort, emit_call_site_warning};
 use quote::quote;
-use syn::{punctuated::Iter, Data, DeriveInput, Fields, Type, Variant};
+use syn::{punctuated::Iter, Data, DeriveInput, Fields, Generics, Type, Variant};
 
-use crate::shared::{self, discriminant_assigner::DiscriminantAssigner, enum_fills_bitsize, fallback::Fallback, unreachable, BitSize};
-use crate::shared::{bitsize_from_type_ident, last_ident_of_path};
+use crate::bitsize_internal::struct_gen;
+use crate::shared::{
+    self, byte_conversions,
+    discriminant_assigner::{self, DiscriminantAssigner},
+    enum_fills_bitsize,
+    fallback::Fallback,
+    generics, required_value,
+    tag::TagLayout,
+    unreachable, BitOrder, BitSize, BitsizeArgs, DeriveKind, Storage,
+};
 
 pub(super) fn try_from_bits(item: TokenStream) -> TokenStream {
     let derive_input = parse(item);
-    let (derive_data, arb_int, name, internal_bitsize, ..) = analyze(&derive_input);
-    match derive_data {
-        Data::Struct(ref data) => codegen_struct(arb_int, name, &data.fields),
+    let (
+        derive_data,
+        BitsizeArgs {
+            bitsize: internal_bitsize,
+            arb_int,
+            bit_order,
+            ..
+        },
+        name,
+        generics,
+        ..,
+    ) = analyze(&derive_input);
+    let generics = match derive_data {
+        Data::Struct(data) => generics::bitfield_generics(generics, &data.fields, quote!()),
+        _ => generics.clone(),
+    };
+    let byte_conversions = byte_conversions::generate_byte_conversions(name, &derive_input.vis, &generics, internal_bitsize, &arb_int, true);
+    let expanded = match derive_data {
+        Data::Struct(ref data) => codegen_struct(arb_int, name, &generics, &data.fields, internal_bitsize, bit_order),
         Data::Enum(ref enum_data) => {
             let variants = enum_data.variants.iter();
-            let match_arms = analyze_enum(variants, name, internal_bitsize, &arb_int);
-            codegen_enum(arb_int, name, match_arms)
+            if let Some(tag_layout) = TagLayout::of(&derive_input.attrs, variants.clone(), internal_bitsize) {
+                codegen_tagged_enum(arb_int, name, variants, tag_layout)
+            } else {
+                let match_arms = analyze_enum(variants, name, internal_bitsize, &arb_int);
+                codegen_enum(arb_int, name, internal_bitsize, match_arms)
+            }
         }
         _ => unreachable(()),
+    };
+    quote! {
+        #expanded
+        #byte_conversions
     }
 }
 
@@ -24,14 +56,14 @@ fn parse(item: TokenStream) -> DeriveInput {
     shared::parse_derive(item)
 }
 
-fn analyze(derive_input: &DeriveInput) -> (&syn::Data, TokenStream, &Ident, BitSize, Option<Fallback>) {
-    shared::analyze_derive(derive_input, true)
+fn analyze(derive_input: &DeriveInput) -> (&syn::Data, BitsizeArgs, &Ident, &Generics, Option<Fallback>) {
+    shared::analyze_derive(derive_input, DeriveKind::TryFromBits)
 }
 
 fn analyze_enum(variants: Iter<Variant>, name: &Ident, internal_bitsize: BitSize, arb_int: &TokenStream) -> (Vec<TokenStream>, Vec<TokenStream>) {
     validate_enum_variants(variants.clone());
 
-    if enum_fills_bitsize(internal_bitsize, variants.len()) {
+    if enum_fills_bitsize(internal_bitsize, discriminant_assigner::value_count(variants.clone())) {
         emit_call_site_warning!("enum fills its bitsize"; help = "you can use `#[derive(FromBits)]` instead, rust will provide `TryFrom` for you (so you don't necessarily have to update call-sites)");
     }
 
@@ -40,21 +72,23 @@ fn analyze_enum(variants: Iter<Variant>, name: &Ident, internal_bitsize: BitSize
     variants
         .map(|variant| {
             let variant_name = &variant.ident;
-            let variant_value = assigner.assign_unsuffixed(variant);
+            let variant_value = assigner.assign(variant);
 
+            let pattern = variant_value.pattern();
             let from_int_match_arm = quote! {
-                #variant_value => Ok(Self::#variant_name),
+                #pattern => Ok(Self::#variant_name),
             };
 
-            let to_int_match_arm = shared::to_int_match_arm(name, variant_name, arb_int, variant_value);
+            let to_int_match_arm = shared::to_int_match_arm(name, variant_name, arb_int, variant_value.to_base_int(arb_int));
 
             (from_int_match_arm, to_int_match_arm)
         })
         .unzip()
 }
 
-fn codegen_enum(arb_int: TokenStream, enum_type: &Ident, match_arms: (Vec<TokenStream>, Vec<TokenStream>)) -> TokenStream {
+fn codegen_enum(arb_int: TokenStream, enum_type: &Ident, bitsize: BitSize, match_arms: (Vec<TokenStream>, Vec<TokenStream>)) -> TokenStream {
     let (from_int_match_arms, to_int_match_arms) = match_arms;
+    let bitsize = bitsize as usize;
 
     let const_ = if cfg!(feature = "nightly") { quote!(const) } else { quote!() };
 
@@ -66,7 +100,7 @@ fn codegen_enum(arb_int: TokenStream, enum_type: &Ident, match_arms: (Vec<TokenS
             fn try_from(number: #arb_int) -> ::core::result::Result<Self, Self::Error> {
                 match number.value() {
                     #( #from_int_match_arms )*
-                    i => Err(::bilge::give_me_error()),
+                    i => Err(::bilge::BitsError::new(#bitsize, i as u128)),
                 }
             }
         }
@@ -76,59 +110,143 @@ fn codegen_enum(arb_int: TokenStream, enum_type: &Ident, match_arms: (Vec<TokenS
     }
 }
 
-fn generate_field_check(ty: &Type) -> TokenStream {
+fn codegen_tagged_enum(arb_int: TokenStream, enum_type: &Ident, variants: Iter<Variant>, tag_layout: TagLayout) -> TokenStream {
+    let const_ = if cfg!(feature = "nightly") { quote!(const) } else { quote!() };
+
+    let try_from_int = tag_layout.generate_from_int(variants.clone(), true);
+    let to_int_match_arms = tag_layout.generate_to_int_match_arms(enum_type, variants, &arb_int);
+    let from_enum_impl = shared::generate_from_enum_impl(&arb_int, enum_type, to_int_match_arms, &const_);
+    quote! {
+        impl #const_ ::core::convert::TryFrom<#arb_int> for #enum_type {
+            type Error = ::bilge::BitsError;
+
+            // validates the tag and the payload of its variant
+            fn try_from(number: #arb_int) -> ::core::result::Result<Self, Self::Error> {
+                #try_from_int
+            }
+        }
+
+        #from_enum_impl
+    }
+}
+
+/// Checks the field called `name`, which evaluates to `Ok(())` or the `BitsError` of its first invalid element.
+pub(crate) fn generate_field_check(
+    ty: &Type, name: &str, offset: &TokenStream, storage: Storage, bit_order: BitOrder, generics: &Generics,
+) -> TokenStream {
+    generate_check(ty, &[quote!(::bilge::PathSegment::Field(#name))], offset, storage, bit_order, generics)
+}
+
+/// Like [`generate_field_check`], but for the value at `path` and `offset`, e.g. a single array element.
+pub(crate) fn generate_check(
+    ty: &Type, path: &[TokenStream], offset: &TokenStream, storage: Storage, bit_order: BitOrder, generics: &Generics,
+) -> TokenStream {
     // Yes, this is hacky module management.
-    crate::bitsize_internal::struct_gen::generate_getter_inner(ty, false)
+    // Always-filled types like `uN` only advance the cursor here.
+    let check = struct_gen::generate_getter_inner(ty, false, path, storage, bit_order, generics);
+    let cursor_init = struct_gen::generate_cursor_init(quote!(value), storage);
+    let advance_cursor = struct_gen::generate_cursor_advance(quote!(field_offset), storage);
+    quote! { {
+        // cursor starts at value's first field
+        #cursor_init
+        // cursor now starts at this field
+        let field_offset = #offset;
+        #advance_cursor
+        let mut bit_offset = field_offset as usize;
+        let result: ::core::result::Result<(), ::bilge::BitsError> = 'check: {
+            #check
+            Ok(())
+        };
+        result
+    } }
 }
 
-fn codegen_struct(arb_int: TokenStream, struct_type: &Ident, fields: &Fields) -> TokenStream {
-    let is_ok: TokenStream = fields
+/// Checks that the bits of each field with a required value, like `#[reserved(0)]`, are exactly that value.
+pub(crate) fn generate_required_value_checks(fields: &Fields, field_offsets: &[TokenStream], storage: Storage) -> Vec<TokenStream> {
+    fields
         .iter()
-        .map(|field| {
-            let ty = &field.ty;
-            let size_from_type = last_ident_of_path(ty).and_then(bitsize_from_type_ident);
-            if let Some(size) = size_from_type {
-                quote! { {
-                    // we still need to shift by the element's size
-                    let size = #size;
-                    cursor = cursor.wrapping_shr(size as u32);
-                    true
-                } }
-            } else {
-                generate_field_check(ty)
-            }
+        .zip(field_offsets)
+        .enumerate()
+        .filter_map(|(i, (field, offset))| {
+            let bits = required_value::required_bits(field)?;
+            let name = shared::field_name(field, i);
+            let width = shared::generate_type_bitsize(&field.ty);
+            let cursor_init = struct_gen::generate_cursor_init(quote!(value), storage);
+            let advance_cursor = struct_gen::generate_cursor_advance(quote!(field_offset), storage);
+            let field_bits = match storage {
+                // zero-width fields would shift by 128
+                Storage::ArbitraryInt => quote!(if (#width) == 0 { 0 } else { (cursor as u128) & (u128::MAX >> (128 - (#width))) }),
+                Storage::ByteArray => quote!(::bilge::read_bits(bytes, cursor, #width)),
+            };
+            Some(quote! { {
+                #cursor_init
+                let field_offset = #offset;
+                #advance_cursor
+                let field_bits = #field_bits;
+                if field_bits == (#bits) as u128 {
+                    Ok(())
+                } else {
+                    let error = ::bilge::BitsError::new((#width) as usize, field_bits);
+                    Err(error.nest(&[::bilge::PathSegment::Field(#name)], field_offset as usize))
+                }
+            } })
         })
-        .reduce(|acc, next| quote!((#acc && #next)))
-        // `Struct {}` would be handled like this:
-        .unwrap_or_else(|| quote!(true));
+        .collect()
+}
 
-    let const_ = if cfg!(feature = "nightly") { quote!(const) } else { quote!() };
+/// All checks of a struct, the fields' and the required values', each evaluating to a `Result<(), BitsError>`.
+pub(crate) fn generate_struct_checks(
+    fields: &Fields, field_offsets: &[TokenStream], storage: Storage, bit_order: BitOrder, generics: &Generics,
+) -> Vec<TokenStream> {
+    fields
+        .iter()
+        .zip(field_offsets)
+        .enumerate()
+        .map(|(i, (field, offset))| generate_field_check(&field.ty, &shared::field_name(field, i), offset, storage, bit_order, generics))
+        .chain(generate_required_value_checks(fields, field_offsets, storage))
+        .collect()
+}
+
+fn codegen_struct(
+    arb_int: TokenStream, struct_type: &Ident, generics: &Generics, fields: &Fields, bitsize: BitSize, bit_order: BitOrder,
+) -> TokenStream {
+    let storage = Storage::from_bitsize(bitsize);
+    let field_offsets = shared::generate_field_offsets(fields, bitsize, bit_order);
+    let checks = generate_struct_checks(fields, &field_offsets, storage, bit_order, generics);
+
+    let const_ = generics::generate_const(generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mask_unused_bits = shared::generate_unused_bits_mask(storage);
 
     quote! {
-        impl #const_ ::core::convert::TryFrom<#arb_int> for #struct_type {
+        impl #impl_generics #const_ ::core::convert::TryFrom<#arb_int> for #struct_type #ty_generics #where_clause {
             type Error = ::bilge::BitsError;
 
-            // validates all values, which means enums, even in inner structs (TODO: and reserved fields?)
+            // validates all values, which means enums, even in inner structs, and fields with a required value
+            // constness: `?` is not const, so the errors are returned by hand
+            #[allow(clippy::question_mark)]
             fn try_from(value: #arb_int) -> ::core::result::Result<Self, Self::Error> {
                 type ArbIntOf<T> = <T as Bitsized>::ArbitraryInt;
-                type BaseIntOf<T> = <ArbIntOf<T> as Number>::UnderlyingType;
+                type BaseIntOf<T> = <ArbIntOf<T> as ::bilge::BaseInt>::UnderlyingType;
 
-                // cursor starts at value's first field
-                let mut cursor = value.value();
+                #mask_unused_bits
 
-                let is_ok: bool = {#is_ok};
+                // the first invalid field is returned
+                #(
+                    if let Err(error) = #checks {
+                        return Err(error);
+                    }
+                )*
 
-                if is_ok {
-                    Ok(Self { value })
-                } else {
-                    Err(::bilge::give_me_error())
-                }
+                // we've just validated all fields
+                Ok(unsafe { Self::from_raw_unchecked(value) })
             }
         }
 
-        impl #const_ ::core::convert::From<#struct_type> for #arb_int {
-            fn from(struct_value: #struct_type) -> Self {
-                struct_value.value
+        impl #impl_generics #const_ ::core::convert::From<#struct_type #ty_generics> for #arb_int #where_clause {
+            fn from(struct_value: #struct_type #ty_generics) -> Self {
+                struct_value.raw()
             }
         }
     }
@@ -0,0 +1,119 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Generics, Type};
+
+use crate::shared::{self, alias, field_access::FieldAccess, generics, unreachable, BitOrder, BitsizeArgs, DeriveKind};
+
+pub(super) fn visit_bits(item: TokenStream) -> TokenStream {
+    let derive_input = parse(item);
+    let (derive_data, BitsizeArgs { bit_order, .. }, name, generics, ..) = analyze(&derive_input);
+    match derive_data {
+        Data::Struct(data) => generate_struct_visit_impl(name, generics, &data.fields, bit_order),
+        Data::Enum(_) => generate_enum_visit_impl(name),
+        _ => unreachable(()),
+    }
+}
+
+fn parse(item: TokenStream) -> DeriveInput {
+    shared::parse_derive(item)
+}
+
+fn analyze(derive_input: &DeriveInput) -> (&syn::Data, BitsizeArgs, &Ident, &Generics, Option<shared::fallback::Fallback>) {
+    shared::analyze_derive(derive_input, DeriveKind::VisitBits)
+}
+
+fn generate_struct_visit_impl(struct_name: &Ident, generics: &Generics, fields: &Fields, bit_order: BitOrder) -> TokenStream {
+    let generics = generics::bitfield_generics(generics, fields, quote!(+ ::bilge::VisitBits));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let visits = fields
+        .iter()
+        .enumerate()
+        // write-only fields have no getter, alias fields would visit bits twice
+        .filter(|(_, field)| FieldAccess::of(field).is_readable() && !alias::is_alias(field))
+        .map(|(i, field)| {
+            let name = shared::field_name(field, i);
+            let getter = format_ident!("{}", name);
+            let offset_name = format_ident!("{}_OFFSET", name.to_uppercase());
+            let visit = generate_visit(&field.ty, &name, bit_order);
+            quote! { {
+                let value = self.#getter();
+                let offset = offset + Self::#offset_name as usize;
+                #visit
+            } }
+        });
+
+    quote! {
+        impl #impl_generics ::bilge::VisitBits for #struct_name #ty_generics #where_clause {
+            fn visit_bits<V: ::bilge::BitsVisitor>(self, name: &str, offset: usize, visitor: &mut V) {
+                visitor.enter_struct(name, offset);
+                #( #visits )*
+                visitor.leave_struct();
+            }
+        }
+    }
+}
+
+/// Walks `value` of type `ty`, which starts at bit `offset`, the same way `struct_gen::generate_getter_inner` does.
+fn generate_visit(ty: &Type, name: &str, bit_order: BitOrder) -> TokenStream {
+    match ty {
+        Type::Tuple(tuple) => {
+            let elem_sizes: Vec<TokenStream> = tuple.elems.iter().map(shared::generate_type_bitsize).collect();
+            let elem_visits = tuple.elems.iter().enumerate().map(|(i, elem)| {
+                // with msb0, the first element is stored at the highest bits
+                let elems_below = match bit_order {
+                    BitOrder::Lsb0 => &elem_sizes[..i],
+                    BitOrder::Msb0 => &elem_sizes[i + 1..],
+                };
+                let elem_offset = elems_below.iter().fold(quote!(0), |acc, next| quote!(#acc + #next));
+                let tuple_index = syn::Index::from(i);
+                let visit = generate_visit(elem, name, bit_order);
+                quote! { {
+                    let value = value.#tuple_index;
+                    let offset = offset + #elem_offset;
+                    #visit
+                } }
+            });
+            quote! {
+                #( #elem_visits )*
+            }
+        }
+        Type::Array(array) => {
+            let elem_ty = &array.elem;
+            let len_expr = &array.len;
+            let elem_size = shared::generate_type_bitsize(elem_ty);
+            let index = match bit_order {
+                BitOrder::Lsb0 => quote!(i),
+                BitOrder::Msb0 => quote!((#len_expr - 1 - i)),
+            };
+            let visit = generate_visit(elem_ty, name, bit_order);
+            quote! {
+                for (i, value) in ::core::iter::IntoIterator::into_iter(value).enumerate() {
+                    let offset = offset + #elem_size * #index;
+                    #visit
+                }
+            }
+        }
+        Type::Path(_) => quote! {
+            ::bilge::VisitBits::visit_bits(value, #name, offset, visitor);
+        },
+        _ => unreachable(()),
+    }
+}
+
+fn generate_enum_visit_impl(enum_name: &Ident) -> TokenStream {
+    quote! {
+        impl ::bilge::VisitBits for #enum_name {
+            fn visit_bits<V: ::bilge::BitsVisitor>(self, name: &str, offset: usize, visitor: &mut V) {
+                type ArbIntOf<T> = <T as ::bilge::Bitsized>::ArbitraryInt;
+                // enums don't need to be `Copy`, so the value is converted back from its bits
+                let bits = <ArbIntOf<Self>>::from(self);
+                let value = match <Self as ::core::convert::TryFrom<ArbIntOf<Self>>>::try_from(bits) {
+                    Ok(value) => value,
+                    Err(_) => panic!("unreachable"),
+                };
+                visitor.visit_field(name, offset, value, ::bilge::arbitrary_int::Number::value(bits).into());
+            }
+        }
+    }
+}
@@ -26,4 +26,10 @@ fn main() {
     assert_eq!(mouse_packet.always_one().value(), 1);
     assert!(mouse_packet.button_left());
     assert!(mouse_packet.x_9th_bit());
+
+    // the port sends the packet byte by byte, starting with the lowest one
+    let bytes_from_port = [0b00111001, 0b00001111, 0b11100111];
+    let mouse_packet = PS2MousePacket::from_le_bytes(bytes_from_port);
+    assert_eq!(mouse_packet.y_1st_to_8th_bit(), 0b11100111);
+    assert_eq!(mouse_packet.to_le_bytes(), bytes_from_port);
 }
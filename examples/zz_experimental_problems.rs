@@ -1,11 +1,16 @@
 #![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
 #![allow(clippy::unusual_byte_groupings)]
+#![allow(unused_variables, unused_assignments)]
 use bilge::prelude::*;
 
 #[bitsize(4)]
 #[derive(TryFromBits)]
 struct CanBeChanged(Unfilled);
 
+#[bitsize(4, sealed)]
+#[derive(TryFromBits)]
+struct CannotBeChanged(Unfilled);
+
 #[bitsize(4)]
 #[derive(TryFromBits)]
 enum Unfilled {
@@ -15,14 +20,18 @@ enum Unfilled {
 }
 
 fn main() {
-    // This file mostly shows one flaw to still be solved or at least to be made configurable:
-    // The inner value of a bitfield, which holds invariants, can currently still be changed.
+    // This file mostly shows one flaw which is only solved by opting in:
+    // The inner value of a bitfield, which holds invariants, can still be changed.
     let mut a = CanBeChanged::new(Unfilled::A);
     // There is no enum value for `3` or `0b11`, but we can set it anyways:
     a.value = u4::new(3);
     // This panics internally:
     // a.val_0();
 
+    // With `sealed`, `b.value = u4::new(3)` doesn't compile, we'd need `unsafe` instead:
+    let b = CannotBeChanged::new(Unfilled::A);
+    let b = unsafe { CannotBeChanged::from_raw_unchecked(b.raw()) };
+
     // Here we try to use a custom impl and also put the generated code inside a module,
     // thereby making `.value` inaccessible.
     // Let's say we want bit 3, 4 and 31, so 0, 1, 1:
@@ -48,7 +57,7 @@ mod somebits {
     // it with a use statement again would be less "leaky", but this would also
     // mean all the private items would not be accessible..
     //
-    // If we find any other way to make `value` inaccessible, update this.
+    // `sealed` makes `value` unwritable without `unsafe`, but it's still readable.
     #[allow(dead_code)]
     fn modify_inner() {
         let a = 0b10101010;
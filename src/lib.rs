@@ -1,23 +1,33 @@
 #![cfg_attr(not(doctest), doc = include_str!("../README.md"))]
 #![no_std]
+#![cfg_attr(feature = "nightly", feature(const_trait_impl))]
 
-use core::fmt;
+use core::{convert::Infallible, fmt, marker::PhantomData};
+
+mod signed;
+mod zero;
 
 #[doc(no_inline)]
 pub use arbitrary_int;
-pub use bilge_impl::{bitsize, bitsize_internal, BinaryBits, DebugBits, DefaultBits, FromBits, TryFromBits};
+pub use bilge_impl::{bitsize, bitsize_internal, BinaryBits, DebugBits, DefaultBits, FromBits, TryFromBits, VisitBits};
 #[cfg(feature = "serde")]
 pub use bilge_impl::{DeserializeBits, SerializeBits};
+pub use signed::*;
+pub use zero::u0;
 
 /// used for `use bilge::prelude::*;`
 pub mod prelude {
     #[rustfmt::skip]
     #[doc(no_inline)]
     pub use super::{
-        bitsize, Bitsized,
+        bitsize, Bitsized, Bitfield, VisitBits,
         FromBits, TryFromBits, DebugBits, BinaryBits, DefaultBits,
         // we control the version, so this should not be a problem
         arbitrary_int::*,
+        // signed counterparts, e.g. `i12`
+        signed::*,
+        // the `ArbitraryInt` of zero-width types
+        u0,
     };
     #[cfg(feature = "serde")]
     pub use super::{DeserializeBits, SerializeBits};
@@ -28,6 +38,110 @@ pub trait Bitsized {
     type ArbitraryInt;
     const BITS: usize;
     const MAX: Self::ArbitraryInt;
+    /// The fields of a bitfield struct, for tools like register dumps. Empty for all other types.
+    const FIELDS: &'static [FieldInfo] = &[];
+    /// Internally used to check `#[reset_value(..)]` at compile time, since `TryFrom` can't run in a const context on stable.
+    /// Generated next to `TryFrom`, from the same fields and variants.
+    #[doc(hidden)]
+    const VALID_BITS: BitPattern = BitPattern::Unknown;
+}
+
+/// Describes one field of a bitfield struct, see [`Bitsized::FIELDS`].
+///
+/// More information may be added in the future, so this can't be constructed outside of bilge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FieldInfo {
+    /// The name of the field's getter, e.g. `val_0` in tuple structs.
+    pub name: &'static str,
+    /// Position of the field's least significant bit.
+    pub offset: usize,
+    /// Number of bits of the field.
+    pub width: usize,
+    /// The field's type, as written in the struct.
+    pub type_name: &'static str,
+    /// Whether this is a `reserved` or `padding` field.
+    pub reserved: bool,
+    /// Whether this is an `#[alias]` field, which gives another view of other fields' bits.
+    pub alias: bool,
+    /// How the field can be accessed, see [`Access`].
+    pub access: Access,
+    /// The fields of a nested bitfield struct, with offsets relative to it.
+    pub fields: &'static [FieldInfo],
+}
+
+impl FieldInfo {
+    /// Used by the generated `FIELDS`, since `#[non_exhaustive]` forbids struct expressions there.
+    #[doc(hidden)]
+    #[allow(clippy::too_many_arguments)]
+    pub const fn new(
+        name: &'static str, offset: usize, width: usize, type_name: &'static str, reserved: bool, alias: bool, access: Access,
+        fields: &'static [FieldInfo],
+    ) -> Self {
+        Self {
+            name,
+            offset,
+            width,
+            type_name,
+            reserved,
+            alias,
+            access,
+            fields,
+        }
+    }
+}
+
+/// How a field can be accessed, given by `#[read_only]`, `#[write_only]`, `#[w1c]` or `#[w1s]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Access {
+    ReadWrite,
+    ReadOnly,
+    WriteOnly,
+    /// Writing 1 clears the bits in hardware.
+    WriteOneToClear,
+    /// Writing 1 sets the bits in hardware.
+    WriteOneToSet,
+}
+
+/// Accesses the fields of a bitfield struct by name, with their bits as `u128`.
+///
+/// This is generated for every struct, see [`Bitsized::FIELDS`] for the names.
+pub trait Bitfield: Bitsized {
+    /// Returns the bits of the field called `name`, if there is such a field and it fits into a `u128`.
+    ///
+    /// Write-only fields can't be read.
+    fn get_raw(&self, name: &str) -> Result<u128, BitsError>;
+    /// Sets the bits of the field called `name`, if they are valid for the field's type.
+    ///
+    /// Reserved and read-only fields can't be set.
+    fn set_raw(&mut self, name: &str, value: u128) -> Result<(), BitsError>;
+}
+
+/// Gets called for every field of a bitfield, see [`VisitBits`].
+///
+/// Tuples and arrays are visited element by element, all with the name of their field.
+pub trait BitsVisitor {
+    /// Visits a value which is not a bitfield struct, e.g. a `u4`, `bool` or enum, starting at bit `offset`.
+    ///
+    /// `bits` are the bits of `value`, as they are stored in the bitfield.
+    fn visit_field<T: Bitsized>(&mut self, name: &str, offset: usize, value: T, bits: u128);
+
+    /// Called before visiting the fields of a bitfield struct, which starts at bit `offset`.
+    fn enter_struct(&mut self, _name: &str, _offset: usize) {}
+
+    /// Called after visiting the fields of a bitfield struct.
+    fn leave_struct(&mut self) {}
+}
+
+/// Walks through a value with a [`BitsVisitor`], which is derived by `#[derive(VisitBits)]`.
+///
+/// All offsets count from the outermost struct's least significant bit.
+pub trait VisitBits: Bitsized {
+    /// Visits this value, which is called `name` and starts at bit `offset`.
+    ///
+    /// Bitfield structs call `enter_struct`, visit their fields and call `leave_struct`,
+    /// everything else calls `visit_field`. Write-only fields are skipped, since they can't be read.
+    fn visit_bits<V: BitsVisitor>(self, name: &str, offset: usize, visitor: &mut V);
 }
 
 /// Internally used marker trait.
@@ -40,22 +154,501 @@ unsafe impl<T> Filled for T where T: Bitsized + From<<T as Bitsized>::ArbitraryI
 /// This is generated to statically validate that a type implements `FromBits`.
 pub const fn assume_filled<T: Filled>() {}
 
+/// Internally used to get the underlying integer of an `ArbitraryInt`, e.g. `u8` for `u4`.
+///
+/// This is `Number::UnderlyingType`, but also covers [`u0`], which isn't a `Number`.
+pub trait BaseInt {
+    type UnderlyingType;
+}
+impl<T: arbitrary_int::Number> BaseInt for T {
+    type UnderlyingType = <T as arbitrary_int::Number>::UnderlyingType;
+}
+
+/// Internally used to describe which bits are valid for a type, see `Bitsized::VALID_BITS`.
+///
+/// Offsets count from the type's least significant bit.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub enum BitPattern {
+    /// Every bit pattern is valid.
+    Any,
+    /// Only `TryFrom` can tell, e.g. for types implementing `Bitsized` by hand.
+    Unknown,
+    /// The valid values, as inclusive ranges.
+    Ranges(&'static [(u128, u128)]),
+    /// Each part has to be valid, e.g. the fields of a struct.
+    Parts(&'static [BitPart]),
+    /// `count` elements of `width` bits next to each other, which all have to be valid.
+    Repeat {
+        count: usize,
+        width: usize,
+        pattern: &'static BitPattern,
+    },
+    /// The tag at `offset` picks the pattern of the payload, which are all other bits below `bitsize`.
+    Tagged {
+        offset: usize,
+        width: usize,
+        bitsize: usize,
+        variants: &'static [(u128, BitPattern)],
+    },
+}
+
+/// A part of a [`BitPattern`], e.g. a struct field.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub struct BitPart {
+    pub offset: usize,
+    pub width: usize,
+    pub pattern: BitPattern,
+}
+
+impl BitPattern {
+    /// Whether `bits` are valid, or `None` if some part of them is [`BitPattern::Unknown`].
+    ///
+    /// Only the lowest 128 bits of a type can be checked.
+    // constness: iterators, for-loops and `?` are not const, so we're using while loops and matches
+    pub const fn matches(&self, bits: u128) -> Option<bool> {
+        match *self {
+            BitPattern::Any => Some(true),
+            BitPattern::Unknown => None,
+            BitPattern::Ranges(ranges) => {
+                let mut i = 0;
+                while i < ranges.len() {
+                    if ranges[i].0 <= bits && bits <= ranges[i].1 {
+                        return Some(true);
+                    }
+                    i += 1;
+                }
+                Some(false)
+            }
+            BitPattern::Parts(parts) => {
+                let mut result = Some(true);
+                let mut i = 0;
+                while i < parts.len() {
+                    let part = &parts[i];
+                    match part.pattern.matches(low_bits(bits, part.offset, part.width)) {
+                        Some(false) => return Some(false),
+                        None => result = None,
+                        Some(true) => {}
+                    }
+                    i += 1;
+                }
+                result
+            }
+            BitPattern::Repeat { count, width, pattern } => {
+                let mut result = Some(true);
+                let mut i = 0;
+                while i < count {
+                    match pattern.matches(low_bits(bits, i * width, width)) {
+                        Some(false) => return Some(false),
+                        None => result = None,
+                        Some(true) => {}
+                    }
+                    i += 1;
+                }
+                result
+            }
+            BitPattern::Tagged {
+                offset,
+                width,
+                bitsize,
+                variants,
+            } => {
+                let tag = low_bits(bits, offset, width);
+                // removes the tag from the bits
+                let payload = low_bits(bits, 0, offset) | (low_bits(bits, offset + width, bitsize - offset - width) << offset);
+                let mut i = 0;
+                while i < variants.len() {
+                    if variants[i].0 == tag {
+                        return variants[i].1.matches(payload);
+                    }
+                    i += 1;
+                }
+                Some(false)
+            }
+        }
+    }
+}
+
+/// The `width` bits of `bits` starting at `offset`, where bits above 128 are zero.
+const fn low_bits(bits: u128, offset: usize, width: usize) -> u128 {
+    let shifted = match bits.checked_shr(offset as u32) {
+        Some(shifted) => shifted,
+        None => 0,
+    };
+    match u128::MAX.checked_shr(128u32.saturating_sub(width as u32)) {
+        Some(mask) if width > 0 => shifted & mask,
+        _ => 0,
+    }
+}
+
+/// The error of `TryFrom`, which tells which value couldn't be parsed.
+///
+/// For structs, this is the first invalid field, e.g. an enum inside an array: its [`path`](BitsError::path)
+/// is `big_fumble[0][1].0`, its [`offset`](BitsError::offset) counts from the struct's least significant bit,
+/// and [`bits`](BitsError::bits) are the [`width`](BitsError::width) bits found there.
+///
+/// It's kept small, since it's returned a lot: bitfields have at most 4096 bits and fields at most 128 bits,
+/// and `bits` is split up, so the error isn't aligned like `u128`.
 #[non_exhaustive]
-#[derive(Debug, PartialEq)]
-pub struct BitsError;
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct BitsError {
+    path: FieldPath,
+    offset: u16,
+    width: u16,
+    low_bits: u64,
+    high_bits: u64,
+}
+
+impl BitsError {
+    /// Internally used for the `Err` of `TryFrom`: `bits` is the invalid value, which is `width` bits wide.
+    #[doc(hidden)]
+    pub const fn new(width: usize, bits: u128) -> BitsError {
+        BitsError {
+            path: FieldPath::EMPTY,
+            offset: 0,
+            width: width as u16,
+            low_bits: bits as u64,
+            high_bits: (bits >> 64) as u64,
+        }
+    }
+
+    /// Internally used for the `Err` of `TryFrom`: the error happened inside the element at `path`, starting at bit `offset`.
+    #[doc(hidden)]
+    pub const fn nest(self, path: &[PathSegment], offset: usize) -> BitsError {
+        BitsError {
+            path: self.path.prepend(path),
+            offset: self.offset + offset as u16,
+            ..self
+        }
+    }
+
+    /// The field which couldn't be parsed. It's empty if the whole value is invalid, e.g. with enums.
+    pub const fn path(&self) -> &FieldPath {
+        &self.path
+    }
+
+    /// The offset of the invalid bits, counting from the least significant bit.
+    pub const fn offset(&self) -> usize {
+        self.offset as usize
+    }
+
+    /// How many bits were invalid. This is zero if the error has no information about them.
+    pub const fn width(&self) -> usize {
+        self.width as usize
+    }
+
+    /// The invalid bits, as found at [`offset`](BitsError::offset).
+    pub const fn bits(&self) -> u128 {
+        (self.high_bits as u128) << 64 | self.low_bits as u128
+    }
+}
+
+impl fmt::Debug for BitsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BitsError")
+            .field("path", &self.path)
+            .field("offset", &self.offset())
+            .field("width", &self.width())
+            .field("bits", &self.bits())
+            .finish()
+    }
+}
 
 impl fmt::Display for BitsError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "unable to parse bit pattern")
+        write!(f, "unable to parse bit pattern")?;
+        if self.width == 0 {
+            return Ok(());
+        }
+        // `0b` and the bits, including leading zeros
+        write!(f, " {:#0width$b}", self.bits(), width = self.width() + 2)?;
+        if !self.path.is_empty() {
+            write!(f, " of `{}`", self.path)?;
+        }
+        write!(f, " at bits {}..{}", self.offset, self.offset + self.width)
     }
 }
 
+/// Nested `FromBits` types can't fail, their `TryFrom` impl comes from `From`.
+///
+/// The impl is only `const` with the nightly feature, which the parser would reject even behind `#[cfg]`.
+macro_rules! impl_from_infallible {
+    ($($const_:tt)?) => {
+        impl $($const_)? From<Infallible> for BitsError {
+            fn from(never: Infallible) -> Self {
+                match never {}
+            }
+        }
+    };
+}
+#[cfg(not(feature = "nightly"))]
+impl_from_infallible!();
+#[cfg(feature = "nightly")]
+impl_from_infallible!(const);
+
+// `core::error::Error` is stable since 1.81, which is newer than our MSRV
+#[rustversion::since(1.81)]
+impl core::error::Error for BitsError {}
+
 /// Internally used for generating the `Result::Err` type in `TryFrom`.
 ///
 /// This is needed since we don't want users to be able to create `BitsError` right now.
-/// We'll be able to turn `BitsError` into an enum later, or anything else really.
+/// This error has no information about the invalid bits, so `TryFrom` uses [`BitsError::new`] instead.
 pub const fn give_me_error() -> BitsError {
-    BitsError
+    BitsError::new(0, 0)
+}
+
+/// One step of a [`FieldPath`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// A struct field, named like its getter, e.g. `val_0` in tuple structs, or the index of a tuple element.
+    Field(&'static str),
+    /// The index of an array element.
+    Index(usize),
+}
+
+impl PathSegment {
+    /// Internally used for the path of nested arrays, which are checked as one flattened array:
+    /// the index into the array at `dim`, where `lengths` starts with the outermost array.
+    #[doc(hidden)]
+    pub const fn nested_index(flattened_index: usize, lengths: &[usize], dim: usize) -> PathSegment {
+        let mut index = flattened_index;
+        let mut inner = lengths.len();
+        while inner > dim + 1 {
+            inner -= 1;
+            index /= lengths[inner];
+        }
+        PathSegment::Index(index % lengths[dim])
+    }
+}
+
+/// Where a value is inside a struct, e.g. `big_fumble[0][1].0`.
+///
+/// Only the innermost [`FieldPath::CAPACITY`] segments are kept, since they tell the most about the invalid value.
+/// The display of longer paths starts with `..`, e.g. `..[0][1].header.class`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FieldPath {
+    segments: [PathSegment; FieldPath::CAPACITY],
+    len: u8,
+    is_truncated: bool,
+}
+
+impl FieldPath {
+    /// How many segments a path can hold.
+    pub const CAPACITY: usize = 5;
+
+    const EMPTY: FieldPath = FieldPath {
+        segments: [PathSegment::Index(0); FieldPath::CAPACITY],
+        len: 0,
+        is_truncated: false,
+    };
+
+    /// The segments, starting with the outermost field.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments[..self.len as usize]
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether the outermost segments were dropped, since the path is longer than [`FieldPath::CAPACITY`].
+    pub const fn is_truncated(&self) -> bool {
+        self.is_truncated
+    }
+
+    /// `outer` followed by this path, dropping the outermost segments if they don't fit.
+    // constness: no iterators or `copy_from_slice`, so we're using while loops
+    const fn prepend(self, outer: &[PathSegment]) -> FieldPath {
+        let len = outer.len() + self.len as usize;
+        let dropped = len.saturating_sub(FieldPath::CAPACITY);
+        let mut path = FieldPath::EMPTY;
+        path.is_truncated = self.is_truncated || dropped > 0;
+        let mut i = dropped;
+        while i < len {
+            path.segments[path.len as usize] = if i < outer.len() { outer[i] } else { self.segments[i - outer.len()] };
+            path.len += 1;
+            i += 1;
+        }
+        path
+    }
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_truncated {
+            write!(f, "..")?;
+        }
+        for (i, segment) in self.segments().iter().enumerate() {
+            match segment {
+                PathSegment::Field(name) if i == 0 => write!(f, "{name}")?,
+                PathSegment::Field(name) => write!(f, ".{name}")?,
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{self}\"")
+    }
+}
+
+/// The storage of bitfields declared with `#[bitsize(N, sealed)]`.
+///
+/// Creating it needs `unsafe`, so the bits of a sealed bitfield can't be changed by accident,
+/// even inside the module defining it. Use the generated `raw()` and `from_raw_unchecked()` instead:
+///
+/// ```compile_fail
+/// use bilge::prelude::*;
+///
+/// #[bitsize(4, sealed)]
+/// struct Sealed {
+///     field: u4,
+/// }
+///
+/// let mut sealed = Sealed::new(u4::new(1));
+/// sealed.value = u4::new(3);
+/// ```
+///
+/// `Owner` is the bitfield holding it, so the bits of another bitfield can't be copied over either:
+///
+/// ```compile_fail
+/// use bilge::prelude::*;
+///
+/// #[bitsize(2)]
+/// #[derive(TryFromBits)]
+/// enum Mode {
+///     Read,
+///     Write,
+///     Execute,
+/// }
+///
+/// #[bitsize(2, sealed)]
+/// #[derive(TryFromBits)]
+/// struct Access {
+///     mode: Mode,
+/// }
+///
+/// #[bitsize(2, sealed)]
+/// #[derive(FromBits)]
+/// struct Raw {
+///     bits: u2,
+/// }
+///
+/// let mut access = Access::new(Mode::Read);
+/// access.value = Raw::new(u2::new(3)).value;
+/// ```
+#[repr(transparent)]
+pub struct Sealed<T, Owner> {
+    value: T,
+    // `fn() -> Owner` doesn't make `Sealed` depend on the owner's auto traits
+    _owner: PhantomData<fn() -> Owner>,
+}
+
+impl<T: Copy, Owner> Sealed<T, Owner> {
+    /// Internally used by `from_raw_unchecked()`.
+    ///
+    /// # Safety
+    ///
+    /// `value` needs to be a valid bit pattern for `Owner`.
+    pub const unsafe fn new_unchecked(value: T) -> Self {
+        Self { value, _owner: PhantomData }
+    }
+
+    /// Internally used by `raw()`.
+    pub const fn get(&self) -> T {
+        self.value
+    }
+}
+
+// derives would require `Owner` to implement these as well
+impl<T: Copy, Owner> Clone for Sealed<T, Owner> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Copy, Owner> Copy for Sealed<T, Owner> {}
+
+impl<T: fmt::Debug, Owner> fmt::Debug for Sealed<T, Owner> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Sealed").field(&self.value).finish()
+    }
+}
+
+impl<T: PartialEq, Owner> PartialEq for Sealed<T, Owner> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Eq, Owner> Eq for Sealed<T, Owner> {}
+
+impl<T: PartialOrd, Owner> PartialOrd for Sealed<T, Owner> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl<T: Ord, Owner> Ord for Sealed<T, Owner> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl<T: core::hash::Hash, Owner> core::hash::Hash for Sealed<T, Owner> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+/// Internally used for reading a value out of bitfields which are stored as a byte array.
+///
+/// Reads `width` bits, starting at bit `offset` (counting from the LSB of `bytes[0]`).
+/// `width` needs to be at most 128.
+pub const fn read_bits<const N: usize>(bytes: &[u8; N], offset: usize, width: usize) -> u128 {
+    let mut value = 0;
+    let mut read = 0;
+    // constness: for-loop, range are not const, so we're using while loops
+    while read < width {
+        let bit = offset + read;
+        let shift = bit % 8;
+        // this may read more bits than needed, we mask those off below
+        let chunk = (bytes[bit / 8] >> shift) as u128;
+        value |= chunk << read;
+        read += 8 - shift;
+    }
+    if width < 128 {
+        value & ((1 << width) - 1)
+    } else {
+        value
+    }
+}
+
+/// Internally used for writing a value into bitfields which are stored as a byte array.
+///
+/// Writes the lowest `width` bits of `value`, starting at bit `offset` (counting from the LSB of `bytes[0]`).
+/// `width` needs to be at most 128.
+///
+/// constness: this takes and returns the array, since `&mut` is not usable in const fns on stable.
+pub const fn write_bits<const N: usize>(mut bytes: [u8; N], offset: usize, width: usize, value: u128) -> [u8; N] {
+    let mut written = 0;
+    while written < width {
+        let bit = offset + written;
+        let shift = bit % 8;
+        let remaining = width - written;
+        let count = if 8 - shift < remaining { 8 - shift } else { remaining };
+        let mask = ((1u16 << count) - 1) as u8;
+        let chunk = (value >> written) as u8 & mask;
+        let index = bit / 8;
+        bytes[index] = (bytes[index] & !(mask << shift)) | (chunk << shift);
+        written += count;
+    }
+    bytes
 }
 
 /// Only basing this on Number did not work, as bool and others are not Number.
@@ -69,6 +662,17 @@ where
     type ArbitraryInt = Self;
     const BITS: usize = BITS;
     const MAX: Self::ArbitraryInt = <Self as arbitrary_int::Number>::MAX;
+    const VALID_BITS: BitPattern = BitPattern::Any;
+}
+
+impl<BaseType, const BITS: usize> VisitBits for arbitrary_int::UInt<BaseType, BITS>
+where
+    arbitrary_int::UInt<BaseType, BITS>: arbitrary_int::Number,
+    BaseType: Copy + Into<u128>,
+{
+    fn visit_bits<V: BitsVisitor>(self, name: &str, offset: usize, visitor: &mut V) {
+        visitor.visit_field(name, offset, self, self.value().into());
+    }
 }
 
 macro_rules! bitsized_impl {
@@ -78,6 +682,13 @@ macro_rules! bitsized_impl {
                 type ArbitraryInt = Self;
                 const BITS: usize = $bits;
                 const MAX: Self::ArbitraryInt = <Self as arbitrary_int::Number>::MAX;
+                const VALID_BITS: BitPattern = BitPattern::Any;
+            }
+
+            impl VisitBits for $name {
+                fn visit_bits<V: BitsVisitor>(self, name: &str, offset: usize, visitor: &mut V) {
+                    visitor.visit_field(name, offset, self, self as u128);
+                }
             }
         )+
     };
@@ -89,4 +700,11 @@ impl Bitsized for bool {
     type ArbitraryInt = arbitrary_int::u1;
     const BITS: usize = 1;
     const MAX: Self::ArbitraryInt = <arbitrary_int::u1 as arbitrary_int::Number>::MAX;
+    const VALID_BITS: BitPattern = BitPattern::Any;
+}
+
+impl VisitBits for bool {
+    fn visit_bits<V: BitsVisitor>(self, name: &str, offset: usize, visitor: &mut V) {
+        visitor.visit_field(name, offset, self, self as u128);
+    }
 }
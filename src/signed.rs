@@ -0,0 +1,191 @@
+//! Signed integers of any width up to 128 bits, like `i12`, for two's-complement fields.
+//!
+//! `arbitrary_int` only has unsigned types, so these are stored as their bits in an unsigned `UInt`
+//! inside bitfields. Getters sign-extend them, setters and `new` only write their lowest `BITS` bits.
+//! `i8` up to `i128` can be used as fields directly.
+
+use arbitrary_int::{Number, TryNewError, UInt};
+use core::fmt;
+
+use crate::{BitPattern, BitsVisitor, Bitsized, VisitBits};
+
+/// A signed integer with `BITS` bits, stored sign-extended in `T`.
+///
+/// Use the aliases like `i12` instead of naming this directly.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct Int<T, const BITS: usize> {
+    value: T,
+}
+
+macro_rules! int_impl {
+    ($(($type:ident, $unsigned:ident)),+) => {
+        $(
+            impl<const BITS: usize> Int<$type, BITS> {
+                pub const BITS: usize = BITS;
+                // arithmetic shifts keep the sign, so this works for `BITS == <$type>::BITS` as well
+                pub const MIN: Self = Self { value: <$type>::MIN >> (<$type>::BITS as usize - BITS) };
+                pub const MAX: Self = Self { value: <$type>::MAX >> (<$type>::BITS as usize - BITS) };
+
+                /// Creates an instance. Panics if the given value is outside of the valid range
+                #[inline]
+                pub const fn new(value: $type) -> Self {
+                    assert!(value >= Self::MIN.value && value <= Self::MAX.value);
+                    Self { value }
+                }
+
+                /// Creates an instance or an error if the given value is outside of the valid range
+                #[inline]
+                pub const fn try_new(value: $type) -> Result<Self, TryNewError> {
+                    if value >= Self::MIN.value && value <= Self::MAX.value {
+                        Ok(Self { value })
+                    } else {
+                        Err(TryNewError)
+                    }
+                }
+
+                /// Returns the type as a fundamental data type
+                #[inline]
+                pub const fn value(self) -> $type {
+                    self.value
+                }
+            }
+
+            impl<const BITS: usize> Bitsized for Int<$type, BITS>
+            where
+                UInt<$unsigned, BITS>: Number,
+            {
+                type ArbitraryInt = UInt<$unsigned, BITS>;
+                const BITS: usize = BITS;
+                const MAX: Self::ArbitraryInt = <UInt<$unsigned, BITS> as Number>::MAX;
+                const VALID_BITS: BitPattern = BitPattern::Any;
+            }
+
+            impl<const BITS: usize> VisitBits for Int<$type, BITS>
+            where
+                UInt<$unsigned, BITS>: Number<UnderlyingType = $unsigned>,
+            {
+                fn visit_bits<V: BitsVisitor>(self, name: &str, offset: usize, visitor: &mut V) {
+                    let bits = UInt::<$unsigned, BITS>::from(self).value() as u128;
+                    visitor.visit_field(name, offset, self, bits);
+                }
+            }
+
+            /// Sign-extends the bits, e.g. `u4::new(0b1110)` becomes `i4::new(-2)`.
+            impl<const BITS: usize> From<UInt<$unsigned, BITS>> for Int<$type, BITS> {
+                #[inline]
+                fn from(bits: UInt<$unsigned, BITS>) -> Self {
+                    let shift = <$type>::BITS as usize - BITS;
+                    Self { value: ((bits.value() as $type) << shift) >> shift }
+                }
+            }
+
+            /// Keeps the lowest `BITS` bits, e.g. `i4::new(-2)` becomes `u4::new(0b1110)`.
+            impl<const BITS: usize> From<Int<$type, BITS>> for UInt<$unsigned, BITS>
+            where
+                UInt<$unsigned, BITS>: Number<UnderlyingType = $unsigned>,
+            {
+                #[inline]
+                fn from(int: Int<$type, BITS>) -> Self {
+                    let mask = <UInt<$unsigned, BITS> as Number>::MAX.value();
+                    Self::new(int.value as $unsigned & mask)
+                }
+            }
+
+            #[cfg(feature = "serde")]
+            impl<'de, const BITS: usize> serde::Deserialize<'de> for Int<$type, BITS> {
+                fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    let value = <$type as serde::Deserialize>::deserialize(deserializer)?;
+                    Self::try_new(value).map_err(|_| {
+                        serde::de::Error::custom(format_args!(
+                            "invalid value: integer `{}`, expected a value between `{}` and `{}`",
+                            value,
+                            Self::MIN.value,
+                            Self::MAX.value
+                        ))
+                    })
+                }
+            }
+        )+
+    };
+}
+
+int_impl!((i8, u8), (i16, u16), (i32, u32), (i64, u64), (i128, u128));
+
+impl<T: fmt::Display, const BITS: usize> fmt::Display for Int<T, BITS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T: fmt::Debug, const BITS: usize> fmt::Debug for Int<T, BITS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize, const BITS: usize> serde::Serialize for Int<T, BITS> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+/// The primitive signed integers are stored as their unsigned counterparts.
+/// Since we can't implement `From` between these, `bitsize` reinterprets them with `as` instead.
+macro_rules! bitsized_primitive_impl {
+    ($(($name:ident, $unsigned:ident, $bits:expr)),+) => {
+        $(
+            impl Bitsized for $name {
+                type ArbitraryInt = $unsigned;
+                const BITS: usize = $bits;
+                const MAX: Self::ArbitraryInt = <$unsigned as Number>::MAX;
+                const VALID_BITS: BitPattern = BitPattern::Any;
+            }
+
+            impl VisitBits for $name {
+                fn visit_bits<V: BitsVisitor>(self, name: &str, offset: usize, visitor: &mut V) {
+                    visitor.visit_field(name, offset, self, self as $unsigned as u128);
+                }
+            }
+        )+
+    };
+}
+bitsized_primitive_impl!((i8, u8, 8), (i16, u16, 16), (i32, u32, 32), (i64, u64, 64), (i128, u128, 128));
+
+macro_rules! int_aliases {
+    ($type:ident: $(($name:ident, $bits:expr)),+) => {
+        $(
+            #[allow(non_camel_case_types)]
+            pub type $name = Int<$type, $bits>;
+        )+
+    };
+}
+
+#[rustfmt::skip]
+int_aliases!(i8: (i1, 1), (i2, 2), (i3, 3), (i4, 4), (i5, 5), (i6, 6), (i7, 7));
+#[rustfmt::skip]
+int_aliases!(i16: (i9, 9), (i10, 10), (i11, 11), (i12, 12), (i13, 13), (i14, 14), (i15, 15));
+#[rustfmt::skip]
+int_aliases!(i32:
+    (i17, 17), (i18, 18), (i19, 19), (i20, 20), (i21, 21), (i22, 22), (i23, 23), (i24, 24),
+    (i25, 25), (i26, 26), (i27, 27), (i28, 28), (i29, 29), (i30, 30), (i31, 31)
+);
+#[rustfmt::skip]
+int_aliases!(i64:
+    (i33, 33), (i34, 34), (i35, 35), (i36, 36), (i37, 37), (i38, 38), (i39, 39), (i40, 40),
+    (i41, 41), (i42, 42), (i43, 43), (i44, 44), (i45, 45), (i46, 46), (i47, 47), (i48, 48),
+    (i49, 49), (i50, 50), (i51, 51), (i52, 52), (i53, 53), (i54, 54), (i55, 55), (i56, 56),
+    (i57, 57), (i58, 58), (i59, 59), (i60, 60), (i61, 61), (i62, 62), (i63, 63)
+);
+#[rustfmt::skip]
+int_aliases!(i128:
+    (i65, 65), (i66, 66), (i67, 67), (i68, 68), (i69, 69), (i70, 70), (i71, 71), (i72, 72),
+    (i73, 73), (i74, 74), (i75, 75), (i76, 76), (i77, 77), (i78, 78), (i79, 79), (i80, 80),
+    (i81, 81), (i82, 82), (i83, 83), (i84, 84), (i85, 85), (i86, 86), (i87, 87), (i88, 88),
+    (i89, 89), (i90, 90), (i91, 91), (i92, 92), (i93, 93), (i94, 94), (i95, 95), (i96, 96),
+    (i97, 97), (i98, 98), (i99, 99), (i100, 100), (i101, 101), (i102, 102), (i103, 103), (i104, 104),
+    (i105, 105), (i106, 106), (i107, 107), (i108, 108), (i109, 109), (i110, 110), (i111, 111), (i112, 112),
+    (i113, 113), (i114, 114), (i115, 115), (i116, 116), (i117, 117), (i118, 118), (i119, 119), (i120, 120),
+    (i121, 121), (i122, 122), (i123, 123), (i124, 124), (i125, 125), (i126, 126), (i127, 127)
+);
@@ -0,0 +1,99 @@
+use crate::{BaseInt, BitPattern, BitsVisitor, Bitsized, VisitBits};
+use core::{fmt, marker::PhantomData};
+
+/// The `ArbitraryInt` of zero-width types, like `()`, `PhantomData<T>` or bitfields declared with `#[bitsize(0)]`.
+///
+/// `arbitrary_int` can't have zero bits, since its `MAX` would shift by the whole underlying integer.
+/// Its only value is `0`, stored as `u8`.
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct u0 {
+    _private: (),
+}
+
+impl u0 {
+    pub const BITS: usize = 0;
+    pub const MIN: Self = Self { _private: () };
+    pub const MAX: Self = Self { _private: () };
+
+    /// Creates a `u0` from `value`, which has to be zero.
+    pub const fn new(value: u8) -> Self {
+        assert!(value == 0);
+        Self::MIN
+    }
+
+    pub const fn value(self) -> u8 {
+        0
+    }
+}
+
+impl BaseInt for u0 {
+    type UnderlyingType = u8;
+}
+
+impl fmt::Debug for u0 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.value(), f)
+    }
+}
+
+impl fmt::Display for u0 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.value(), f)
+    }
+}
+
+impl Bitsized for u0 {
+    type ArbitraryInt = Self;
+    const BITS: usize = 0;
+    const MAX: Self::ArbitraryInt = u0::MAX;
+    const VALID_BITS: BitPattern = BitPattern::Any;
+}
+
+impl VisitBits for u0 {
+    fn visit_bits<V: BitsVisitor>(self, name: &str, offset: usize, visitor: &mut V) {
+        visitor.visit_field(name, offset, self, 0);
+    }
+}
+
+/// Zero-width types hold no bits, so they can be used as markers in bitfields.
+///
+/// The conversions are only `const` with the nightly feature, which the parser would reject even behind `#[cfg]`.
+macro_rules! zero_width_impl {
+    ([$($const_:tt)?] <$($param:ident),*> $name:ty, $value:expr) => {
+        impl<$($param: ?Sized),*> Bitsized for $name {
+            type ArbitraryInt = u0;
+            const BITS: usize = 0;
+            const MAX: Self::ArbitraryInt = u0::MAX;
+            const VALID_BITS: BitPattern = BitPattern::Any;
+        }
+
+        impl<$($param: ?Sized),*> $($const_)? From<u0> for $name {
+            fn from(_: u0) -> Self {
+                $value
+            }
+        }
+
+        impl<$($param: ?Sized),*> $($const_)? From<$name> for u0 {
+            fn from(_: $name) -> Self {
+                u0::MIN
+            }
+        }
+
+        impl<$($param: ?Sized),*> VisitBits for $name {
+            fn visit_bits<V: BitsVisitor>(self, name: &str, offset: usize, visitor: &mut V) {
+                visitor.visit_field(name, offset, self, 0);
+            }
+        }
+    };
+}
+macro_rules! zero_width_impls {
+    ($($const_:tt)?) => {
+        zero_width_impl!([$($const_)?] <> (), ());
+        zero_width_impl!([$($const_)?] <T> PhantomData<T>, PhantomData);
+    };
+}
+#[cfg(not(feature = "nightly"))]
+zero_width_impls!();
+#[cfg(feature = "nightly")]
+zero_width_impls!(const);
@@ -0,0 +1,67 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+
+/// A typical MMIO register: the status is set by hardware, the command is only ever written.
+#[bitsize(16)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Register {
+    #[read_only]
+    status: u4,
+    /// the command to run next
+    #[write_only]
+    command: u4,
+    data: u8,
+}
+
+#[test]
+fn read_only() {
+    // `status` is not an argument of `new` and starts out zeroed
+    let register = Register::new(u4::new(0x3), 0xAB);
+    assert_eq!(register.status(), u4::new(0));
+
+    let register = Register::from(0xAB_3_5);
+    assert_eq!(register.status(), u4::new(0x5));
+}
+
+#[test]
+fn write_only() {
+    let mut register = Register::from(0);
+    register.set_command(u4::new(0x7));
+    register.set_data(0x12);
+    assert_eq!(u16::from(register), 0x12_7_0);
+    // `command` is hidden, since it has no getter
+    assert_eq!(format!("{register:?}"), "Register { status: 0, data: 18 }");
+}
+
+#[bitsize(8)]
+#[derive(FromBits, DebugBits)]
+struct Pair(#[write_only] u4, #[read_only] u4);
+
+#[test]
+fn tuple_fields() {
+    let mut pair = Pair::new(u4::new(0xA));
+    assert_eq!(pair.val_1(), u4::new(0));
+    pair.set_val_0(u4::new(0xB));
+    assert_eq!(u8::from(pair), 0x0B);
+    assert_eq!(format!("{:?}", Pair::from(0xC0)), "Pair(12)");
+}
+
+#[bitsize(140)]
+#[derive(FromBits, DebugBits)]
+struct Wide {
+    #[read_only]
+    low: u128,
+    #[write_only]
+    high: u12,
+}
+
+#[test]
+fn byte_array_fields() {
+    let mut wide = Wide::new(u12::new(0xFFF));
+    assert_eq!(wide.low(), 0);
+    wide.set_high(u12::new(0x123));
+    let bytes = <[u8; 18]>::from(wide);
+    assert_eq!(bytes[16..], [0x23, 0x01]);
+    assert_eq!(format!("{:?}", Wide::from(bytes)), "Wide { low: 0 }");
+}
@@ -0,0 +1,123 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+use bilge::prelude::*;
+
+/// A data register, which can be accessed as a whole or in halves.
+#[bitsize(32)]
+#[derive(FromBits, DebugBits, DefaultBits, BinaryBits, PartialEq, Clone, Copy)]
+struct Data {
+    #[view]
+    bytes: [u8; 4],
+    lo: u16,
+    hi: u16,
+    #[alias]
+    low_byte: u8,
+}
+
+#[bitsize(32)]
+#[derive(FromBits, PartialEq, Clone, Copy)]
+struct Word {
+    lo: u16,
+    hi: u16,
+    #[alias]
+    word: u32,
+    // starts at bit 0 again, instead of after `word`
+    #[alias]
+    low_byte: u8,
+}
+
+/// The operand means different things, depending on the opcode.
+#[bitsize(16)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Instruction {
+    #[bits(0..=3)]
+    opcode: u4,
+    #[bits(4..=15)]
+    operand: u12,
+    #[bits(4..=7)]
+    #[alias]
+    register: u4,
+    #[bits(8..=15)]
+    #[alias]
+    immediate: i8,
+}
+
+#[bitsize(16, msb0)]
+#[derive(FromBits, PartialEq, Clone, Copy)]
+struct Msb0 {
+    high: u8,
+    low: u8,
+    #[alias]
+    top: u4,
+    #[alias]
+    top_byte: u8,
+}
+
+#[bitsize(160)]
+#[derive(FromBits, DebugBits, DefaultBits, PartialEq)]
+struct Wide {
+    address: u128,
+    length: u32,
+    #[alias]
+    address_low: u64,
+}
+
+#[test]
+fn getters_and_setters() {
+    let mut word = Word::new(0x5678, 0x1234);
+    assert_eq!(word.word(), 0x1234_5678);
+    word.set_word(0xAABB_CCDD);
+    assert_eq!((word.lo(), word.hi()), (0xCCDD, 0xAABB));
+    assert_eq!(Word::WORD_MASK, u32::MAX);
+    assert_eq!(word.low_byte(), 0xDD);
+    word.set_low_byte(0x11);
+    assert_eq!(word.word(), 0xAABB_CC11);
+    assert_eq!(Word::LOW_BYTE_OFFSET, 0);
+
+    let mut data = Data::new(0x5678, 0x1234);
+    assert_eq!(data.low_byte(), 0x78);
+    assert_eq!(data.bytes(), [0x78, 0x56, 0x34, 0x12]);
+    data.set_bytes_at(3, 0x11);
+    assert_eq!(data.hi(), 0x1134);
+    let data = data.with_low_byte(0);
+    assert_eq!(u32::from(data), 0x1134_5600);
+
+    assert_eq!(Data::LOW_BYTE_OFFSET, 0);
+    assert_eq!(Data::BYTES_OFFSET, 0);
+}
+
+#[test]
+fn bit_ranges() {
+    let mut instruction = Instruction::new(u4::new(0x3), u12::new(0xFE5));
+    assert_eq!(instruction.register(), u4::new(0x5));
+    assert_eq!(instruction.immediate(), -2);
+    instruction.set_immediate(-128);
+    assert_eq!(instruction.operand(), u12::new(0x805));
+    assert_eq!(u16::from(instruction), 0x8053);
+}
+
+#[test]
+fn msb0() {
+    let value = Msb0::new(0xAB, 0xCD);
+    assert_eq!(value.top(), u4::new(0xA));
+    assert_eq!(value.top_byte(), 0xAB);
+    assert_eq!(Msb0::TOP_OFFSET, 12);
+    assert_eq!(Msb0::TOP_BYTE_OFFSET, 8);
+}
+
+#[test]
+fn byte_arrays() {
+    let mut wide = Wide::new(u128::MAX - 1, 7);
+    assert_eq!(wide.address_low(), u64::MAX - 1);
+    wide.set_address_low(3);
+    assert_eq!(wide.address(), (u128::MAX << 64) | 3);
+    assert_eq!(wide.length(), 7);
+    assert_eq!(Wide::default().address_low(), 0);
+}
+
+#[test]
+fn derives_skip_aliases() {
+    let data = Data::new(0x5678, 0x1234);
+    assert_eq!(format!("{:?}", data), "Data { lo: 22136, hi: 4660 }");
+    assert_eq!(format!("{:b}", data), "0001001000110100_0101011001111000");
+    assert_eq!(Data::default(), Data::from(0));
+}
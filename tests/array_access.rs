@@ -0,0 +1,135 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+
+#[bitsize(2)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Cell {
+    Empty,
+    Cross,
+    Circle,
+}
+
+#[bitsize(24)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Grid {
+    rows: [[u4; 3]; 2],
+}
+
+#[bitsize(24, msb0)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Msb0Grid {
+    rows: [[u4; 3]; 2],
+}
+
+#[bitsize(24)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Cube {
+    flag: bool,
+    cells: [[[bool; 2]; 3]; 2],
+    rest: u11,
+}
+
+#[bitsize(18)]
+#[derive(TryFromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Board {
+    cells: [[Cell; 3]; 3],
+}
+
+#[bitsize(144)]
+#[derive(FromBits, DebugBits)]
+struct Wide {
+    words: [[u16; 3]; 3],
+}
+
+#[test]
+fn nested_indices() {
+    let mut grid = Grid::from(u24::new(0x65_4321));
+    assert_eq!(grid.rows_element_at(0, 0), u4::new(1));
+    assert_eq!(grid.rows_element_at(0, 2), u4::new(3));
+    assert_eq!(grid.rows_element_at(1, 0), u4::new(4));
+    assert_eq!(grid.rows_element_at(1, 2), u4::new(6));
+
+    grid.set_rows_element_at(1, 1, u4::new(0xF));
+    assert_eq!(grid.value, u24::new(0x6F_4321));
+    let grid = grid.with_rows_element_at(0, 1, u4::new(0));
+    assert_eq!(grid.rows()[0][1], u4::new(0));
+
+    let mut cube = Cube::from(u24::new(0));
+    cube.set_cells_element_at(1, 2, 1, true);
+    // the last cell is bit 12, after `flag`
+    assert_eq!(cube.value, u24::new(1 << 12));
+    assert!(cube.cells_element_at(1, 2, 1));
+    assert!(cube.cells()[1][2][1]);
+    assert!(!cube.cells_element_at(1, 2, 0));
+}
+
+#[test]
+fn bit_orders_and_storage() {
+    let mut grid = Msb0Grid::from(u24::new(0x12_3456));
+    // the first element is stored at the highest bits
+    assert_eq!(grid.rows_element_at(0, 0), u4::new(1));
+    assert_eq!(grid.rows_element_at(1, 2), u4::new(6));
+    assert_eq!(grid.rows(), [[u4::new(1), u4::new(2), u4::new(3)], [u4::new(4), u4::new(5), u4::new(6)]]);
+    grid.set_rows_element_at(0, 2, u4::new(0xA));
+    assert_eq!(grid.value, u24::new(0x12_A456));
+
+    let mut wide = Wide::from([0; 18]);
+    wide.set_words_element_at(2, 1, 0xBEEF);
+    assert_eq!(wide.words_element_at(2, 1), 0xBEEF);
+    assert_eq!(wide.words()[2][1], 0xBEEF);
+    assert_eq!(wide.words_iter().filter(|&word| word != 0).count(), 1);
+}
+
+#[test]
+#[should_panic(expected = "assertion failed: index_1 < 3")]
+fn out_of_bounds() {
+    Grid::from(u24::new(0)).rows_element_at(0, 3);
+}
+
+#[test]
+fn single_index() {
+    // with one index, the inner array is returned
+    let mut grid = Grid::from(u24::new(0x65_4321));
+    assert_eq!(grid.rows_at(1), [u4::new(4), u4::new(5), u4::new(6)]);
+    grid.set_rows_at(0, [u4::new(0xA); 3]);
+    assert_eq!(grid.value, u24::new(0x65_4AAA));
+    assert_eq!(grid.with_rows_at(1, [u4::new(0); 3]).rows_at(1), [u4::new(0); 3]);
+
+    let msb0 = Msb0Grid::from(u24::new(0x12_3456));
+    assert_eq!(msb0.rows_at(0), [u4::new(1), u4::new(2), u4::new(3)]);
+}
+
+#[test]
+fn iterators() {
+    let grid = Grid::from(u24::new(0x65_4321));
+    let iter = grid.rows_iter();
+    assert_eq!(iter.len(), 6);
+    let values: Vec<u4> = iter.collect();
+    assert_eq!(values, (1..=6).map(u4::new).collect::<Vec<_>>());
+
+    // the iterator follows the index order, not the bit order
+    let msb0 = Msb0Grid::from(u24::new(0x12_3456));
+    assert!(msb0.rows_iter().eq(grid.rows_iter()));
+
+    let board = Board::try_from(u18::new(0b01_00_10_00_01_00_00_00_01)).unwrap();
+    let crosses = board.cells_iter().filter(|&cell| cell == Cell::Cross).count();
+    assert_eq!(crosses, 3);
+    assert!(board.try_cells_iter().all(|cell| cell.is_ok()));
+}
+
+#[test]
+fn try_iterators() {
+    // the cell at [1][1] has the invalid bits 0b11
+    let board = unsafe { Board::from_raw_unchecked(u18::new(0b11 << 8 | 0b10)) };
+    let mut cells = board.try_cells_iter();
+    assert_eq!(cells.len(), 9);
+    assert_eq!(cells.next().unwrap(), Ok(Cell::Circle));
+
+    let error = cells.find_map(Result::err).unwrap();
+    assert_eq!(error.path().to_string(), "cells[1][1]");
+    assert_eq!((error.offset(), error.width(), error.bits()), (8, 2, 0b11));
+    // the following elements are still read
+    assert_eq!(cells.len(), 4);
+    assert!(cells.all(|cell| cell == Ok(Cell::Empty)));
+}
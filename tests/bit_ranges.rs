@@ -0,0 +1,82 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+
+#[bitsize(2)]
+#[derive(FromBits, Debug, PartialEq, Clone, Copy, Default)]
+enum Mode {
+    #[default]
+    Off,
+    Low,
+    High,
+    Max,
+}
+
+/// Fields may be declared in any order, bits 2..=3 and 12..=15 are reserved.
+#[bitsize(16)]
+#[derive(FromBits, DebugBits, BinaryBits, DefaultBits, PartialEq, Clone, Copy)]
+struct Control {
+    #[bits(4..=7)]
+    divider: u4,
+    #[bits(0..2)]
+    mode: Mode,
+    #[bits(8..=11)]
+    lanes: [bool; 4],
+}
+
+#[test]
+fn explicit_ranges() {
+    let control = Control::new(u4::new(0xA), Mode::High, [true, false, false, true]);
+    assert_eq!(u16::from(control), 0b0000_1001_1010_00_10);
+    assert_eq!(control.divider(), u4::new(0xA));
+    assert_eq!(control.mode(), Mode::High);
+    assert!(control.lanes_at(3));
+
+    // reserved bits are kept, but not accessible
+    let mut control = Control::from(0xF00C);
+    assert_eq!(control.divider(), u4::new(0));
+    assert_eq!(control.mode(), Mode::Off);
+    control.set_divider(u4::new(1));
+    assert_eq!(u16::from(control), 0xF01C);
+
+    assert_eq!(Control::default(), Control::from(0));
+}
+
+#[test]
+fn explicit_ranges_fmt() {
+    let control = Control::from(0b1111_1001_1010_11_10);
+    assert_eq!(format!("{control:b}"), "1111_1001_1010_11_10");
+    assert_eq!(
+        format!("{control:?}"),
+        "Control { divider: 10, mode: High, lanes: [true, false, false, true] }"
+    );
+}
+
+/// Single bits and half-open ranges, counted from the most significant bit with `msb0`.
+#[bitsize(8, msb0)]
+#[derive(TryFromBits, DebugBits, BinaryBits, PartialEq, Clone, Copy)]
+struct Status {
+    #[bits(0)]
+    busy: bool,
+    #[bits(4..8)]
+    code: Code,
+}
+
+#[bitsize(4)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Code {
+    Ok = 0x0,
+    Retry = 0x7,
+}
+
+#[test]
+fn explicit_ranges_msb0() {
+    let status = Status::new(true, Code::Retry);
+    assert_eq!(u8::from(status), 0b1000_0111);
+    // the reserved bits in between are not validated
+    let status = Status::try_from(0b0111_0000).unwrap();
+    assert!(!status.busy());
+    assert_eq!(status.code(), Code::Ok);
+    assert!(Status::try_from(0b0000_0001).is_err());
+    assert_eq!(format!("{:b}", Status::new(true, Code::Ok)), "1_000_0000");
+}
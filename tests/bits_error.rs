@@ -0,0 +1,175 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::{prelude::*, BitsError, PathSegment};
+
+#[bitsize(2)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Class {
+    Mass,
+    Network,
+    Display,
+}
+
+#[bitsize(16)]
+#[derive(TryFromBits, DebugBits, Clone, Copy)]
+struct Device {
+    id: u4,
+    big_fumble: [[(Class, u1); 2]; 2],
+}
+
+#[bitsize(4)]
+#[derive(TryFromBits, DebugBits, Clone, Copy)]
+struct Header {
+    valid: bool,
+    class: Class,
+    reserved: u1,
+}
+
+#[bitsize(12)]
+#[derive(TryFromBits, DebugBits, Clone, Copy)]
+struct Outer {
+    flag: bool,
+    headers: [Header; 2],
+    reserved: u3,
+}
+
+#[bitsize(12)]
+#[derive(TryFromBits, DebugBits)]
+struct Deep {
+    levels: [[[Outer; 1]; 1]; 1],
+}
+
+#[bitsize(8, msb0)]
+#[derive(TryFromBits, DebugBits)]
+struct Msb0 {
+    pair: (Class, Class),
+    rest: u4,
+}
+
+#[bitsize(4)]
+#[derive(TryFromBits, DebugBits)]
+struct Pair(bool, Class, bool);
+
+#[bitsize(136)]
+#[derive(TryFromBits)]
+struct Wide {
+    payload: u128,
+    class: Class,
+    padding: u6,
+}
+
+#[bitsize(8)]
+#[derive(TryFromBits, DebugBits)]
+struct Frame {
+    #[must_be(0b101)]
+    start: u3,
+    data: u5,
+}
+
+fn error_of<T>(result: Result<T, BitsError>) -> BitsError {
+    match result {
+        Ok(_) => panic!("the bits should be invalid"),
+        Err(error) => error,
+    }
+}
+
+#[test]
+fn enums() {
+    let error = error_of(Class::try_from(u2::new(3)));
+    assert!(error.path().is_empty());
+    assert_eq!((error.offset(), error.width(), error.bits()), (0, 2, 3));
+    assert_eq!(error.to_string(), "unable to parse bit pattern 0b11 at bits 0..2");
+}
+
+#[test]
+fn nested_arrays_and_tuples() {
+    // big_fumble starts at bit 4, every element is 3 bits
+    let error = error_of(Device::try_from(0b11 << 7));
+    assert_eq!(error.path().to_string(), "big_fumble[0][1].0");
+    assert_eq!(
+        error.path().segments(),
+        [
+            PathSegment::Field("big_fumble"),
+            PathSegment::Index(0),
+            PathSegment::Index(1),
+            PathSegment::Field("0")
+        ]
+    );
+    assert_eq!((error.offset(), error.width(), error.bits()), (7, 2, 3));
+    assert_eq!(error.to_string(), "unable to parse bit pattern 0b11 of `big_fumble[0][1].0` at bits 7..9");
+
+    let error = error_of(Device::try_from(0b11 << 10));
+    assert_eq!(error.path().to_string(), "big_fumble[1][0].0");
+    assert_eq!(error.offset(), 10);
+
+    // the first invalid element is reported
+    let error = error_of(Device::try_from(0b11 << 13 | 0b11 << 4));
+    assert_eq!(error.path().to_string(), "big_fumble[0][0].0");
+    assert_eq!(error.offset(), 4);
+}
+
+#[test]
+fn nested_structs() {
+    // the second header starts at bit 5, its class at bit 6
+    let error = error_of(Outer::try_from(u12::new(0b11 << 6)));
+    assert_eq!(error.path().to_string(), "headers[1].class");
+    assert_eq!((error.offset(), error.width(), error.bits()), (6, 2, 3));
+    assert!(Outer::try_from(u12::new(0b10 << 6)).is_ok());
+}
+
+#[test]
+fn long_paths() {
+    let error = error_of(Deep::try_from(u12::new(0b11 << 6)));
+    assert!(error.path().is_truncated());
+    assert_eq!(error.path().segments().len(), bilge::FieldPath::CAPACITY);
+    // the outermost segments are dropped, the ones leading to the invalid value are kept
+    assert_eq!(error.path().to_string(), "..[0][0].headers[1].class");
+    assert_eq!(error.path().segments().last(), Some(&PathSegment::Field("class")));
+    assert_eq!(error.offset(), 6);
+    // it's returned a lot, so it should stay small
+    assert!(core::mem::size_of::<BitsError>() < 128);
+}
+
+#[test]
+fn bit_orders_and_storage() {
+    // with msb0, the tuple's second element is stored below the first one
+    let error = error_of(Msb0::try_from(0b00_11_0000));
+    assert_eq!(error.path().to_string(), "pair.1");
+    assert_eq!(error.offset(), 4);
+
+    let error = error_of(Pair::try_from(u4::new(0b0_11_0)));
+    assert_eq!(error.path().to_string(), "val_1");
+    assert_eq!(error.offset(), 1);
+
+    let mut bytes = [0; 17];
+    bytes[16] = 0b11;
+    let error = error_of(Wide::try_from_le_bytes(bytes));
+    assert_eq!(error.path().to_string(), "class");
+    assert_eq!((error.offset(), error.width(), error.bits()), (128, 2, 3));
+}
+
+#[test]
+fn required_values() {
+    let error = error_of(Frame::try_from(0b00000_111));
+    assert_eq!(error.path().to_string(), "start");
+    assert_eq!((error.offset(), error.width(), error.bits()), (0, 3, 0b111));
+    assert_eq!(error.to_string(), "unable to parse bit pattern 0b111 of `start` at bits 0..3");
+}
+
+#[test]
+fn set_raw() {
+    let mut device = Device::try_from(0).unwrap();
+    let error = device.set_raw("big_fumble", 0b011_000_000_000).unwrap_err();
+    assert_eq!(error.path().to_string(), "big_fumble[1][1].0");
+    assert_eq!(error.offset(), 13);
+}
+
+#[rustversion::since(1.81)]
+#[test]
+fn is_error() {
+    fn source_of(error: &dyn core::error::Error) -> Option<&dyn core::error::Error> {
+        error.source()
+    }
+    let error = error_of(Class::try_from(u2::new(3)));
+    assert!(source_of(&error).is_none());
+}
@@ -0,0 +1,101 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+use bilge::prelude::*;
+
+#[bitsize(24)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Packet {
+    status: u8,
+    x: u8,
+    y: u8,
+}
+
+#[test]
+fn three_bytes() {
+    let packet = Packet::from_le_bytes([0x39, 0x0F, 0xE7]);
+    assert_eq!(packet.status(), 0x39);
+    assert_eq!(packet.x(), 0x0F);
+    assert_eq!(packet.y(), 0xE7);
+    assert_eq!(packet.to_le_bytes(), [0x39, 0x0F, 0xE7]);
+    assert_eq!(packet.to_be_bytes(), [0xE7, 0x0F, 0x39]);
+    assert_eq!(Packet::from_be_bytes([0xE7, 0x0F, 0x39]), packet);
+}
+
+#[bitsize(40)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Address {
+    low: u32,
+    high: u8,
+}
+
+#[test]
+fn five_bytes() {
+    let address = Address::from_be_bytes([0x12, 0x34, 0x56, 0x78, 0x9A]);
+    assert_eq!(address.high(), 0x12);
+    assert_eq!(address.low(), 0x3456_789A);
+    assert_eq!(address.to_le_bytes(), [0x9A, 0x78, 0x56, 0x34, 0x12]);
+    assert_eq!(Address::from_le_bytes(address.to_le_bytes()), address);
+}
+
+#[bitsize(12)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Short {
+    value: u12,
+}
+
+#[test]
+fn unused_bits_are_ignored() {
+    assert_eq!(Short::from_le_bytes([0xCD, 0xFB]).value(), u12::new(0xBCD));
+    assert_eq!(Short::from_be_bytes([0xFB, 0xCD]).value(), u12::new(0xBCD));
+    assert_eq!(Short::new(u12::new(0xBCD)).to_le_bytes(), [0xCD, 0x0B]);
+    assert_eq!(Short::new(u12::new(0xBCD)).to_be_bytes(), [0x0B, 0xCD]);
+}
+
+#[bitsize(2)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Kind {
+    A,
+    B,
+    C,
+}
+
+#[bitsize(16)]
+#[derive(TryFromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Checked {
+    kind: Kind,
+    rest: u14,
+}
+
+#[test]
+fn try_from_bytes() {
+    let checked = Checked::try_from_le_bytes([0b0000_0110, 0x01]).unwrap();
+    assert_eq!(checked.kind(), Kind::C);
+    assert_eq!(checked.rest(), u14::new(0x0106 >> 2));
+    assert_eq!(Checked::try_from_be_bytes(checked.to_be_bytes()), Ok(checked));
+    assert!(Checked::try_from_le_bytes([0b11, 0]).is_err());
+
+    assert_eq!(Kind::try_from_le_bytes([2]), Ok(Kind::C));
+    assert!(Kind::try_from_be_bytes([3]).is_err());
+    assert_eq!(Kind::B.to_le_bytes(), [1]);
+}
+
+#[bitsize(132)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Wide {
+    low: u128,
+    high: u4,
+}
+
+#[test]
+fn wide_bytes() {
+    let mut bytes = [0; 17];
+    bytes[0] = 0xF1;
+    bytes[16] = 0xFA;
+    let wide = Wide::from_le_bytes(bytes);
+    assert_eq!(wide.low(), 0xF1);
+    assert_eq!(wide.high(), u4::new(0xA));
+
+    let be = wide.to_be_bytes();
+    assert_eq!(be[0], 0x0A);
+    assert_eq!(be[16], 0xF1);
+    assert_eq!(Wide::from_be_bytes(be), wide);
+}
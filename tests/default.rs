@@ -0,0 +1,117 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+
+#[bitsize(2)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy, Default)]
+enum Mode {
+    #[default]
+    Off,
+    Slow,
+    Fast,
+}
+
+/// A control register, with the reset values from its datasheet.
+#[bitsize(16)]
+#[derive(TryFromBits, DebugBits, DefaultBits, PartialEq, Clone, Copy)]
+struct Control {
+    enable: bool,
+    #[default = u3::new(5)]
+    divider: u3,
+    #[default(0b10)]
+    mode: Mode,
+    #[default(-3i8 as u8)]
+    trim: i8,
+    #[default = (true, i1::new(-1))]
+    flags: (bool, i1),
+}
+
+#[bitsize(8, msb0)]
+#[derive(FromBits, DebugBits, DefaultBits, PartialEq, Clone, Copy)]
+struct Msb0Defaults {
+    #[default(0b101)]
+    high: u3,
+    low: u5,
+}
+
+#[bitsize(136)]
+#[derive(FromBits, DebugBits, DefaultBits, PartialEq)]
+struct Wide {
+    #[default = u100::new(1 << 99)]
+    low: u100,
+    #[default(0xAB)]
+    middle: u8,
+    #[default = [u7::new(1), u7::new(2), u7::new(3), u7::new(4)]]
+    high: [u7; 4],
+}
+
+/// The datasheet gives the reset value of the whole register.
+#[bitsize(32)]
+#[reset_value(0x0000_1F00)]
+#[derive(TryFromBits, DebugBits, DefaultBits, PartialEq, Clone, Copy)]
+struct Status {
+    flags: u8,
+    level: u5,
+    mode: Mode,
+    reserved: u17,
+}
+
+#[bitsize(4)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Op {
+    #[tag(0)]
+    Nop,
+    #[tag(1)]
+    Load(u3),
+}
+
+/// The reset value of nested fields is checked at compile time as well.
+#[bitsize(16)]
+#[reset_value(0xB691)]
+#[derive(TryFromBits, DebugBits, DefaultBits, PartialEq, Clone, Copy)]
+struct Channels {
+    enable: bool,
+    reserved: u3,
+    modes: [Mode; 2],
+    pair: (Mode, bool, bool),
+    op: Op,
+}
+
+#[test]
+fn field_defaults() {
+    let control = Control::default();
+    assert!(!control.enable());
+    assert_eq!(control.divider(), u3::new(5));
+    assert_eq!(control.mode(), Mode::Fast);
+    assert_eq!(control.trim(), -3);
+    assert_eq!(control.flags(), (true, i1::new(-1)));
+    assert_eq!(u16::from(control), 0b11_11111101_10_101_0);
+
+    let msb0 = Msb0Defaults::default();
+    assert_eq!(u8::from(msb0), 0b101_00000);
+}
+
+#[test]
+fn byte_array_field_defaults() {
+    let wide = Wide::default();
+    assert_eq!(wide.low(), u100::new(1 << 99));
+    assert_eq!(wide.middle(), 0xAB);
+    assert_eq!(wide.high(), [u7::new(1), u7::new(2), u7::new(3), u7::new(4)]);
+}
+
+#[test]
+fn reset_value() {
+    let status = Status::default();
+    assert_eq!(u32::from(status), 0x0000_1F00);
+    assert_eq!(status.level(), u5::new(0x1F));
+    assert_eq!(status.mode(), Mode::Off);
+}
+
+#[test]
+fn nested_reset_value() {
+    let channels = Channels::default();
+    assert!(channels.enable());
+    assert_eq!(channels.modes(), [Mode::Slow, Mode::Fast]);
+    assert_eq!(channels.pair(), (Mode::Fast, true, false));
+    assert_eq!(channels.op(), Op::Load(u3::new(5)));
+}
@@ -0,0 +1,88 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+use bilge::prelude::*;
+
+const OP_ADD: u8 = 0x10;
+const OP_SUB: u8 = OP_ADD + 1;
+
+#[bitsize(5)]
+#[derive(TryFromBits, BinaryBits, Debug, PartialEq, Clone, Copy)]
+enum Opcode {
+    Nop,
+    Add = OP_ADD,
+    Sub = OP_SUB,
+    // counts up from the previous expression
+    Mul,
+    Jump = 1 << 3,
+    Halt = 0x1F,
+}
+
+#[bitsize(3)]
+#[derive(FromBits, BinaryBits, Debug, PartialEq, Clone, Copy)]
+enum Speed {
+    Slow = Speed::BASE as isize,
+    Medium,
+    Fast,
+    #[fallback]
+    Unknown,
+}
+
+impl Speed {
+    const BASE: u16 = 1;
+}
+
+#[bitsize(8)]
+#[repr(u8)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum WithRepr {
+    First = OP_ADD,
+    Second = OP_SUB * 2,
+}
+
+#[bitsize(48)]
+#[repr(u64)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Address {
+    Low = 0x1000,
+    // above `i32::MAX`
+    High = 1 << 40,
+    Higher,
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(Opcode::try_from(u5::new(0)), Ok(Opcode::Nop));
+    assert_eq!(Opcode::try_from(u5::new(0x10)), Ok(Opcode::Add));
+    assert_eq!(Opcode::try_from(u5::new(0x11)), Ok(Opcode::Sub));
+    assert_eq!(Opcode::try_from(u5::new(0x12)), Ok(Opcode::Mul));
+    assert_eq!(Opcode::try_from(u5::new(0x08)), Ok(Opcode::Jump));
+    assert_eq!(Opcode::try_from(u5::new(0x1F)), Ok(Opcode::Halt));
+    assert!(Opcode::try_from(u5::new(0x13)).is_err());
+    assert_eq!(u5::from(Opcode::Mul), u5::new(0x12));
+    assert_eq!(u5::from(Opcode::Jump), u5::new(0x08));
+
+    assert_eq!(Speed::from(u3::new(0)), Speed::Unknown);
+    assert_eq!(Speed::from(u3::new(1)), Speed::Slow);
+    assert_eq!(Speed::from(u3::new(3)), Speed::Fast);
+    assert_eq!(Speed::from(u3::new(7)), Speed::Unknown);
+    assert_eq!(u3::from(Speed::Medium), u3::new(2));
+    assert_eq!(u3::from(Speed::Unknown), u3::new(4));
+
+    assert_eq!(WithRepr::try_from(0x22), Ok(WithRepr::Second));
+    assert_eq!(u8::from(WithRepr::First), 0x10);
+
+    assert_eq!(Address::try_from(u48::new((1 << 40) + 1)), Ok(Address::Higher));
+    assert_eq!(u48::from(Address::High), u48::new(1 << 40));
+}
+
+#[test]
+fn rust_discriminants() {
+    assert_eq!(Opcode::Mul as u8, 0x12);
+    assert_eq!(WithRepr::Second as u8, 0x22);
+    assert_eq!(Address::Higher as u64, (1 << 40) + 1);
+}
+
+#[test]
+fn binary() {
+    assert_eq!(format!("{:b}", Opcode::Sub), "10001");
+    assert_eq!(format!("{:b}", Speed::Fast), "011");
+}
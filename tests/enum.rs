@@ -39,7 +39,7 @@ fn conversions() {
                 }
                 assert_eq!(u2::from(a), value);
             }
-            Err(e) => assert_eq!(format!("{e:?}"), "BitsError"),
+            Err(e) => assert_eq!(e.to_string(), "unable to parse bit pattern 0b11 at bits 0..2"),
         }
     }
 }
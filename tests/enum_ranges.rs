@@ -0,0 +1,103 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+
+/// The reserved values all map to one variant.
+#[bitsize(3)]
+#[derive(FromBits, BinaryBits, Debug, PartialEq, Clone, Copy)]
+enum Mode {
+    Off,
+    Slow,
+    Fast,
+    Turbo,
+    #[bits(4..=7)]
+    Reserved,
+}
+
+/// `Enabled` counts up from the highest value of `Disabled`.
+#[bitsize(2)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Switch {
+    #[bits(0 | 1)]
+    Disabled,
+    Enabled,
+}
+
+/// The values of `Sleep` aren't in order, so `Deep` comes after 5.
+#[bitsize(3)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Power {
+    On,
+    #[bits(4..=5 | 1)]
+    Sleep,
+    Deep,
+}
+
+#[bitsize(4)]
+#[derive(FromBits, Debug, PartialEq, Clone, Copy)]
+enum Level {
+    #[bits(0..4 | 8)]
+    Low,
+    High = 9,
+    #[fallback]
+    Invalid,
+}
+
+#[bitsize(10)]
+#[derive(TryFromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Control {
+    mode: Mode,
+    switch: Switch,
+    level: Level,
+    ready: bool,
+}
+
+#[test]
+fn ranges_fill_the_enum() {
+    assert_eq!(Mode::from(u3::new(3)), Mode::Turbo);
+    assert_eq!(Mode::from(u3::new(4)), Mode::Reserved);
+    assert_eq!(Mode::from(u3::new(7)), Mode::Reserved);
+    // the canonical value is the first one
+    assert_eq!(u3::from(Mode::Reserved), u3::new(4));
+    assert_eq!(Mode::Reserved as u8, 4);
+    assert_eq!(format!("{:b}", Mode::Reserved), "100");
+}
+
+#[test]
+fn alternatives() {
+    assert_eq!(Switch::try_from(u2::new(0)), Ok(Switch::Disabled));
+    assert_eq!(Switch::try_from(u2::new(1)), Ok(Switch::Disabled));
+    assert_eq!(Switch::try_from(u2::new(2)), Ok(Switch::Enabled));
+    assert!(Switch::try_from(u2::new(3)).is_err());
+    assert_eq!(u2::from(Switch::Disabled), u2::new(0));
+    assert_eq!(u2::from(Switch::Enabled), u2::new(2));
+    assert_eq!(Switch::Enabled as u8, 2);
+
+    assert_eq!(Power::try_from(u3::new(1)), Ok(Power::Sleep));
+    assert_eq!(Power::try_from(u3::new(6)), Ok(Power::Deep));
+    assert!(Power::try_from(u3::new(2)).is_err());
+    assert_eq!(u3::from(Power::Sleep), u3::new(4));
+    assert_eq!(Power::Sleep as u8, 4);
+    assert_eq!(Power::Deep as u8, 6);
+}
+
+#[test]
+fn ranges_and_fallback() {
+    for value in [0, 3, 8] {
+        assert_eq!(Level::from(u4::new(value)), Level::Low);
+    }
+    assert_eq!(Level::from(u4::new(9)), Level::High);
+    assert_eq!(u4::from(Level::Low), u4::new(0));
+    assert_eq!(Level::from(u4::new(4)), Level::Invalid);
+    assert_eq!(Level::from(u4::new(15)), Level::Invalid);
+}
+
+#[test]
+fn in_structs() {
+    let control = Control::try_from(u10::new(0b1_0100_10_110)).unwrap();
+    assert_eq!(control.mode(), Mode::Reserved);
+    assert_eq!(control.switch(), Switch::Enabled);
+    assert_eq!(control.level(), Level::Invalid);
+    assert!(control.ready());
+    assert!(Control::try_from(u10::new(0b0_0000_11_000)).is_err());
+}
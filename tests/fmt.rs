@@ -37,7 +37,7 @@ struct Lunch(Bangers, Mash, Register);
 fn binary_formatting() {
     let b = u10::new(0b1100110011).into();
     let m = u2::new(0b00).into();
-    let reg = u52::new(0b110010110010101001_1011_1011011001100011_1_1011001100000).into();
+    let reg = u52::new(0b1100101100101010011011101101100110001111011001100000).into();
 
     let lunch = Lunch::new(b, m, reg);
 
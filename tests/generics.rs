@@ -0,0 +1,126 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::arbitrary_int::UInt;
+use bilge::prelude::*;
+
+/// A page-table entry, whose payload differs between table levels.
+#[bitsize(16)]
+#[derive(FromBits, DebugBits, DefaultBits, BinaryBits, PartialEq, Clone, Copy)]
+struct Entry<T> {
+    present: bool,
+    payload: T,
+}
+
+/// The same entry, for payloads which can hold invalid values.
+#[bitsize(16)]
+#[derive(TryFromBits, DebugBits, PartialEq, Clone, Copy)]
+struct CheckedEntry<T: Copy> {
+    present: bool,
+    payload: T,
+}
+
+#[bitsize(15)]
+#[derive(FromBits, DebugBits, DefaultBits, PartialEq, Clone, Copy)]
+struct Table {
+    index: u12,
+    level: u3,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[bitsize(1)]
+#[derive(FromBits)]
+enum Kind {
+    #[default]
+    Code,
+    Data,
+}
+
+#[bitsize(15)]
+#[derive(TryFromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Descriptor {
+    limit: u14,
+    kind: Kind,
+}
+
+#[bitsize(8)]
+#[derive(TryFromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Tagged<T>(T, u4, bool)
+where
+    T: Copy;
+
+/// Const parameters work as well, the size check makes sure `N` is 7.
+#[bitsize(8)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Padded<const N: usize> {
+    valid: bool,
+    data: UInt<u8, N>,
+}
+
+#[bitsize(3)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Tiny {
+    A = 0,
+    B = 1,
+}
+
+#[test]
+fn integer_payload() {
+    let mut entry = Entry::new(true, u15::new(0x1234));
+    assert!(entry.present());
+    assert_eq!(entry.payload(), u15::new(0x1234));
+    entry.set_payload(u15::new(0x7FFF));
+    assert_eq!(u16::from(entry), 0xFFFF);
+
+    let entry = Entry::<u15>::from(0b0000_0000_0000_0110);
+    assert!(!entry.present());
+    assert_eq!(entry.payload(), u15::new(3));
+    assert_eq!(Entry::<u15>::default(), Entry::new(false, u15::new(0)));
+}
+
+#[test]
+fn struct_payload() {
+    let table = Table::new(u12::new(0xABC), u3::new(5));
+    let entry = Entry::new(true, table);
+    assert_eq!(entry.payload(), table);
+    assert_eq!(entry.payload().level(), u3::new(5));
+    assert_eq!(u16::from(entry), 0b101_1010_1011_1100_1);
+
+    let entry = Entry::<Table>::from(u16::from(entry));
+    assert_eq!(entry.payload().index(), u12::new(0xABC));
+}
+
+#[test]
+fn try_from_payload() {
+    let descriptor = Descriptor::new(u14::new(100), Kind::Data);
+    let entry = CheckedEntry::new(false, descriptor);
+    assert_eq!(u16::from(entry), (1 << 15) | (100 << 1));
+
+    let mut entry = CheckedEntry::<Descriptor>::try_from(u16::from(entry)).unwrap();
+    assert_eq!(entry.payload().kind(), Kind::Data);
+    entry.set_present(true);
+    assert!(entry.present());
+
+    assert!(Tagged::<Tiny>::try_from(0b1_0101_010).is_err());
+    let tagged = Tagged::<Tiny>::try_from(0b1_0101_001).unwrap();
+    assert_eq!(tagged.val_0(), Tiny::B);
+    assert_eq!(tagged.val_1(), u4::new(0b0101));
+    assert!(tagged.val_2());
+}
+
+#[test]
+fn const_parameter() {
+    let mut padded = Padded::<7>::new(true, u7::new(0x40));
+    assert_eq!(u8::from(padded), 0b1000_0001);
+    padded.set_data(u7::new(1));
+    assert_eq!(padded.data(), u7::new(1));
+    assert_eq!(Padded::<7>::from(0xFF).data(), u7::new(0x7F));
+}
+
+#[test]
+fn fmt() {
+    let entry = Entry::new(true, u15::new(3));
+    assert_eq!(format!("{:?}", entry), "Entry { present: true, payload: 3 }");
+    assert_eq!(format!("{:b}", entry), "000000000000011_1");
+    let entry = Entry::new(false, Table::new(u12::new(1), u3::new(0)));
+    assert_eq!(format!("{:?}", entry), "Entry { present: false, payload: Table { index: 1, level: 0 } }");
+}
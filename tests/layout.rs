@@ -0,0 +1,91 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+
+#[bitsize(32)]
+#[derive(FromBits)]
+struct Register {
+    header: u4,
+    body: u7,
+    flags: (bool, u2),
+    reserved: u2,
+    lanes: [u4; 4],
+}
+
+#[bitsize(16, msb0)]
+#[derive(FromBits)]
+struct Msb0 {
+    high: u4,
+    low: u12,
+}
+
+#[bitsize(16)]
+#[derive(FromBits)]
+struct Ranges {
+    #[bits(4..=7)]
+    divider: u4,
+    #[bits(15)]
+    enable: bool,
+}
+
+#[bitsize(8)]
+#[derive(FromBits)]
+struct Tuple(u3, u5);
+
+#[bitsize(200)]
+#[derive(FromBits)]
+struct Wide {
+    low: u4,
+    middle: [u64; 2],
+    high: u68,
+}
+
+#[test]
+fn offsets_widths_and_masks() {
+    assert_eq!(Register::HEADER_OFFSET, 0u32);
+    assert_eq!(Register::HEADER_WIDTH, 4u32);
+    assert_eq!(Register::HEADER_MASK, 0x0000_000Fu32);
+    assert_eq!(Register::BODY_OFFSET, 4);
+    assert_eq!(Register::BODY_MASK, 0x0000_07F0);
+    assert_eq!(Register::FLAGS_OFFSET, 11);
+    assert_eq!(Register::FLAGS_WIDTH, 3);
+    assert_eq!(Register::RESERVED_I_OFFSET, 14);
+    assert_eq!(Register::LANES_OFFSET, 16);
+    assert_eq!(Register::LANES_WIDTH, 16);
+    assert_eq!(Register::LANES_MASK, 0xFFFF_0000);
+
+    // the masks select the field's bits
+    let register = Register::from(0xDEAD_BEEF);
+    assert_eq!(
+        (register.raw() & Register::BODY_MASK) >> Register::BODY_OFFSET,
+        register.body().value() as u32
+    );
+}
+
+#[test]
+fn bit_order_and_ranges() {
+    assert_eq!(Msb0::HIGH_OFFSET, 12u16);
+    assert_eq!(Msb0::HIGH_MASK, 0xF000u16);
+    assert_eq!(Msb0::LOW_MASK, 0x0FFF);
+
+    assert_eq!(Ranges::DIVIDER_OFFSET, 4u16);
+    assert_eq!(Ranges::DIVIDER_MASK, 0x00F0);
+    assert_eq!(Ranges::ENABLE_MASK, 0x8000);
+
+    assert_eq!(Tuple::VAL_0_MASK, 0b000_111u8);
+    assert_eq!(Tuple::VAL_1_OFFSET, 3);
+    assert_eq!(Tuple::VAL_1_MASK, 0b11111_000);
+}
+
+#[test]
+fn byte_arrays() {
+    assert_eq!(Wide::MIDDLE_OFFSET, 4usize);
+    assert_eq!(Wide::MIDDLE_WIDTH, 128usize);
+    assert_eq!(Wide::HIGH_OFFSET, 132);
+    assert_eq!(Wide::LOW_MASK[0], 0x0F);
+    assert_eq!(Wide::MIDDLE_MASK[0], 0xF0);
+    assert_eq!(Wide::MIDDLE_MASK[1..16], [0xFF; 15]);
+    assert_eq!(Wide::MIDDLE_MASK[16], 0x0F);
+    assert_eq!(Wide::HIGH_MASK[16], 0xF0);
+    assert_eq!(Wide::HIGH_MASK[24], 0xFF);
+}
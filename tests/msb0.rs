@@ -0,0 +1,134 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+use bilge::prelude::*;
+
+/// The IPv4 header's first word, as listed in RFC 791: the first field is the most significant one.
+#[bitsize(32, msb0)]
+#[derive(FromBits, DebugBits, BinaryBits, DefaultBits, PartialEq, Clone, Copy)]
+struct Ipv4Word {
+    version: u4,
+    ihl: u4,
+    dscp: u6,
+    ecn: u2,
+    total_length: u16,
+}
+
+#[test]
+fn first_field_is_most_significant() {
+    let word = Ipv4Word::from(0x4500_0054);
+    assert_eq!(word.version(), u4::new(4));
+    assert_eq!(word.ihl(), u4::new(5));
+    assert_eq!(word.dscp(), u6::new(0));
+    assert_eq!(word.ecn(), u2::new(0));
+    assert_eq!(word.total_length(), 0x54);
+
+    let mut word = Ipv4Word::new(u4::new(4), u4::new(5), u6::new(0b10_1110), u2::new(0b01), 0x1234);
+    assert_eq!(u32::from(word), 0x45B9_1234);
+    word.set_ihl(u4::new(0xF));
+    assert_eq!(u32::from(word), 0x4FB9_1234);
+
+    assert_eq!(format!("{word:b}"), "0100_1111_101110_01_0001001000110100");
+    assert_eq!(
+        format!("{word:?}"),
+        "Ipv4Word { version: 4, ihl: 15, dscp: 46, ecn: 1, total_length: 4660 }"
+    );
+}
+
+#[bitsize(1)]
+#[derive(TryFromBits, Debug, PartialEq, Default)]
+enum Flag {
+    #[default]
+    Unset,
+}
+
+#[bitsize(16, lsb0)]
+#[derive(FromBits, PartialEq, Clone, Copy)]
+struct ExplicitLsb0 {
+    low: u4,
+    high: u12,
+}
+
+#[bitsize(24, msb0)]
+#[derive(TryFromBits, DebugBits, DefaultBits, PartialEq, Clone, Copy)]
+struct Nested {
+    lanes: [u4; 3],
+    pair: (u2, u6),
+    flag: Flag,
+    rest: u3,
+}
+
+#[test]
+fn arrays_and_tuples_are_reversed() {
+    let nested = Nested::new(
+        [u4::new(0x1), u4::new(0x2), u4::new(0x3)],
+        (u2::new(0b10), u6::new(0b00_0001)),
+        Flag::Unset,
+        u3::new(0),
+    );
+    // lanes[0] is the most significant nibble, pair.0 comes before pair.1
+    assert_eq!(u24::from(nested), u24::new(0x0012_3810));
+    assert_eq!(nested.lanes(), [u4::new(0x1), u4::new(0x2), u4::new(0x3)]);
+    assert_eq!(nested.lanes_at(0), u4::new(0x1));
+    assert_eq!(nested.lanes_at(2), u4::new(0x3));
+    assert_eq!(nested.pair(), (u2::new(0b10), u6::new(0b00_0001)));
+
+    let mut nested = nested;
+    nested.set_lanes_at(0, u4::new(0xA));
+    nested.set_pair((u2::new(0b01), u6::new(0b11_1111)));
+    assert_eq!(u24::from(nested), u24::new(0x00A2_37F0));
+    assert_eq!(nested.lanes(), [u4::new(0xA), u4::new(0x2), u4::new(0x3)]);
+    assert_eq!(nested.pair(), (u2::new(0b01), u6::new(0b11_1111)));
+
+    assert_eq!(Nested::default(), Nested::try_from(u24::new(0)).unwrap());
+}
+
+#[test]
+fn try_from_checks_fields_at_their_offsets() {
+    // `flag` is bit 3, counting from the least significant bit
+    assert!(Nested::try_from(u24::new(0xFF_FFF7)).is_ok());
+    assert!(Nested::try_from(u24::new(0x00_0008)).is_err());
+}
+
+#[test]
+fn lsb0_is_the_default() {
+    let explicit = ExplicitLsb0::from(0xABC1);
+    assert_eq!(explicit.low(), u4::new(0x1));
+    assert_eq!(explicit.high(), u12::new(0xABC));
+}
+
+/// Wide bitfields are stored as a little-endian byte array, msb0 still places the first field at the highest bits.
+#[bitsize(136, msb0)]
+#[derive(FromBits, DebugBits, BinaryBits, DefaultBits, PartialEq, Clone, Copy)]
+struct WideMsb0 {
+    tag: u8,
+    lanes: [u8; 2],
+    payload: u104,
+    pair: (u4, u4),
+}
+
+#[test]
+fn byte_array_msb0() {
+    let wide = WideMsb0::new(0xAB, [0x01, 0x02], u104::new(0x1234), (u4::new(0xC), u4::new(0xD)));
+    let bytes = <[u8; 17]>::from(wide);
+    assert_eq!(bytes[16], 0xAB);
+    assert_eq!(bytes[15], 0x01);
+    assert_eq!(bytes[14], 0x02);
+    assert_eq!(bytes[0], 0xCD);
+    assert_eq!(bytes[1], 0x34);
+    assert_eq!(bytes[2], 0x12);
+
+    let mut wide = WideMsb0::from(bytes);
+    assert_eq!(wide.tag(), 0xAB);
+    assert_eq!(wide.lanes(), [0x01, 0x02]);
+    assert_eq!(wide.lanes_at(1), 0x02);
+    assert_eq!(wide.payload(), u104::new(0x1234));
+    assert_eq!(wide.pair(), (u4::new(0xC), u4::new(0xD)));
+
+    wide.set_lanes_at(0, 0xFF);
+    wide.set_pair((u4::new(0x1), u4::new(0x2)));
+    let bytes = <[u8; 17]>::from(wide);
+    assert_eq!(bytes[15], 0xFF);
+    assert_eq!(bytes[0], 0x12);
+
+    assert_eq!(WideMsb0::default(), WideMsb0::from([0; 17]));
+    assert!(format!("{wide:b}").starts_with("10101011_1111111100000010_"));
+}
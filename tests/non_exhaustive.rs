@@ -0,0 +1,33 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+use bilge::prelude::*;
+use registers::Control;
+
+#[bitsize(8)]
+#[derive(TryFromBits, DebugBits, DefaultBits, VisitBits, PartialEq, Clone, Copy)]
+#[non_exhaustive]
+struct Local {
+    flag: bool,
+    reserved: u7,
+}
+
+#[test]
+fn other_crates_use_conversions_and_accessors() {
+    let mut control = Control::try_from(0b1011).unwrap();
+    assert!(control.enable());
+    assert_eq!(control.mode(), u3::new(0b101));
+    control.set_mode(u3::new(0b010));
+    let control = control.with_enable(false);
+    assert_eq!(u16::from(control), 0b0100);
+    assert_eq!(format!("{:?}", control), "Control { enable: false, mode: 2, reserved_i: 0 }");
+}
+
+#[test]
+fn own_crate_uses_constructors() {
+    let local = Local::new(true);
+    assert_eq!(u8::from(local), 1);
+    assert_eq!(
+        Local::new_preserving(Local::try_from(0xFE).unwrap(), true),
+        Local::try_from(0xFF).unwrap()
+    );
+    assert_eq!(Local::default(), Local::new(false));
+}
@@ -0,0 +1,94 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+
+#[bitsize(16)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Register {
+    enable: bool,
+    reserved: u3,
+    mode: u4,
+    #[read_only]
+    busy: bool,
+    padding: u7,
+}
+
+#[bitsize(8)]
+#[derive(FromBits, PartialEq, Clone, Copy)]
+struct Interrupts {
+    #[w1c]
+    pending: u2,
+    mask: u2,
+    reserved: u4,
+}
+
+#[bitsize(8)]
+#[derive(TryFromBits, PartialEq, Clone, Copy)]
+struct Header {
+    #[must_be(0b1)]
+    always_one: u1,
+    length: u7,
+}
+
+#[bitsize(16)]
+#[derive(FromBits, PartialEq, Clone, Copy)]
+struct Ranges {
+    #[bits(0..=3)]
+    low: u4,
+    #[bits(12..=15)]
+    high: u4,
+}
+
+#[bitsize(136)]
+#[derive(FromBits, PartialEq)]
+struct Wide {
+    value: u128,
+    reserved: u8,
+}
+
+#[test]
+fn keeps_bits_which_are_not_arguments() {
+    let read = Register::from(0xFFFF);
+    let written = Register::new_preserving(read, false, u4::new(0x5));
+    assert_eq!(u16::from(written), 0b1111111_1_0101_111_0);
+    assert_eq!(written.reserved_i(), u3::new(0b111));
+    assert!(written.busy());
+    // `new` writes zero instead
+    assert_eq!(u16::from(Register::new(false, u4::new(0x5))), 0b0101_000_0);
+
+    let ranges = Ranges::new_preserving(Ranges::from(0xFFFF), u4::new(0), u4::new(0));
+    assert_eq!(u16::from(ranges), 0x0FF0);
+
+    let wide = Wide::new_preserving(Wide::from_le_bytes([0xFF; 17]), 0);
+    assert_eq!(wide.reserved_i(), 0xFF);
+    assert_eq!(wide.value(), 0);
+}
+
+#[test]
+fn zeroes_write_one_fields() {
+    let read = Interrupts::from(0xFF);
+    let written = Interrupts::new_preserving(read, u2::new(0));
+    assert_eq!(u8::from(written), 0b1111_00_00);
+}
+
+#[test]
+fn required_values_are_kept() {
+    let header = Header::new_preserving(Header::new(u7::new(1)), u7::new(9));
+    assert_eq!(u8::from(header), (9 << 1) | 1);
+}
+
+#[test]
+fn reserved_setters_are_unsafe() {
+    let mut register = Register::new(true, u4::new(0));
+    unsafe {
+        register.set_reserved_i(u3::new(0b101));
+        register.set_padding_i(u7::new(1));
+    }
+    assert_eq!(u16::from(register), (1 << 9) | (0b101 << 1) | 1);
+    // reserved bits still can't be written by name
+    assert!(register.set_raw("reserved_i", 0).is_err());
+
+    let mut header = Header::new(u7::new(2));
+    unsafe { header.set_always_one(u1::new(0)) };
+    assert_eq!(u8::from(header), 2 << 1);
+}
@@ -0,0 +1,139 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+use bilge::Access;
+
+#[bitsize(2)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Mode {
+    Off,
+    Slow,
+    Fast,
+}
+
+#[bitsize(6)]
+#[derive(TryFromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Clock {
+    mode: Mode,
+    divider: u4,
+}
+
+#[bitsize(32)]
+#[derive(TryFromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Control {
+    enable: bool,
+    clock: Clock,
+    reserved: u1,
+    #[read_only]
+    status: u4,
+    #[w1c]
+    irq: bool,
+    lanes: [u4; 2],
+    pair: (u2, bool),
+    padding: u8,
+}
+
+#[bitsize(8)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Command {
+    #[write_only]
+    opcode: u4,
+    flags: u4,
+}
+
+#[bitsize(8, msb0)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Msb0(u3, u5);
+
+#[bitsize(236)]
+#[derive(FromBits, DebugBits, PartialEq)]
+struct Wide {
+    low: u100,
+    middle: u8,
+    high: [u64; 2],
+}
+
+#[test]
+fn fields() {
+    let fields = Control::FIELDS;
+    let names: Vec<&str> = fields.iter().map(|field| field.name).collect();
+    assert_eq!(names, ["enable", "clock", "reserved_i", "status", "irq", "lanes", "pair", "padding_i"]);
+    let status = fields[3];
+    assert_eq!(status.name, "status");
+    assert_eq!((status.offset, status.width), (8, 4));
+    assert_eq!(status.type_name, "u4");
+    assert!(!status.reserved);
+    assert!(!status.alias);
+    assert_eq!(status.access, Access::ReadOnly);
+    assert!(status.fields.is_empty());
+    assert!(fields[2].reserved);
+    assert!(fields[7].reserved);
+    assert_eq!(fields[4].access, Access::WriteOneToClear);
+    assert_eq!(fields[5].type_name, "[u4; 2]");
+    assert_eq!(fields[5].width, 8);
+    assert_eq!(fields[6].type_name, "(u2, bool)");
+    assert_eq!(fields[6].offset, 21);
+
+    // nested structs show up as child tables
+    assert_eq!(fields[1].fields, Clock::FIELDS);
+    assert_eq!(fields[1].fields[1].name, "divider");
+    assert_eq!(fields[1].fields[1].offset, 2);
+    assert!(Mode::FIELDS.is_empty());
+
+    assert_eq!(Msb0::FIELDS[0].name, "val_0");
+    assert_eq!(Msb0::FIELDS[0].offset, 5);
+    assert_eq!(Msb0::FIELDS[1].offset, 0);
+}
+
+#[test]
+fn get_raw() {
+    let control = Control::try_from(0x00AB_1F25).unwrap();
+    assert_eq!(control.get_raw("enable"), Ok(1));
+    assert_eq!(control.get_raw("clock"), Ok(0b10010));
+    assert_eq!(control.get_raw("status"), Ok(0xF));
+    assert_eq!(control.get_raw("lanes"), Ok(0x58));
+    assert_eq!(control.get_raw("padding_i"), Ok(0));
+    assert!(control.get_raw("missing").is_err());
+
+    // like read-only fields can't be set, write-only ones can't be read
+    let command = Command::from(0x5A);
+    assert!(command.get_raw("opcode").is_err());
+    assert_eq!(command.get_raw("flags"), Ok(0x5));
+
+    let wide = Wide::new(u100::new(3), 0x7E, [u64::MAX, 1]);
+    assert_eq!(wide.get_raw("low"), Ok(3));
+    assert_eq!(wide.get_raw("middle"), Ok(0x7E));
+    assert_eq!(wide.get_raw("high"), Ok(u64::MAX as u128 | 1 << 64));
+}
+
+#[test]
+fn set_raw() {
+    let mut control = Control::try_from(0x0000_1000).unwrap();
+    assert_eq!(control.set_raw("clock", 0b0110_10), Ok(()));
+    assert_eq!(control.clock(), Clock::new(Mode::Fast, u4::new(0b0110)));
+    assert_eq!(control.set_raw("pair", 0b1_11), Ok(()));
+    assert_eq!(control.pair(), (u2::new(3), true));
+    // other W1C fields are written as zero, like with the setters
+    assert!(!control.irq());
+    assert_eq!(control.set_raw("irq", 1), Ok(()));
+    assert!(control.irq());
+
+    let before = control;
+    // invalid enum value
+    assert!(control.set_raw("clock", 0b11).is_err());
+    // too wide
+    assert!(control.set_raw("enable", 2).is_err());
+    // not writable
+    assert!(control.set_raw("status", 0).is_err());
+    assert!(control.set_raw("reserved_i", 1).is_err());
+    assert!(control.set_raw("missing", 0).is_err());
+    assert_eq!(control, before);
+
+    let mut wide = Wide::new(u100::new(0), 0, [0, 0]);
+    assert_eq!(wide.set_raw("low", u128::MAX >> 28), Ok(()));
+    assert_eq!(wide.low(), u100::new(u128::MAX >> 28));
+    assert!(wide.set_raw("low", 1 << 100).is_err());
+    assert_eq!(wide.set_raw("high", u128::MAX), Ok(()));
+    assert_eq!(wide.high(), [u64::MAX; 2]);
+    assert_eq!(wide.middle(), 0);
+}
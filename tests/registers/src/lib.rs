@@ -0,0 +1,12 @@
+//! Bitfields defined in another crate, to check what downstream crates can use.
+use bilge::prelude::*;
+
+/// A register which is expected to get new fields, e.g. when `reserved` gets used.
+#[bitsize(16)]
+#[derive(TryFromBits, DebugBits, PartialEq, Clone, Copy)]
+#[non_exhaustive]
+pub struct Control {
+    pub enable: bool,
+    pub mode: u3,
+    reserved: u12,
+}
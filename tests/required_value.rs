@@ -0,0 +1,125 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+
+#[bitsize(24)]
+#[derive(TryFromBits, DebugBits, DefaultBits, PartialEq, Clone, Copy)]
+struct MousePacket {
+    left: bool,
+    right: bool,
+    middle: bool,
+    #[must_be(0b1)]
+    always_one: u1,
+    x_sign: bool,
+    y_sign: bool,
+    x_overflow: bool,
+    y_overflow: bool,
+    x: u8,
+    y: u8,
+}
+
+#[bitsize(16)]
+#[derive(TryFromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Status {
+    ready: bool,
+    #[reserved(0)]
+    reserved: u3,
+    code: u8,
+    #[reserved(0b1010)]
+    reserved: u4,
+}
+
+#[bitsize(16)]
+#[derive(TryFromBits, DefaultBits, PartialEq, Clone, Copy)]
+#[reset_value(0x8001)]
+struct Control {
+    enable: bool,
+    mode: u14,
+    #[must_be(true)]
+    valid: bool,
+}
+
+#[bitsize(8, msb0)]
+#[derive(TryFromBits, PartialEq, Clone, Copy)]
+struct Msb0 {
+    #[must_be(0b10)]
+    marker: u2,
+    value: u6,
+}
+
+#[bitsize(136)]
+#[derive(TryFromBits, PartialEq)]
+struct Wide {
+    payload: u128,
+    #[must_be(0xA5)]
+    magic: u8,
+}
+
+#[bitsize(8)]
+#[derive(TryFromBits, DebugBits, DefaultBits, PartialEq, Clone, Copy)]
+struct ZeroWidth {
+    #[reserved(0)]
+    pad: u0,
+    value: u8,
+    #[must_be(0)]
+    end: u0,
+}
+
+#[test]
+fn new_writes_required_values() {
+    let packet = MousePacket::new(true, false, false, false, true, false, false, 3, 5);
+    assert_eq!(packet.always_one(), u1::new(1));
+    assert_eq!(u24::from(packet), u24::new(0x05_03_29));
+
+    let status = Status::new(true, 0x42);
+    assert_eq!(u16::from(status), 0b1010_01000010_000_1);
+    assert_eq!(Msb0::new(u6::new(1)).marker(), u2::new(0b10));
+    assert_eq!(u8::from(Msb0::new(u6::new(1))), 0b10_000001);
+}
+
+#[test]
+fn try_from_rejects_other_bits() {
+    assert!(MousePacket::try_from(u24::new(0x05_03_29)).is_ok());
+    assert!(MousePacket::try_from(u24::new(0x05_03_21)).is_err());
+
+    assert!(Status::try_from(0b1010_01000010_000_1).is_ok());
+    assert!(Status::try_from(0b1010_01000010_100_1).is_err());
+    assert!(Status::try_from(0b0010_01000010_000_1).is_err());
+
+    assert!(Msb0::try_from(0b10_111111).is_ok());
+    assert!(Msb0::try_from(0b11_111111).is_err());
+
+    let bytes = |magic: u8| {
+        let mut bytes = [0xFF; 17];
+        bytes[16] = magic;
+        bytes
+    };
+    let wide = Wide::try_from_le_bytes(bytes(0xA5)).unwrap();
+    assert_eq!(wide.payload(), u128::MAX);
+    assert_eq!(wide.magic(), 0xA5);
+    assert!(Wide::try_from_le_bytes(bytes(0xA4)).is_err());
+
+    assert_eq!(ZeroWidth::try_from(0xFF).unwrap().value(), 0xFF);
+    assert_eq!(Wide::new(7).to_le_bytes(), {
+        let mut bytes = [0; 17];
+        bytes[0] = 7;
+        bytes[16] = 0xA5;
+        bytes
+    });
+}
+
+#[test]
+fn default_uses_required_values() {
+    assert_eq!(u24::from(MousePacket::default()), u24::new(0b1000));
+    assert!(Control::default().valid());
+    assert!(Control::default().enable());
+    assert_eq!(ZeroWidth::default(), ZeroWidth::new(0));
+}
+
+#[test]
+fn reflection_has_no_setter() {
+    let mut packet = MousePacket::default();
+    assert!(packet.set_raw("always_one", 0).is_err());
+    assert!(packet.set_raw("x", 1).is_ok());
+    assert_eq!(packet.get_raw("always_one"), Ok(1));
+}
@@ -0,0 +1,59 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+
+#[bitsize(2)]
+#[derive(TryFromBits, Debug, PartialEq, Default)]
+enum State {
+    #[default]
+    Idle,
+    Busy,
+    Done,
+}
+
+#[bitsize(8, sealed)]
+#[derive(TryFromBits, DebugBits, BinaryBits, DefaultBits, PartialEq, Clone, Copy)]
+struct Status {
+    state: State,
+    count: u6,
+}
+
+#[test]
+fn sealed_accessors() {
+    let mut status = Status::new(State::Busy, u6::new(5));
+    assert_eq!(status.state(), State::Busy);
+    status.set_count(u6::new(63));
+    status.set_state(State::Done);
+    assert_eq!(status.raw(), 0b111111_10);
+    assert_eq!(u8::from(status), 0b111111_10);
+    assert_eq!(format!("{status:?}"), "Status { state: Done, count: 63 }");
+    assert_eq!(format!("{status:b}"), "111111_10");
+    assert_eq!(Status::default().raw(), 0);
+}
+
+#[test]
+fn sealed_conversions() {
+    assert_eq!(Status::try_from(0b000001_01).unwrap(), Status::new(State::Busy, u6::new(1)));
+    assert!(Status::try_from(0b11).is_err());
+    assert_eq!(Status::try_from_le_bytes([0b10]).unwrap().state(), State::Done);
+
+    // the escape hatch, which skips validation
+    let status = unsafe { Status::from_raw_unchecked(0b000010_00) };
+    assert_eq!(status.count(), u6::new(2));
+}
+
+#[bitsize(136, sealed)]
+#[derive(FromBits, DebugBits, DefaultBits, PartialEq)]
+struct WideSealed {
+    low: u128,
+    high: u8,
+}
+
+#[test]
+fn sealed_byte_array() {
+    let mut wide = WideSealed::new(1, 2);
+    wide.set_high(0xFF);
+    assert_eq!(wide.raw()[16], 0xFF);
+    assert_eq!(wide, WideSealed::from(wide.raw()));
+    assert_eq!(WideSealed::default().raw(), [0; 17]);
+}
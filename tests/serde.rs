@@ -1,7 +1,7 @@
 #![cfg(feature = "serde")]
 
 use bilge::prelude::*;
-use serde_test::{assert_de_tokens_error, assert_tokens, Token};
+use serde_test::{assert_de_tokens, assert_de_tokens_error, assert_ser_tokens, assert_tokens, Token};
 
 #[bitsize(17)]
 #[derive(FromBits, PartialEq, SerializeBits, DeserializeBits, DebugBits)]
@@ -91,3 +91,157 @@ fn serde_tuple_struct_map() {
         r#"invalid type: string "val_0", expected u8"#,
     );
 }
+
+#[bitsize(12)]
+#[derive(FromBits, PartialEq, SerializeBits, DeserializeBits, DebugBits)]
+struct BitsAccessStruct {
+    #[read_only]
+    status: u4,
+    #[write_only]
+    command: u4,
+    field: u4,
+}
+
+#[test]
+fn serde_read_only_write_only() {
+    let bits = BitsAccessStruct::from(u12::new(0x3_2_1));
+
+    // write-only fields have no getter, and read-only fields can't be passed to `new`,
+    // so both are skipped in both directions
+    assert_ser_tokens(
+        &bits,
+        &[
+            Token::Struct {
+                name: "BitsAccessStruct",
+                len: 1,
+            },
+            Token::Str("field"),
+            Token::U8(0x3),
+            Token::StructEnd,
+        ],
+    );
+
+    // so everything that's serialized is deserialized again
+    assert_tokens(
+        &BitsAccessStruct::new(u4::new(0), u4::new(0x3)),
+        &[
+            Token::Struct {
+                name: "BitsAccessStruct",
+                len: 1,
+            },
+            Token::Str("field"),
+            Token::U8(0x3),
+            Token::StructEnd,
+        ],
+    );
+
+    assert_de_tokens_error::<BitsAccessStruct>(
+        &[
+            Token::Struct {
+                name: "BitsAccessStruct",
+                len: 2,
+            },
+            Token::Str("status"),
+        ],
+        "unknown field `status`, expected `field`",
+    );
+}
+
+#[bitsize(8)]
+#[derive(FromBits, PartialEq, SerializeBits, DeserializeBits, DebugBits)]
+struct BitsGenericStruct<T> {
+    flag: bool,
+    payload: T,
+}
+
+#[test]
+fn serde_generic_struct() {
+    let bits = BitsGenericStruct::new(true, u7::new(0x12));
+
+    assert_tokens(
+        &bits,
+        &[
+            Token::Struct {
+                name: "BitsGenericStruct",
+                len: 2,
+            },
+            Token::Str("flag"),
+            Token::Bool(true),
+            Token::Str("payload"),
+            Token::U8(0x12),
+            Token::StructEnd,
+        ],
+    );
+}
+
+#[bitsize(16)]
+#[derive(FromBits, PartialEq, SerializeBits, DeserializeBits, DebugBits)]
+struct BitsSignedStruct {
+    field1: i4,
+    field2: i12,
+}
+
+#[test]
+fn serde_signed_struct() {
+    let bits = BitsSignedStruct::new(i4::new(-8), i12::new(-300));
+
+    assert_tokens(
+        &bits,
+        &[
+            Token::Struct {
+                name: "BitsSignedStruct",
+                len: 2,
+            },
+            Token::Str("field1"),
+            Token::I8(-8),
+            Token::Str("field2"),
+            Token::I16(-300),
+            Token::StructEnd,
+        ],
+    );
+
+    assert_de_tokens_error::<BitsSignedStruct>(
+        &[
+            Token::Struct {
+                name: "BitsSignedStruct",
+                len: 2,
+            },
+            Token::Str("field1"),
+            Token::I8(8),
+        ],
+        "invalid value: integer `8`, expected a value between `-8` and `7`",
+    );
+}
+
+#[bitsize(0)]
+#[derive(FromBits, PartialEq, SerializeBits, DeserializeBits, DebugBits)]
+struct Marker;
+
+#[bitsize(8)]
+#[derive(FromBits, PartialEq, SerializeBits, DeserializeBits, DebugBits)]
+struct Marked {
+    marker: Marker,
+    value: u8,
+    kind: core::marker::PhantomData<u32>,
+}
+
+#[test]
+fn serde_zero_width() {
+    assert_tokens(&Marker::new(), &[Token::Struct { name: "Marker", len: 0 }, Token::StructEnd]);
+
+    let bits = Marked::new(Marker::new(), 42, core::marker::PhantomData);
+    assert_tokens(
+        &bits,
+        &[
+            Token::Struct { name: "Marked", len: 3 },
+            Token::Str("marker"),
+            Token::Struct { name: "Marker", len: 0 },
+            Token::StructEnd,
+            Token::Str("value"),
+            Token::U8(42),
+            Token::Str("kind"),
+            Token::UnitStruct { name: "PhantomData" },
+            Token::StructEnd,
+        ],
+    );
+}
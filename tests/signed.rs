@@ -0,0 +1,111 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+
+/// An accelerometer sample, with two's-complement axes.
+#[bitsize(32)]
+#[derive(FromBits, DebugBits, DefaultBits, PartialEq, Clone, Copy)]
+struct Sample {
+    x: i12,
+    y: i12,
+    temperature: i8,
+}
+
+/// A branch instruction with a signed displacement.
+#[bitsize(16)]
+#[derive(TryFromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Branch {
+    opcode: Opcode,
+    displacement: (i5, i5),
+    condition: i4,
+}
+
+#[bitsize(2)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Opcode {
+    Jump,
+    Call,
+    Return,
+}
+
+#[bitsize(260)]
+#[derive(FromBits, DebugBits, DefaultBits, PartialEq)]
+struct Wide {
+    low: i100,
+    middle: i128,
+    high: [i16; 2],
+}
+
+#[test]
+fn int_type() {
+    assert_eq!(i12::MIN.value(), -2048);
+    assert_eq!(i12::MAX.value(), 2047);
+    assert_eq!(i1::MIN.value(), -1);
+    assert_eq!(i1::MAX.value(), 0);
+    assert_eq!(i127::MIN.value(), i128::MIN >> 1);
+    assert!(i4::try_new(8).is_err());
+    assert!(i4::try_new(-9).is_err());
+    assert_eq!(i4::try_new(-8).map(i4::value), Ok(-8));
+
+    // sign extension
+    assert_eq!(i4::from(u4::new(0b1110)), i4::new(-2));
+    assert_eq!(i4::from(u4::new(0b0111)), i4::new(7));
+    assert_eq!(u4::from(i4::new(-2)), u4::new(0b1110));
+    assert_eq!(u12::from(i12::new(-1)), u12::new(0xFFF));
+}
+
+#[test]
+fn getters_sign_extend() {
+    let sample = Sample::from(0x80_FFF_801);
+    assert_eq!(sample.x(), i12::new(-2047));
+    assert_eq!(sample.y(), i12::new(-1));
+    assert_eq!(sample.temperature(), i8::MIN);
+
+    let sample = Sample::from(0x7F_7FF_000);
+    assert_eq!(sample.x(), i12::new(0));
+    assert_eq!(sample.y(), i12::new(2047));
+    assert_eq!(sample.temperature(), 127);
+}
+
+#[test]
+fn setters_mask() {
+    let mut sample = Sample::new(i12::new(-1), i12::new(0), -1);
+    assert_eq!(u32::from(sample), 0xFF_000_FFF);
+
+    // negative values don't overwrite other fields
+    sample.set_y(i12::new(-2));
+    assert_eq!(u32::from(sample), 0xFF_FFE_FFF);
+    sample.set_x(i12::new(5));
+    sample.set_temperature(-128);
+    assert_eq!(u32::from(sample), 0x80_FFE_005);
+    assert_eq!(sample.y(), i12::new(-2));
+    assert_eq!(Sample::default(), Sample::from(0));
+}
+
+#[test]
+fn try_from_and_tuples() {
+    let branch = Branch::new(Opcode::Call, (i5::new(-16), i5::new(15)), i4::new(-3));
+    assert_eq!(u16::from(branch), 0b1101_01111_10000_01);
+    assert_eq!(Branch::try_from(u16::from(branch)), Ok(branch));
+    assert_eq!(branch.displacement(), (i5::new(-16), i5::new(15)));
+    assert!(Branch::try_from(0b1101_01111_10000_11).is_err());
+}
+
+#[test]
+fn byte_array() {
+    let mut wide = Wide::new(i100::new(-3), -5, [-1, 7]);
+    assert_eq!(wide.low(), i100::new(-3));
+    assert_eq!(wide.middle(), -5);
+    assert_eq!(wide.high(), [-1, 7]);
+    wide.set_middle(i128::MIN);
+    assert_eq!(wide.middle(), i128::MIN);
+    assert_eq!(wide.low(), i100::new(-3));
+    assert_eq!(Wide::default().high(), [0, 0]);
+}
+
+#[test]
+fn fmt() {
+    let sample = Sample::new(i12::new(-300), i12::new(2), -40);
+    assert_eq!(format!("{:?}", sample), "Sample { x: -300, y: 2, temperature: -40 }");
+    assert_eq!(format!("{}", i5::new(-7)), "-7");
+}
@@ -4,7 +4,7 @@ use bilge::prelude::*;
 #[bitsize(32)]
 #[derive(TryFromBits, PartialEq, DebugBits)]
 struct Wrapper {
-    foo: FillsU32,
+    value: FillsU32,
 }
 
 #[bitsize(32)]
@@ -16,6 +16,6 @@ enum FillsU32 {
 
 #[test]
 fn single_filled_enum_works_issue_36() {
-    let foo = Wrapper::try_from(0xDEADBEEF);
-    assert_eq!(foo, Ok(Wrapper::new(FillsU32::Foo)));
+    let wrapper = Wrapper::try_from(0xDEADBEEF);
+    assert_eq!(wrapper, Ok(Wrapper::new(FillsU32::Foo)));
 }
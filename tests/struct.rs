@@ -45,7 +45,7 @@ fn conversions() {
                 }
                 assert_eq!(u2::from(a), value);
             }
-            Err(e) => assert_eq!(format!("{e:?}"), "BitsError"),
+            Err(e) => assert_eq!(e.to_string(), "unable to parse bit pattern 0b11 of `inner` at bits 0..2"),
         }
     }
 }
@@ -397,8 +397,11 @@ fn that_one_test() {
     assert_eq!(uem1.value, uem2.value);
     assert_eq!(uem1, uem2);
     let raw = u18::new(0b1_0101_11___11____0_1010_1010);
-    let err = UnfilledEnumMess::try_from(raw);
-    assert!(err.is_err());
+    let err = UnfilledEnumMess::try_from(raw).unwrap_err();
+    // the enum of the second tuple has the invalid bits 0b11,
+    // and only the innermost `FieldPath::CAPACITY` segments of its path are kept
+    assert_eq!(err.path().to_string(), "..[1].0[0][0].0");
+    assert_eq!((err.offset(), err.width(), err.bits()), (9, 2, 0b11));
 
     // mess.arr_arr_ay_ay_at(2); //panics, like it should
 
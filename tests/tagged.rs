@@ -0,0 +1,145 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+
+#[bitsize(30)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct LoadFields {
+    register: u5,
+    base: u5,
+    immediate: u20,
+}
+
+#[bitsize(30)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct StoreFields {
+    base: u5,
+    register: u5,
+    immediate: u20,
+}
+
+#[bitsize(2)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Condition {
+    Always,
+    Zero,
+    NotZero,
+}
+
+#[bitsize(30)]
+#[derive(TryFromBits, DebugBits, PartialEq, Clone, Copy)]
+struct BranchFields {
+    condition: Condition,
+    target: u28,
+}
+
+/// The tag is in the two least significant bits.
+#[bitsize(32)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Insn {
+    #[tag(0b00)]
+    Load(LoadFields),
+    #[tag(0b01)]
+    Store(StoreFields),
+    #[tag(0b10)]
+    Branch(BranchFields),
+    #[tag(0b11)]
+    Halt,
+}
+
+/// The tag is in the two most significant bits.
+#[bitsize(32)]
+#[tag_bits(30..=31)]
+#[derive(FromBits, Debug, PartialEq, Clone, Copy)]
+enum Frame {
+    #[tag(0)]
+    Data(u30),
+    #[tag(1)]
+    Ack(u30),
+    #[tag(2)]
+    Nack(u30),
+    #[tag(3)]
+    Load(LoadFields),
+}
+
+/// The tag is in the middle, so the payload's upper bits are shifted.
+#[bitsize(16)]
+#[tag_bits(4..=7)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Packet {
+    #[tag(0x3)]
+    Small(u12),
+    #[tag(0xA)]
+    Signed(i12),
+}
+
+#[bitsize(9)]
+#[derive(FromBits, Debug, PartialEq, Clone, Copy)]
+enum Byte {
+    #[tag(0)]
+    Unsigned(u8),
+    #[tag(1)]
+    Signed(i8),
+}
+
+#[test]
+fn tag_in_low_bits() {
+    let load = Insn::Load(LoadFields::new(u5::new(1), u5::new(2), u20::new(0x12345)));
+    let raw = u32::from(load);
+    assert_eq!(raw, (0x12345 << 12) | (2 << 7) | (1 << 2));
+    assert_eq!(raw & 0b11, 0b00);
+    assert_eq!(Insn::try_from(raw), Ok(load));
+
+    let store = Insn::Store(StoreFields::from(u30::new(0x3FFF_FFFF)));
+    assert_eq!(u32::from(store), 0xFFFF_FFFD);
+    assert_eq!(Insn::try_from(0xFFFF_FFFD), Ok(store));
+
+    let branch = Insn::Branch(BranchFields::new(Condition::NotZero, u28::new(0x40)));
+    assert_eq!(u32::from(branch), (0x40 << 4) | (0b10 << 2) | 0b10);
+    assert_eq!(Insn::try_from(u32::from(branch)), Ok(branch));
+
+    assert_eq!(u32::from(Insn::Halt), 0b11);
+    assert_eq!(Insn::try_from(0b11), Ok(Insn::Halt));
+}
+
+#[test]
+fn invalid_values() {
+    // invalid condition in the branch payload
+    assert!(Insn::try_from((0b11 << 2) | 0b10).is_err());
+    // unit variants don't have any payload
+    assert!(Insn::try_from(0b111).is_err());
+    // unused tag
+    assert!(Packet::try_from(0x0050).is_err());
+}
+
+#[test]
+fn tag_in_high_bits() {
+    assert_eq!(Frame::from(0x0000_0042), Frame::Data(u30::new(0x42)));
+    assert_eq!(Frame::from(0x4000_0042), Frame::Ack(u30::new(0x42)));
+    assert_eq!(Frame::from(0xBFFF_FFFF), Frame::Nack(u30::new(0x3FFF_FFFF)));
+    let load = Frame::from(0xC000_0021);
+    assert_eq!(load, Frame::Load(LoadFields::new(u5::new(1), u5::new(1), u20::new(0))));
+    assert_eq!(u32::from(load), 0xC000_0021);
+    assert_eq!(u32::from(Frame::Nack(u30::new(7))), 0x8000_0007);
+}
+
+#[test]
+fn tag_in_the_middle() {
+    let small = Packet::try_from(0xAB_3_D).unwrap();
+    assert_eq!(small, Packet::Small(u12::new(0xAB_D)));
+    assert_eq!(u16::from(small), 0xAB_3_D);
+
+    let signed = Packet::Signed(i12::new(-1));
+    assert_eq!(u16::from(signed), 0xFF_A_F);
+    assert_eq!(Packet::try_from(0xFF_A_F), Ok(signed));
+}
+
+#[test]
+fn primitives() {
+    // the tag is the least significant bit
+    assert_eq!(Byte::from(u9::new(0x1FE)), Byte::Unsigned(255));
+    assert_eq!(Byte::from(u9::new(0x0FF)), Byte::Signed(127));
+    assert_eq!(Byte::from(u9::new(0x1FF)), Byte::Signed(-1));
+    assert_eq!(u9::from(Byte::Signed(-128)), u9::new(0x101));
+    assert_eq!(Byte::from_le_bytes([0x01, 0x01]), Byte::Signed(-128));
+}
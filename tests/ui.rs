@@ -8,7 +8,8 @@
 // `cargo +nightly test` will fail
 // `cargo +nightly-2022-11-03 test` should not fail
 #[allow(unused_attributes)]
-#[rustversion::attr(not(nightly), ignore)]
+// only one of these may apply, `ignore` can't be given twice
+#[rustversion::attr(not(nightly), cfg_attr(feature = "nightly", ignore))]
 #[cfg_attr(not(feature = "nightly"), ignore)]
 #[test]
 fn ui() {
@@ -0,0 +1,28 @@
+use bilge::prelude::*;
+
+#[bitsize(8)]
+#[derive(FromBits)]
+struct OnlyAliases {
+    #[alias]
+    field: u8,
+}
+
+#[bitsize(8)]
+#[derive(FromBits)]
+struct WriteOneAlias {
+    field: u8,
+    #[alias]
+    #[w1c]
+    flag: bool,
+}
+
+#[bitsize(8)]
+#[derive(FromBits, DefaultBits)]
+struct DefaultAlias {
+    field: u8,
+    #[alias]
+    #[default = u4::new(1)]
+    low: u4,
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use bilge::prelude::*;
+
+// unknown option
+#[bitsize(8, msb)]
+struct Test { field: u8 }
+
+// options need to be identifiers
+#[bitsize(8, "msb0")]
+struct Test { field: u8 }
+
+// enums are always stored as a single number
+#[bitsize(2, msb0)]
+enum Test { A, B, C, D }
+
+fn main() {}
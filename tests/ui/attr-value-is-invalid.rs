@@ -4,20 +4,20 @@ use bilge::prelude::*;
 #[bitsize(-1)]
 enum Test {}
 
-// one below lowest value
+// zero-width enum
 #[bitsize(0)]
 enum Test {}
 
 // one above highest (struct) value
-#[bitsize(129)]
+#[bitsize(4097)]
 struct Test {}
 
 // one above highest enum value
-#[bitsize(65)]
+#[bitsize(129)]
 enum Test {}
 
 // one above highest enum value, this compiles
-#[bitsize(65)]
-struct Test { field: u65 }
+#[bitsize(129)]
+struct Test { field: u128, flag: bool }
 
 fn main() {}
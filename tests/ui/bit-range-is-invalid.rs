@@ -0,0 +1,41 @@
+use bilge::prelude::*;
+
+// overlapping ranges
+#[bitsize(8)]
+struct Overlap {
+    #[bits(0..=3)]
+    low: u4,
+    #[bits(3..=6)]
+    high: u4,
+}
+
+// range doesn't match the field's size
+#[bitsize(8)]
+struct Mismatch {
+    #[bits(0..=2)]
+    low: u4,
+}
+
+// one field is missing its range
+#[bitsize(8)]
+struct Missing {
+    #[bits(0..=3)]
+    low: u4,
+    high: u4,
+}
+
+// range exceeds the bitsize
+#[bitsize(8)]
+struct Exceeding {
+    #[bits(4..=8)]
+    high: u5,
+}
+
+// empty range
+#[bitsize(8)]
+struct Empty {
+    #[bits(4..4)]
+    high: u4,
+}
+
+fn main() {}
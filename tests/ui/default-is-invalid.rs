@@ -0,0 +1,27 @@
+use bilge::prelude::*;
+
+#[bitsize(8)]
+#[derive(FromBits, DefaultBits)]
+struct TooWide {
+    #[default(0b1000)]
+    field: u3,
+    other: u5,
+}
+
+#[bitsize(8)]
+#[derive(FromBits, DefaultBits)]
+struct MissingValue {
+    #[default]
+    field: u8,
+}
+
+#[bitsize(8)]
+#[reset_value(0x12)]
+#[derive(FromBits, DefaultBits)]
+struct BothDefaults {
+    #[default = u4::new(2)]
+    field: u4,
+    other: u4,
+}
+
+fn main() {}
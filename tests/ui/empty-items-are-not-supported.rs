@@ -1,13 +1,14 @@
 use bilge::prelude::*;
 
+// structs without fields are supported, but they take up 0 bits
 #[bitsize(1)]
 struct A;
 #[bitsize(1)]
-struct A();
+struct B();
 #[bitsize(1)]
-struct A{}
+struct C {}
 
 #[bitsize(1)]
-enum B {}
+enum D {}
 
 fn main() {}
@@ -0,0 +1,34 @@
+use bilge::prelude::*;
+
+#[bitsize(2)]
+#[derive(TryFromBits)]
+enum Overlapping {
+    #[bits(0 | 1)]
+    Disabled,
+    Enabled = 1,
+}
+
+#[bitsize(2)]
+#[derive(TryFromBits)]
+enum WithDiscriminant {
+    #[bits(0 | 1)]
+    Disabled = 0,
+    Enabled = 2,
+}
+
+#[bitsize(3)]
+#[derive(TryFromBits)]
+enum EmptyRange {
+    #[bits(4..4)]
+    Reserved,
+}
+
+#[bitsize(2)]
+#[derive(FromBits)]
+enum NotFilled {
+    #[bits(1..=2)]
+    Enabled,
+    Disabled = 0,
+}
+
+fn main() {}
@@ -0,0 +1,44 @@
+use bilge::prelude::*;
+
+#[bitsize(8)]
+struct Both {
+    #[read_only]
+    #[write_only]
+    field: u8,
+}
+
+#[bitsize(8)]
+struct WithArgs {
+    #[read_only(true)]
+    field: u8,
+}
+
+#[bitsize(8)]
+struct NotAnInt {
+    #[w1c]
+    field: [bool; 8],
+}
+
+#[bitsize(136)]
+struct TooWide {
+    #[w1s]
+    field: u8,
+    rest: u128,
+}
+
+#[bitsize(2)]
+#[derive(TryFromBits)]
+enum Mode {
+    Read = 1,
+    Write,
+    Execute,
+}
+
+// `new` would write zero, which isn't a `Mode`
+#[bitsize(2)]
+struct ReadOnlyEnum {
+    #[read_only]
+    mode: Mode,
+}
+
+fn main() {}
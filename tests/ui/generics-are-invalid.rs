@@ -0,0 +1,22 @@
+use bilge::prelude::*;
+
+// bitfields can't borrow anything
+#[bitsize(8)]
+struct Borrowing<'a, T> {
+    value: T,
+    other: &'a u8,
+}
+
+// arrays are converted with `transmute`, which needs their size
+#[bitsize(8)]
+struct GenericArray<T> {
+    values: [T; 2],
+}
+
+#[bitsize(1)]
+enum GenericEnum<T> {
+    A,
+    B(T),
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use bilge::prelude::*;
+use registers::Control;
+
+fn main() {
+    let _ = Control::new(true, u3::new(1));
+    let _ = Control::new_preserving(Control::try_from(0).unwrap(), true, u3::new(1));
+}
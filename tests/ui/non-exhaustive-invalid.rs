@@ -20,4 +20,13 @@ struct C(u4);
 #[non_exhaustive]
 enum D { A, B, C }
 
+// only `FromBits` rules out `#[non_exhaustive]`
+#[bitsize(4)]
+#[derive(TryFromBits, DefaultBits, DebugBits, VisitBits)]
+#[non_exhaustive]
+struct E {
+    flag: bool,
+    reserved: u3,
+}
+
 fn main() {}
@@ -0,0 +1,36 @@
+use bilge::prelude::*;
+
+#[bitsize(8)]
+#[derive(FromBits)]
+struct NotTryFrom {
+    #[must_be(1)]
+    always_one: u1,
+    value: u7,
+}
+
+#[bitsize(8)]
+#[derive(TryFromBits)]
+struct TooBig {
+    #[reserved(0b100)]
+    reserved: u2,
+    value: u6,
+}
+
+#[bitsize(8)]
+#[derive(TryFromBits)]
+struct NotBits {
+    #[reserved(0)]
+    reserved: (u1, u1),
+    value: u6,
+}
+
+#[bitsize(8)]
+#[derive(TryFromBits)]
+struct WithAccess {
+    #[must_be(1)]
+    #[read_only]
+    always_one: u1,
+    value: u7,
+}
+
+fn main() {}
@@ -0,0 +1,82 @@
+use bilge::prelude::*;
+
+#[bitsize(2)]
+#[derive(TryFromBits, Clone, Copy)]
+enum Mode {
+    Off,
+    Slow,
+    Fast,
+}
+
+#[bitsize(8)]
+#[reset_value(0b11)]
+#[derive(TryFromBits, DefaultBits)]
+struct InvalidField {
+    mode: Mode,
+    reserved: u6,
+}
+
+#[bitsize(8)]
+#[reset_value(0b11_00_0000)]
+#[derive(TryFromBits, DefaultBits)]
+struct InvalidElement {
+    reserved: u4,
+    modes: [Mode; 2],
+}
+
+#[bitsize(4)]
+#[derive(TryFromBits)]
+enum Op {
+    #[tag(0)]
+    Nop,
+    #[tag(1)]
+    Load(u3),
+}
+
+#[bitsize(4)]
+#[reset_value(0b0010)]
+#[derive(TryFromBits, DefaultBits)]
+struct InvalidPayload {
+    op: Op,
+}
+
+#[bitsize(8)]
+#[reset_value(0x12)]
+#[derive(TryFromBits, DefaultBits)]
+struct InvalidRequiredValue {
+    #[reserved(0)]
+    reserved: u4,
+    value: u4,
+}
+
+// implements `Bitsized` by hand
+#[derive(Clone, Copy)]
+struct Manual(u4);
+
+impl Bitsized for Manual {
+    type ArbitraryInt = u4;
+    const BITS: usize = u4::BITS;
+    const MAX: Self::ArbitraryInt = <u4 as Bitsized>::MAX;
+}
+
+impl From<u4> for Manual {
+    fn from(value: u4) -> Self {
+        Manual(value)
+    }
+}
+
+impl From<Manual> for u4 {
+    fn from(value: Manual) -> Self {
+        value.0
+    }
+}
+
+#[bitsize(8)]
+#[reset_value(0)]
+#[derive(TryFromBits, DefaultBits)]
+struct UnknownField {
+    manual: Manual,
+    value: u4,
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use bilge::prelude::*;
+
+// `raw` is generated for every struct
+#[bitsize(8)]
+struct RawField {
+    raw: u8,
+}
+
+// enums can't be changed to an invalid value anyways
+#[bitsize(2, sealed)]
+enum Sealed {
+    A,
+    B,
+    C,
+    D,
+}
+
+fn main() {}
@@ -0,0 +1,37 @@
+use bilge::prelude::*;
+
+#[bitsize(8)]
+#[derive(TryFromBits)]
+enum MissingTag {
+    #[tag(0)]
+    First(u7),
+    Second(u7),
+}
+
+#[bitsize(8)]
+#[derive(TryFromBits)]
+enum DuplicateTag {
+    #[tag(1)]
+    First(u7),
+    #[tag(1)]
+    Second(u7),
+}
+
+#[bitsize(8)]
+#[tag_bits(6..=7)]
+#[derive(TryFromBits)]
+enum TagTooBig {
+    #[tag(4)]
+    First(u6),
+}
+
+#[bitsize(8)]
+#[derive(FromBits)]
+enum UnusedTag {
+    #[tag(0)]
+    First(u6),
+    #[tag(2)]
+    Second(u6),
+}
+
+fn main() {}
@@ -19,6 +19,15 @@ mod hidden {
         // the best use for padding
         pub padding: Diary,
     }
+
+    #[bitsize(8)]
+    #[derive(FromBits)]
+    struct Status(u8);
+
+    #[allow(private_interfaces)]
+    pub fn status() -> Status {
+        Status::from_le_bytes([42])
+    }
 }
 use hidden::*;
 
@@ -45,4 +54,7 @@ fn main() {
     r.padding_i();
     r.reserved_ii();
     r.padding_ii();
+
+    // byte conversions have the visibility of the struct
+    status().to_le_bytes();
 }
@@ -0,0 +1,17 @@
+use bilge::prelude::*;
+
+#[bitsize(200)]
+#[derive(FromBits)]
+struct Wide {
+    low: u128,
+    high: u72,
+}
+
+#[bitsize(208)]
+#[derive(FromBits)]
+struct Outer {
+    wide: Wide,
+    tail: u8,
+}
+
+fn main() {}
@@ -0,0 +1,144 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+use bilge::prelude::*;
+use bilge::BitsVisitor;
+
+#[bitsize(2)]
+#[derive(FromBits, VisitBits, Debug, PartialEq)]
+enum Mode {
+    Off,
+    Slow,
+    Fast,
+    Turbo,
+}
+
+#[bitsize(6)]
+#[derive(FromBits, VisitBits)]
+struct Clock {
+    mode: Mode,
+    divider: u4,
+}
+
+#[bitsize(32)]
+#[derive(FromBits, VisitBits)]
+struct Control {
+    enable: bool,
+    clock: Clock,
+    lanes: [u4; 2],
+    pair: (u2, i3),
+    #[write_only]
+    command: u3,
+    reserved: u9,
+}
+
+#[bitsize(8, msb0)]
+#[derive(FromBits, VisitBits)]
+struct Msb0 {
+    high: (u1, u3),
+    low: [u2; 2],
+}
+
+/// Records everything it visits, nested structs as `{name` and `}`.
+#[derive(Default)]
+struct Recorder {
+    visited: Vec<(String, usize, usize)>,
+}
+
+impl BitsVisitor for Recorder {
+    fn visit_field<T: Bitsized>(&mut self, name: &str, offset: usize, _value: T, _bits: u128) {
+        self.visited.push((name.to_string(), offset, T::BITS));
+    }
+
+    fn enter_struct(&mut self, name: &str, offset: usize) {
+        self.visited.push((format!("{{{name}"), offset, 0));
+    }
+
+    fn leave_struct(&mut self) {
+        self.visited.push(("}".to_string(), 0, 0));
+    }
+}
+
+fn visit(value: impl VisitBits) -> Vec<(String, usize, usize)> {
+    let mut recorder = Recorder::default();
+    value.visit_bits("root", 0, &mut recorder);
+    recorder.visited
+}
+
+#[test]
+fn visits_all_fields() {
+    let visited = visit(Control::from(0));
+    let expected = [
+        ("{root", 0, 0),
+        ("enable", 0, 1),
+        ("{clock", 1, 0),
+        ("mode", 1, 2),
+        ("divider", 3, 4),
+        ("}", 0, 0),
+        ("lanes", 7, 4),
+        ("lanes", 11, 4),
+        ("pair", 15, 2),
+        ("pair", 17, 3),
+        // `command` is write-only
+        ("reserved_i", 23, 9),
+        ("}", 0, 0),
+    ];
+    let expected: Vec<_> = expected.iter().map(|(name, offset, bits)| (name.to_string(), *offset, *bits)).collect();
+    assert_eq!(visited, expected);
+}
+
+/// Collects the bits of every field, e.g. for logging or diffing registers.
+#[derive(Default)]
+struct Values {
+    values: Vec<(String, u128)>,
+}
+
+impl BitsVisitor for Values {
+    fn visit_field<T: Bitsized>(&mut self, name: &str, _offset: usize, _value: T, bits: u128) {
+        self.values.push((name.to_string(), bits));
+    }
+}
+
+#[test]
+fn visits_field_values() {
+    let raw = 1 | 2 << 1 | 0b1010 << 3 | 0x3 << 7 | 0xC << 11 | 0b01 << 15 | 0b111 << 17 | 0b101 << 20;
+    let control = Control::from(raw);
+    let mut values = Values::default();
+    control.visit_bits("root", 0, &mut values);
+    let expected = [
+        ("enable", 1),
+        ("mode", 2),
+        ("divider", 0b1010),
+        ("lanes", 0x3),
+        ("lanes", 0xC),
+        ("pair", 0b01),
+        // `i3::new(-1)`
+        ("pair", 0b111),
+        ("reserved_i", 0),
+    ];
+    let expected: Vec<_> = expected.iter().map(|(name, bits)| (name.to_string(), *bits)).collect();
+    assert_eq!(values.values, expected);
+
+    let mut values = Values::default();
+    Mode::Turbo.visit_bits("mode", 0, &mut values);
+    assert_eq!(values.values, [("mode".to_string(), 3)]);
+}
+
+#[test]
+fn msb0() {
+    let visited = visit(Msb0::from(0));
+    let offsets: Vec<usize> = visited.iter().map(|(_, offset, _)| *offset).collect();
+    assert_eq!(offsets, [0, 7, 4, 2, 0, 0]);
+}
+
+#[bitsize(8)]
+#[derive(FromBits, VisitBits)]
+struct Generic<T> {
+    tag: T,
+    value: u6,
+}
+
+#[test]
+fn generics() {
+    let visited = visit(Generic::<Mode>::from(0));
+    assert_eq!(visited[1], ("tag".to_string(), 0, 2));
+    assert_eq!(visited[2], ("value".to_string(), 2, 6));
+}
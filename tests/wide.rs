@@ -0,0 +1,105 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+use bilge::prelude::*;
+
+/// Bitfields wider than 128 bits are stored as a little-endian byte array.
+#[bitsize(260)]
+#[derive(FromBits, DebugBits, DefaultBits, PartialEq)]
+struct Descriptor {
+    flag: bool,
+    address: u64,
+    length: u27,
+    lanes: [u12; 8],
+    tail: (u3, u33),
+    reserved: u35,
+    kind: Kind,
+}
+
+#[bitsize(1)]
+#[derive(FromBits, Debug, PartialEq, Default)]
+enum Kind {
+    #[default]
+    Read,
+    Write,
+}
+
+#[test]
+fn byte_array_storage() {
+    let lanes = [0xABC, 0x123, 0xFFF, 0, 1, 0x800, 0x7FF, 0x555].map(u12::new);
+    let tail = (u3::new(0b101), u33::new(0x1_2345_6789));
+    let desc = Descriptor::new(true, 0xDEAD_BEEF_CAFE_F00D, u27::new(0x7AB_CDEF), lanes, tail, Kind::Write);
+
+    assert!(desc.flag());
+    assert_eq!(desc.address(), 0xDEAD_BEEF_CAFE_F00D);
+    assert_eq!(desc.length(), u27::new(0x7AB_CDEF));
+    assert_eq!(desc.lanes(), lanes);
+    assert_eq!(desc.lanes_at(5), u12::new(0x800));
+    assert_eq!(desc.tail(), tail);
+    assert_eq!(desc.kind(), Kind::Write);
+
+    // bit 0 is the flag, the address starts at bit 1 and straddles all of the bytes 0 to 8
+    let bytes = <[u8; 33]>::from(desc);
+    assert_eq!(bytes[0], 0b0001_1011);
+    assert_eq!(bytes[8] & 1, 1);
+    // only the lowest 4 bits of the last byte are used, the last one of them is `kind`
+    assert_eq!(bytes[32], 0b1000);
+
+    let mut desc = Descriptor::from(bytes);
+    desc.set_address(1);
+    desc.set_lanes_at(2, u12::new(0x3C3));
+    desc.set_kind(Kind::Read);
+    assert!(desc.flag());
+    assert_eq!(desc.address(), 1);
+    assert_eq!(desc.length(), u27::new(0x7AB_CDEF));
+    assert_eq!(desc.lanes_at(1), u12::new(0x123));
+    assert_eq!(desc.lanes_at(2), u12::new(0x3C3));
+    assert_eq!(desc.lanes_at(3), u12::new(0));
+    assert_eq!(desc.tail(), tail);
+    assert_eq!(desc.kind(), Kind::Read);
+}
+
+#[test]
+fn byte_array_unused_bits_are_ignored() {
+    let desc = Descriptor::from([0xFF; 33]);
+    assert_eq!(<[u8; 33]>::from(desc)[32], 0b1111);
+    assert_eq!(Descriptor::MAX[32], 0b1111);
+    assert_eq!(Descriptor::BITS, 260);
+}
+
+#[test]
+fn byte_array_default_and_debug() {
+    let desc = Descriptor::default();
+    assert_eq!(desc, Descriptor::from([0; 33]));
+    assert_eq!(
+        format!("{desc:?}"),
+        "Descriptor { flag: false, address: 0, length: 0, lanes: [0, 0, 0, 0, 0, 0, 0, 0], tail: (0, 0), reserved_i: 0, kind: Read }"
+    );
+}
+
+#[bitsize(136)]
+#[derive(TryFromBits, DebugBits, BinaryBits)]
+struct Unfilled {
+    padding: u7,
+    state: State,
+    value: u128,
+}
+
+#[bitsize(1)]
+#[derive(TryFromBits, Debug, PartialEq)]
+enum State {
+    Ready,
+}
+
+#[test]
+fn byte_array_try_from() {
+    let mut bytes = [0; 17];
+    bytes[16] = 0xFF;
+    let valid = Unfilled::try_from(bytes).unwrap();
+    assert_eq!(valid.state(), State::Ready);
+    assert_eq!(valid.value(), 0xFF << 120);
+
+    bytes[0] = 0b1000_0000;
+    assert!(Unfilled::try_from(bytes).is_err());
+
+    let binary = format!("{valid:b}");
+    assert_eq!(binary, format!("1111111100000000{}_0_0000000", "0".repeat(112)));
+}
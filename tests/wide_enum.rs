@@ -0,0 +1,93 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+use bilge::prelude::*;
+
+const CAPABILITY_PM: u128 = 0x4D50_0000_0000_0000_0000_0001;
+
+/// GUID-like capability IDs
+#[bitsize(96)]
+#[derive(FromBits, BinaryBits, Debug, PartialEq, Clone, Copy)]
+enum Capability {
+    PowerManagement = CAPABILITY_PM,
+    Msi = 0x4D53_4900_0000_0000_0000_0002,
+    MsiX,
+    #[fallback]
+    Other(u96),
+}
+
+#[bitsize(128)]
+#[derive(FromBits, Debug, PartialEq, Clone, Copy)]
+enum Magic {
+    Zero,
+    Max = 0xFFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF,
+    #[fallback]
+    Other(u128),
+}
+
+#[bitsize(128)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Sparse {
+    First = 1u128 << 100,
+    Second,
+}
+
+/// Unsuffixed discriminants are evaluated as `u128`, not `i32`.
+#[bitsize(128)]
+#[derive(TryFromBits, Debug, PartialEq, Clone, Copy)]
+enum Untyped {
+    Low = 1 << 40,
+    High = 1 << 100,
+    Next,
+}
+
+/// The tag is in the most significant bits, so there are no payload bits above it.
+#[bitsize(128)]
+#[tag_bits(124..=127)]
+#[derive(TryFromBits, Debug, PartialEq)]
+enum Descriptor {
+    #[tag(1)]
+    Read(u124),
+    #[tag(2)]
+    Write(u124),
+}
+
+#[bitsize(128)]
+#[derive(FromBits, DebugBits, PartialEq)]
+struct Header {
+    capability: Capability,
+    version: u32,
+}
+
+#[test]
+fn conversions() {
+    let msi_x = u96::new(0x4D53_4900_0000_0000_0000_0003);
+    assert_eq!(Capability::from(msi_x), Capability::MsiX);
+    assert_eq!(u96::from(Capability::MsiX), msi_x);
+    assert_eq!(Capability::from(u96::new(CAPABILITY_PM)), Capability::PowerManagement);
+    assert_eq!(Capability::from(u96::new(7)), Capability::Other(u96::new(7)));
+    assert_eq!(format!("{:b}", Capability::Msi), format!("{:096b}", 0x4D53_4900_0000_0000_0000_0002_u128));
+
+    assert_eq!(Magic::from(u128::MAX), Magic::Max);
+    assert_eq!(Magic::from(0), Magic::Zero);
+    assert_eq!(Magic::from(1), Magic::Other(1));
+    assert_eq!(u128::from(Magic::Max), u128::MAX);
+
+    assert_eq!(Sparse::try_from(1 << 100), Ok(Sparse::First));
+    assert_eq!(Sparse::try_from((1 << 100) + 1), Ok(Sparse::Second));
+    assert!(Sparse::try_from(0).is_err());
+    assert_eq!(u128::from(Sparse::Second), (1 << 100) + 1);
+
+    assert_eq!(Untyped::try_from(1 << 40), Ok(Untyped::Low));
+    assert_eq!(Untyped::try_from((1 << 100) + 1), Ok(Untyped::Next));
+    assert_eq!(u128::from(Untyped::High), 1 << 100);
+
+    let header = Header::new(Capability::Msi, 3);
+    assert_eq!(header.capability(), Capability::Msi);
+    assert_eq!(header.version(), 3);
+}
+
+#[test]
+fn tagged() {
+    assert_eq!(Descriptor::try_from((2 << 124) | 5), Ok(Descriptor::Write(u124::new(5))));
+    assert_eq!(u128::from(Descriptor::Read(u124::new(7))), (1 << 124) | 7);
+    assert!(Descriptor::try_from(3 << 124).is_err());
+}
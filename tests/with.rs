@@ -0,0 +1,67 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+
+#[bitsize(32)]
+#[derive(FromBits, DebugBits, DefaultBits, PartialEq, Clone, Copy)]
+struct Config {
+    enable: bool,
+    mode: u3,
+    reserved: u4,
+    lanes: [u4; 4],
+    pair: (u2, bool, bool),
+    #[read_only]
+    status: u4,
+}
+
+#[bitsize(8)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Tuple(bool, u7);
+
+#[bitsize(160)]
+#[derive(FromBits, DebugBits, DefaultBits, PartialEq)]
+struct Wide {
+    address: u128,
+    sizes: [u8; 4],
+}
+
+#[test]
+fn chaining() {
+    let config = Config::default()
+        .with_enable(true)
+        .with_mode(u3::new(5))
+        .with_lanes([u4::new(1), u4::new(2), u4::new(3), u4::new(4)])
+        .with_lanes_at(2, u4::new(0xF))
+        .with_pair((u2::new(3), false, true));
+    assert_eq!(u32::from(config), 0x0_B_4F21_0_B);
+
+    let mut expected = Config::default();
+    expected.set_enable(true);
+    expected.set_mode(u3::new(5));
+    expected.set_lanes([u4::new(1), u4::new(2), u4::new(0xF), u4::new(4)]);
+    expected.set_pair((u2::new(3), false, true));
+    assert_eq!(config, expected);
+}
+
+#[test]
+fn chaining_tuple_structs() {
+    let tuple = Tuple::from(0).with_val_0(true).with_val_1(u7::new(0x12));
+    assert_eq!(u8::from(tuple), 0x25);
+}
+
+#[test]
+fn chaining_byte_arrays() {
+    let wide = Wide::default().with_address(u128::MAX).with_sizes_at(3, 0xAB);
+    assert_eq!(wide.address(), u128::MAX);
+    assert_eq!(wide.sizes(), [0, 0, 0, 0xAB]);
+}
+
+#[cfg(feature = "nightly")]
+#[test]
+fn chaining_in_const() {
+    const CONFIG: Config = Config::new(false, u3::new(0), [u4::new(0); 4], (u2::new(0), false, false))
+        .with_enable(true)
+        .with_lanes_at(0, u4::new(7));
+    assert!(CONFIG.enable());
+    assert_eq!(CONFIG.lanes_at(0), u4::new(7));
+}
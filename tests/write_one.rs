@@ -0,0 +1,58 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+
+/// An interrupt register: pending interrupts are cleared and software interrupts are raised by writing 1.
+#[bitsize(16)]
+#[derive(FromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Interrupts {
+    enable: bool,
+    #[w1c]
+    rx_done: bool,
+    #[w1c]
+    tx_done: bool,
+    /// raises a software interrupt
+    #[w1s]
+    software: bool,
+    #[w1c]
+    errors: u4,
+    priority: u8,
+}
+
+#[test]
+fn setters_keep_pending_bits() {
+    // everything pending
+    let mut interrupts = Interrupts::from(0x00_F_F);
+    assert!(interrupts.rx_done());
+    assert_eq!(interrupts.errors(), u4::new(0xF));
+
+    // writing this back doesn't clear or set anything
+    interrupts.set_priority(3);
+    assert_eq!(u16::from(interrupts), 0x03_0_1);
+    assert!(!interrupts.rx_done());
+    assert!(interrupts.enable());
+}
+
+#[test]
+fn clear_and_set() {
+    let mut interrupts = Interrupts::from(0x00_F_F);
+    interrupts.clear_tx_done();
+    assert_eq!(u16::from(interrupts), 0x00_0_5);
+
+    interrupts.set_software();
+    assert_eq!(u16::from(interrupts), 0x00_0_9);
+
+    let mut interrupts = Interrupts::from(0x00_F_F);
+    interrupts.clear_errors();
+    assert_eq!(u16::from(interrupts), 0x00_F_1);
+}
+
+#[test]
+fn write_value() {
+    let interrupts = Interrupts::from(0x05_F_F);
+    assert_eq!(u16::from(interrupts.write_value()), 0x05_0_1);
+
+    // W1C and W1S fields are not arguments of `new`
+    let interrupts = Interrupts::new(true, 0x05);
+    assert_eq!(u16::from(interrupts), 0x05_0_1);
+}
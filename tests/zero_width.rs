@@ -0,0 +1,119 @@
+#![cfg_attr(feature = "nightly", feature(const_convert, const_trait_impl, const_mut_refs))]
+#![allow(clippy::unusual_byte_groupings)]
+use bilge::prelude::*;
+use core::marker::PhantomData;
+
+#[bitsize(0)]
+#[derive(FromBits, DebugBits, DefaultBits, PartialEq, Clone, Copy)]
+struct Marker;
+
+#[bitsize(0)]
+#[derive(TryFromBits, DebugBits, PartialEq, Clone, Copy)]
+struct Empty {}
+
+#[bitsize(0)]
+#[derive(FromBits, DebugBits, PartialEq)]
+struct EmptyTuple();
+
+#[bitsize(8)]
+#[derive(FromBits, DebugBits, DefaultBits, PartialEq, Clone, Copy)]
+struct Tagged<T> {
+    marker: Marker,
+    kind: PhantomData<T>,
+    value: u4,
+    nothing: (),
+    flags: u4,
+    end: Marker,
+}
+
+#[bitsize(8)]
+#[derive(TryFromBits, DebugBits, PartialEq)]
+struct Nested {
+    empty: Empty,
+    value: u8,
+    markers: [Marker; 4],
+    empties: ((), Empty),
+}
+
+#[bitsize(8, msb0)]
+#[derive(FromBits, DebugBits, PartialEq)]
+struct Msb0 {
+    start: (),
+    high: u4,
+    middle: PhantomData<u8>,
+    low: u4,
+    end: (),
+}
+
+#[bitsize(136)]
+#[derive(FromBits, DebugBits)]
+struct Wide {
+    payload: u128,
+    marker: Marker,
+    tail: u8,
+    end: PhantomData<bool>,
+}
+
+#[test]
+fn zero_width_structs() {
+    assert_eq!(<Marker as Bitsized>::BITS, 0);
+    assert_eq!(<Empty as Bitsized>::BITS, 0);
+    assert_eq!(Marker::from(u0::new(0)), Marker::new());
+    assert_eq!(u0::from(Marker::new()), u0::MIN);
+    assert_eq!(Empty::try_from(u0::new(0)), Ok(Empty::new()));
+    assert_eq!(EmptyTuple::from(u0::new(0)), EmptyTuple::new());
+    assert_eq!(Marker::default(), Marker::new());
+    assert_eq!(format!("{:?}", Marker::new()), "Marker");
+    assert_eq!(format!("{:?}", Empty::new()), "Empty");
+    assert_eq!(format!("{:?}", EmptyTuple::new()), "EmptyTuple");
+}
+
+#[test]
+fn zero_width_fields() {
+    let mut tagged = Tagged::<u32>::new(Marker::new(), PhantomData, u4::new(0b1010), (), u4::new(0b0101), Marker::new());
+    assert_eq!(tagged.value, 0b0101_1010);
+    assert_eq!(tagged.value(), u4::new(0b1010));
+    assert_eq!(tagged.flags(), u4::new(0b0101));
+    assert_eq!(tagged.end(), Marker::new());
+
+    // setting a zero-width field at the very end doesn't touch the other bits
+    tagged.set_end(Marker::new());
+    tagged.set_nothing(());
+    tagged.set_kind(PhantomData);
+    assert_eq!(tagged.value, 0b0101_1010);
+    assert_eq!(Tagged::<u32>::default().value, 0);
+
+    assert_eq!(
+        format!("{:?}", tagged),
+        "Tagged { marker: Marker, kind: PhantomData<u32>, value: 10, nothing: (), flags: 5, end: Marker }"
+    );
+}
+
+#[test]
+fn nested() {
+    let nested = Nested::try_from(0b1100_0011).unwrap();
+    assert_eq!(nested.value(), 0b1100_0011);
+    assert_eq!(nested.markers(), [Marker::new(); 4]);
+    assert_eq!(nested.markers_at(3), Marker::new());
+    assert_eq!(nested.empties(), ((), Empty::new()));
+    assert_eq!(Nested::new(Empty::new(), 7, [Marker::new(); 4], ((), Empty::new())).value, 7);
+}
+
+#[test]
+fn bit_orders_and_storage() {
+    let mut msb0 = Msb0::from(0b1001_0110);
+    assert_eq!(msb0.high(), u4::new(0b1001));
+    assert_eq!(msb0.low(), u4::new(0b0110));
+    msb0.set_end(());
+    msb0.set_start(());
+    assert_eq!(msb0.value, 0b1001_0110);
+
+    let mut bytes = [0; 17];
+    bytes[16] = 0xab;
+    let mut wide = Wide::from_le_bytes(bytes);
+    assert_eq!(wide.tail(), 0xab);
+    wide.set_marker(Marker::new());
+    wide.set_end(PhantomData);
+    assert_eq!(wide.tail(), 0xab);
+    assert_eq!(wide.payload(), 0);
+}